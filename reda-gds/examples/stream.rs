@@ -0,0 +1,27 @@
+use reda_gds::{GdsEvent, GdsReader};
+
+fn main_result() -> Result<(), Box<dyn std::error::Error>> {
+    let mut reader = GdsReader::open("./data/sram/sram_1rw0r0w_8_256_freepdk45.gds")?;
+
+    let mut elements = 0;
+    for event in reader.events() {
+        match event? {
+            GdsEvent::Library(lib) => eprintln!("library: {}", lib.name),
+            GdsEvent::BeginStructure(s) => {
+                eprint!("{}: ", s.name);
+                elements = 0;
+            }
+            GdsEvent::Element(_) => elements += 1,
+            GdsEvent::EndStructure => eprintln!("{} elements", elements),
+            GdsEvent::EndLibrary => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn main() {
+    if let Err(e) = main_result() {
+        eprintln!("{}", e);
+    }
+}
@@ -0,0 +1,33 @@
+use std::path::PathBuf;
+use reda_gds::{GdsLibrary, OasisWriter};
+use clap::Parser;
+
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    /// Input GDS file path
+    input_path: PathBuf,
+
+    /// Output OASIS file path
+    output_path: PathBuf,
+
+    /// Compress the content of every cell
+    #[arg(long)]
+    compress: bool,
+}
+
+fn main_result() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    let library = GdsLibrary::read_gds(cli.input_path)?;
+    let mut writer = OasisWriter::open(cli.output_path)?.with_cblocks(cli.compress);
+    writer.write(&library)?;
+    Ok(())
+}
+
+fn main() {
+    if let Err(e) = main_result() {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use plotters::style::RGBColor;
+use reda_gds::{GdsFill, GdsLayerKey, GdsLayerStyle, GdsLibrary, GdsRendererBuilder};
+use clap::Parser;
+
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    /// GDS file path
+    gds_path: PathBuf,
+
+    /// Cell to draw, by default the first top cell
+    #[arg(long)]
+    cell: Option<String>,
+
+    /// Output file, drawn as PNG when it ends with `.png` and as SVG otherwise
+    #[arg(short, long)]
+    output: PathBuf,
+
+    /// Number of reference levels to expand, all of them by default
+    #[arg(long)]
+    depth: Option<usize>,
+
+    #[arg(long, default_value_t = 1024)]
+    width: u32,
+
+    #[arg(long, default_value_t = 1024)]
+    height: u32,
+
+    /// Layer to hide, as `layer/datatype`, may be repeated
+    #[arg(long)]
+    hide: Vec<GdsLayerKey>,
+
+    /// Layer style, as `layer/datatype=rrggbb[:solid|translucent|hatched|outline]`, may be repeated
+    #[arg(long, value_parser = parse_style)]
+    style: Vec<(GdsLayerKey, GdsLayerStyle)>,
+
+    /// Do not draw texts and instance names
+    #[arg(long)]
+    no_texts: bool,
+}
+
+fn parse_style(s: &str) -> Result<(GdsLayerKey, GdsLayerStyle), String> {
+    let (key, style) = s.split_once('=').ok_or("expected 'layer/datatype=rrggbb[:fill]'")?;
+    let key = key.parse::<GdsLayerKey>().map_err(|e| e.to_string())?;
+    let (color, fill) = style.split_once(':').unwrap_or((style, "translucent"));
+
+    let color = u32::from_str_radix(color.trim_start_matches('#'), 16).map_err(|e| e.to_string())?;
+    let color = RGBColor((color >> 16) as u8, (color >> 8) as u8, color as u8);
+    let fill = match fill {
+        "solid" => GdsFill::Solid,
+        "translucent" => GdsFill::Translucent,
+        "hatched" => GdsFill::Hatched,
+        "outline" => GdsFill::Outline,
+        _ => return Err(format!("unknown fill '{}'", fill)),
+    };
+    Ok((key, GdsLayerStyle::new(color, fill)))
+}
+
+fn main_result() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    let library = GdsLibrary::read_gds(&cli.gds_path)?;
+
+    let cell = match cli.cell {
+        Some(cell) => cell,
+        None => library.hierarchy().top_cells().first()
+            .ok_or("the library has no structure")?
+            .to_string(),
+    };
+
+    let mut styles: BTreeMap<GdsLayerKey, GdsLayerStyle> = cli.style.into_iter().collect();
+    for key in cli.hide {
+        styles.insert(key, GdsLayerStyle::hidden());
+    }
+
+    let mut builder = GdsRendererBuilder::default();
+    builder
+        .width(cli.width)
+        .height(cli.height)
+        .styles(styles)
+        .draw_texts(!cli.no_texts);
+    if let Some(depth) = cli.depth {
+        builder.depth(depth);
+    }
+    let renderer = builder.build()?;
+
+    let png = cli.output.extension().is_some_and(|e| e.eq_ignore_ascii_case("png"));
+    match png {
+        true => renderer.render_png(&library, &cell, &cli.output)?,
+        false => renderer.render_svg(&library, &cell, &cli.output)?,
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(e) = main_result() {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
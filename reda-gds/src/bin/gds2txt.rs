@@ -1,5 +1,5 @@
-use std::path::PathBuf;
-use reda_gds::GdsLibrary;
+use std::path::{Path, PathBuf};
+use reda_gds::{GdsLayerMap, GdsReadError, GdsReader, TextWriter};
 use clap::Parser;
 
 
@@ -11,17 +11,65 @@ struct Cli {
 
     /// Output text file path
     output_path: PathBuf,
+
+    /// Skip unknown or unexpected records, listing them on stderr
+    #[arg(long)]
+    tolerant: bool,
+
+    /// Name layers from a KLayout .lyp file, a Cadence layermap or a `name layer datatype` table
+    #[arg(long)]
+    layer_map: Option<PathBuf>,
 }
 
-fn main_result() -> Result<(), Box<dyn std::error::Error>> {
-    let cli = Cli::parse();
-    let library = GdsLibrary::read_gds(cli.input_path)?;
-    library.write_text(cli.output_path)?;
+fn main_result(cli: &Cli) -> Result<(), Box<dyn std::error::Error>> {
+    let mut reader = GdsReader::open(&cli.input_path)?.with_tolerance(cli.tolerant);
+    let library = reader.read()?;
+    for warning in reader.warnings() {
+        eprintln!("warning: {}", warning);
+    }
+    let mut writer = TextWriter::open(&cli.output_path)?;
+    if let Some(path) = &cli.layer_map {
+        writer = writer.with_layer_map(GdsLayerMap::read(path)?);
+    }
+    writer.write(&library)?;
     Ok(())
 }
 
+/// Print the bytes around the record a read failed in, its header in brackets
+fn dump_failure(path: &Path, error: &GdsReadError) {
+    const WIDTH: usize = 16;
+    const LINES: usize = 4;
+
+    let Some(location) = error.location() else { return };
+    let Ok(bytes) = std::fs::read(path) else { return };
+    let offset = location.offset as usize;
+    let first = (offset / WIDTH).saturating_sub(LINES) * WIDTH;
+    let last = ((offset / WIDTH + LINES + 1) * WIDTH).min(bytes.len());
+
+    for start in (first..last).step_by(WIDTH) {
+        let line = &bytes[start..(start + WIDTH).min(last)];
+        let marker = if (start..start + WIDTH).contains(&offset) { ">" } else { " " };
+        let hex: Vec<String> = line.iter().enumerate()
+            .map(|(i, b)| match start + i == offset {
+                true => format!("[{:02x}", b),
+                false if start + i == offset + 4 => format!("]{:02x}", b),
+                false => format!(" {:02x}", b),
+            })
+            .collect();
+        let ascii: String = line.iter()
+            .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+            .collect();
+        eprintln!("{}{:08x} {:<48}  |{}|", marker, start, hex.concat(), ascii);
+    }
+}
+
 fn main() {
-    if let Err(e) = main_result() {
+    let cli = Cli::parse();
+    if let Err(e) = main_result(&cli) {
         eprintln!("{}", e);
+        if let Some(e) = e.downcast_ref::<GdsReadError>() {
+            dump_failure(&cli.input_path, e);
+        }
+        std::process::exit(1);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,118 @@
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use reda_gds::{GdsElement, GdsLibrary};
+use clap::Parser;
+
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    /// First GDS file path
+    first_path: PathBuf,
+
+    /// Second GDS file path
+    second_path: PathBuf,
+
+    /// Compare the flattened geometry layer by layer instead of the elements of each structure
+    #[arg(long)]
+    geometric: bool,
+
+    /// Cell to compare in geometric mode, by default the top cells of the first file
+    #[arg(long)]
+    cell: Option<String>,
+
+    /// Name of the compared cell in the second file, when it differs from `--cell`
+    #[arg(long)]
+    other_cell: Option<String>,
+
+    /// Write the geometric differences to this GDS file
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+fn describe(element: &GdsElement) -> String {
+    let at = |xy: &[reda_gds::GdsDbCoord]| match xy.first() {
+        Some(p) => format!("at ({}, {})", p.x, p.y),
+        None => "without points".to_string(),
+    };
+    match element {
+        GdsElement::Boundary(e) => format!("boundary {} {} with {} points", e.layer_key(), at(&e.xy), e.xy.len()),
+        GdsElement::Path(e) => format!("path {} {} with {} points", e.layer_key(), at(&e.xy), e.xy.len()),
+        GdsElement::Sref(e) => format!("sref {} {}", e.s_name, at(&e.xy)),
+        GdsElement::Aref(e) => format!("aref {} {}x{} {}", e.s_name, e.col, e.row, at(&e.xy)),
+        GdsElement::Text(e) => format!("text {} '{}' {}", e.layer_key(), e.string, at(&e.xy)),
+        GdsElement::Node(e) => format!("node {} {}", e.layer_key(), at(&e.xy)),
+        GdsElement::Box(e) => format!("box {} {}", e.layer_key(), at(&e.xy)),
+    }
+}
+
+fn structural(first: &GdsLibrary, second: &GdsLibrary) -> bool {
+    let diff = first.diff(second);
+    if diff.units_differ {
+        println!("units: {} / {} -> {} / {}",
+            first.usrunits_per_dbunit, first.meters_per_dbunit, second.usrunits_per_dbunit, second.meters_per_dbunit);
+    }
+    for name in &diff.removed_structures {
+        println!("- structure {}", name);
+    }
+    for name in &diff.added_structures {
+        println!("+ structure {}", name);
+    }
+    for structure in &diff.changed_structures {
+        println!("~ structure {}", structure.name);
+        for element in &structure.removed {
+            println!("  - {}", describe(element));
+        }
+        for element in &structure.added {
+            println!("  + {}", describe(element));
+        }
+    }
+    !diff.is_empty()
+}
+
+fn geometric(cli: &Cli, first: &GdsLibrary, second: &GdsLibrary) -> Result<bool, Box<dyn std::error::Error>> {
+    let cells = match &cli.cell {
+        Some(cell) => vec![cell.clone()],
+        None => first.hierarchy().top_cells().into_iter().map(str::to_string).collect(),
+    };
+
+    let mut output = first.clone();
+    output.structures.clear();
+    let mut differ = false;
+    for cell in cells {
+        let other_cell = cli.other_cell.as_ref().unwrap_or(&cell);
+        let diff = first.geometric_diff(&cell, second, other_cell)?;
+        for (key, stats) in diff.layer_report(first.usrunits_per_dbunit) {
+            println!("{} {}: {:.6}", cell, key, stats.area);
+        }
+        differ |= !diff.boundarys.is_empty();
+        output.structures.insert(cell, Arc::new(RwLock::new(diff)));
+    }
+
+    if let Some(path) = &cli.output {
+        output.write_gds(path)?;
+    }
+    Ok(differ)
+}
+
+fn main_result() -> Result<bool, Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    let first = GdsLibrary::read_gds(&cli.first_path)?;
+    let second = GdsLibrary::read_gds(&cli.second_path)?;
+
+    match cli.geometric {
+        true => geometric(&cli, &first, &second),
+        false => Ok(structural(&first, &second)),
+    }
+}
+
+fn main() {
+    match main_result() {
+        Ok(false) => {}
+        Ok(true) => std::process::exit(1),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use std::path::PathBuf;
+use reda_gds::{GdsLayerKey, GdsLayerMap, GdsLayerRemap, GdsLibrary};
+use clap::Parser;
+
+
+/// Move, copy or delete shapes by layer, e.g. `--move 31/0=5/0 --delete 63`.
+/// Layers are given as `layer/datatype`, a name of the layer map, or a bare `layer` for
+/// all of its datatypes. A bare target layer keeps the datatype of the shapes moved
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    /// Input GDS file path
+    input_path: PathBuf,
+
+    /// Output GDS file path
+    output_path: PathBuf,
+
+    /// Put the shapes of a layer on another one, as `FROM=TO`
+    #[arg(long = "move", value_name = "FROM=TO")]
+    moves: Vec<String>,
+
+    /// Add a copy of the shapes of a layer on another one, as `FROM=TO`
+    #[arg(long = "copy", value_name = "FROM=TO")]
+    copies: Vec<String>,
+
+    /// Remove the shapes of a layer
+    #[arg(long = "delete", value_name = "LAYER")]
+    deletes: Vec<String>,
+
+    /// Remove the shapes of every layer neither kept nor moved or copied
+    #[arg(long = "keep", value_name = "LAYER")]
+    keeps: Vec<String>,
+
+    /// Name layers from a KLayout .lyp file, a Cadence layermap or a `name layer datatype` table
+    #[arg(long)]
+    layer_map: Option<PathBuf>,
+}
+
+fn resolve(layer_map: &GdsLayerMap, spec: &str) -> Result<GdsLayerKey, String> {
+    layer_map.resolve(spec).ok_or_else(|| format!("Unknown layer '{}'", spec))
+}
+
+/// Pairs a layer stands for, among the `present` ones for a bare layer number
+fn resolve_all(layer_map: &GdsLayerMap, present: &[GdsLayerKey], spec: &str) -> Result<Vec<GdsLayerKey>, String> {
+    match spec.parse::<i16>() {
+        Ok(layer) => Ok(present.iter().filter(|key| key.layer == layer).copied().collect()),
+        Err(_) => Ok(vec![resolve(layer_map, spec)?]),
+    }
+}
+
+fn resolve_pairs(layer_map: &GdsLayerMap, present: &[GdsLayerKey], spec: &str) -> Result<Vec<(GdsLayerKey, GdsLayerKey)>, String> {
+    let (from, to) = spec.split_once('=').ok_or_else(|| format!("Expected FROM=TO, got '{}'", spec))?;
+    let target = |key: GdsLayerKey| match to.parse::<i16>() {
+        Ok(layer) => Ok(GdsLayerKey::new(layer, key.data_type)),
+        Err(_) => resolve(layer_map, to),
+    };
+    resolve_all(layer_map, present, from)?.into_iter()
+        .map(|key| Ok((key, target(key)?)))
+        .collect()
+}
+
+fn main_result() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    let mut library = GdsLibrary::read_gds(&cli.input_path)?;
+    let layer_map = match &cli.layer_map {
+        Some(path) => GdsLayerMap::read(path)?,
+        None => GdsLayerMap::new(),
+    };
+
+    let present: Vec<GdsLayerKey> = library.layer_report().keys().copied().collect();
+    let mut remap = GdsLayerRemap::new();
+    for spec in &cli.moves {
+        for (from, to) in resolve_pairs(&layer_map, &present, spec)? {
+            remap.move_layer(from, to);
+        }
+    }
+    for spec in &cli.copies {
+        for (from, to) in resolve_pairs(&layer_map, &present, spec)? {
+            remap.copy_layer(from, to);
+        }
+    }
+    for spec in &cli.deletes {
+        for key in resolve_all(&layer_map, &present, spec)? {
+            remap.delete_layer(key);
+        }
+    }
+    if !cli.keeps.is_empty() {
+        let mut keeps = Vec::new();
+        for spec in &cli.keeps {
+            keeps.extend(resolve_all(&layer_map, &present, spec)?);
+        }
+        for key in &present {
+            if !keeps.contains(key) && remap.targets(*key).is_none() {
+                remap.delete_layer(*key);
+            }
+        }
+    }
+
+    let matched = library.remap_layers(&remap);
+    println!("{} shapes remapped", matched);
+    library.write_gds(&cli.output_path)?;
+    Ok(())
+}
+
+fn main() {
+    if let Err(e) = main_result() {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
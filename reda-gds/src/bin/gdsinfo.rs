@@ -0,0 +1,64 @@
+use std::path::PathBuf;
+use reda_gds::{GdsLayerMap, GdsLibrary};
+use clap::Parser;
+
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    /// Input GDS file path
+    input_path: PathBuf,
+
+    /// Report this cell with its hierarchy expanded: elements are counted once per instance.
+    /// Without it, every structure of the library is counted once
+    #[arg(long)]
+    cell: Option<String>,
+
+    /// Name layers from a KLayout .lyp file, a Cadence layermap or a `name layer datatype` table
+    #[arg(long)]
+    layer_map: Option<PathBuf>,
+}
+
+fn main_result() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    let library = GdsLibrary::read_gds(cli.input_path)?;
+    let layer_map = match &cli.layer_map {
+        Some(path) => GdsLayerMap::read(path)?,
+        None => GdsLayerMap::new(),
+    };
+    let hierarchy = library.hierarchy();
+
+    println!("library: {}", library.name);
+    println!("units: {} user units, {} meters per database unit", library.usrunits_per_dbunit, library.meters_per_dbunit);
+    println!("structures: {}", library.structures.len());
+    println!("top cells: {}", hierarchy.top_cells().join(", "));
+    for dangling in hierarchy.dangling_references() {
+        println!("dangling reference: {} -> {}", dangling.parent, dangling.s_name);
+    }
+    for cycle in hierarchy.cycles() {
+        println!("reference cycle: {}", cycle.join(" -> "));
+    }
+
+    let report = match &cli.cell {
+        Some(cell) => library.hierarchical_layer_report(cell)?,
+        None => library.layer_report(),
+    };
+
+    println!();
+    println!("{:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>16}  name", "layer", "boundaries", "paths", "boxes", "texts", "nodes", "area");
+    for (key, stats) in &report {
+        let name = layer_map.get(*key).map(|name| name.to_string()).unwrap_or_default();
+        let line = format!("{:>10} {:>10} {:>10} {:>10} {:>10} {:>10} {:>16.6}  {}", 
+            key.to_string(), stats.boundaries, stats.paths, stats.boxes, stats.texts, stats.nodes, stats.area, name);
+        println!("{}", line.trim_end());
+    }
+
+    Ok(())
+}
+
+fn main() {
+    if let Err(e) = main_result() {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
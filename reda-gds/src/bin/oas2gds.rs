@@ -0,0 +1,35 @@
+use std::path::PathBuf;
+use reda_gds::GdsLibrary;
+use clap::Parser;
+
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    /// Input OASIS file path
+    input_path: PathBuf,
+
+    /// Output GDS file path
+    output_path: PathBuf,
+
+    /// Library name, OASIS files do not carry one
+    #[arg(long)]
+    name: Option<String>,
+}
+
+fn main_result() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+    let mut library = GdsLibrary::read_oasis(cli.input_path)?;
+    if let Some(name) = cli.name {
+        library.name = name;
+    }
+    library.write_gds(cli.output_path)?;
+    Ok(())
+}
+
+fn main() {
+    if let Err(e) = main_result() {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,70 @@
+use crate::{GdsLayerKey, GdsStructure};
+use super::{GdsBooleanOp, GdsRegion};
+
+/// Layer derivations on the shapes of a single structure, flatten it first to include references
+impl GdsStructure {
+    /// Boundaries, boxes and paths on the layer, unmerged
+    pub fn region(&self, key: GdsLayerKey) -> GdsRegion {
+        let mut region = GdsRegion::new();
+        self.boundarys.iter()
+            .filter(|b| b.layer_key() == key)
+            .for_each(|b| region.push_boundary(b));
+        self.boxes.iter()
+            .filter(|b| b.layer_key() == key)
+            .for_each(|b| region.push_box(b));
+        self.paths.iter()
+            .filter(|p| p.layer_key() == key)
+            .for_each(|p| region.push_path(p));
+        region
+    }
+
+    /// Append the region to the layer as boundaries
+    pub fn insert_region(&mut self, key: GdsLayerKey, region: &GdsRegion) {
+        self.boundarys.extend(region.to_boundaries(key));
+    }
+
+    /// Compute `a op b` and append the result to `output`
+    pub fn boolean(&mut self, a: GdsLayerKey, op: GdsBooleanOp, b: GdsLayerKey, output: GdsLayerKey) {
+        let result = self.region(a).boolean(&self.region(b), op);
+        self.insert_region(output, &result);
+    }
+
+    /// Grow (or shrink for a negative distance) the layer and append the result to `output`
+    pub fn size_layer(&mut self, input: GdsLayerKey, distance: i32, output: GdsLayerKey) {
+        let result = self.region(input).sized(distance);
+        self.insert_region(output, &result);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{GdsBoxBuilder, GdsDbCoord, GdsPathBuilder, GdsRect};
+    use super::*;
+
+    #[test]
+    fn test_layer_boolean() {
+        let mut structure = GdsStructure::new("top");
+        let square = GdsRegion::from_rect(&GdsRect::new(0, 0, 100, 100));
+        structure.insert_region(GdsLayerKey::new(1, 0), &square);
+        structure.push_element(GdsBoxBuilder::default()
+            .layer(1).box_type(0)
+            .xy(GdsRect::new(100, 0, 150, 50).corners().into_iter().chain([GdsDbCoord::new(100, 0)]).collect())
+            .build().unwrap());
+        structure.push_element(GdsPathBuilder::default()
+            .layer(2).data_type(0).width(20)
+            .xy(vec![GdsDbCoord::new(50, -50), GdsDbCoord::new(50, 150)])
+            .build().unwrap());
+
+        let (metal, via, out) = (GdsLayerKey::new(1, 0), GdsLayerKey::new(2, 0), GdsLayerKey::new(10, 0));
+        structure.boolean(metal, GdsBooleanOp::And, via, out);
+        assert_eq!(structure.region(out).area(), 2000.0);
+
+        structure.boolean(metal, GdsBooleanOp::Or, metal, GdsLayerKey::new(11, 0));
+        let merged = structure.region(GdsLayerKey::new(11, 0));
+        assert_eq!(merged.polygons().len(), 1);
+        assert_eq!(merged.area(), 12500.0);
+
+        structure.size_layer(via, 5, GdsLayerKey::new(12, 0));
+        assert_eq!(structure.region(GdsLayerKey::new(12, 0)).bbox(), Some(GdsRect::new(35, -55, 65, 155)));
+    }
+}
@@ -0,0 +1,8 @@
+mod path;
+mod scanline;
+mod region;
+mod layer_ops;
+
+pub use path::*;
+pub use region::*;
+pub(crate) use scanline::winding;
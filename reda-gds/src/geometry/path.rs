@@ -0,0 +1,203 @@
+use std::f64::consts::PI;
+use crate::{GdsBoundary, GdsDbCoord, GdsPath, GdsPathType, round_to_db};
+
+/// Segments used to approximate a half circle of a round path end
+pub const DEFAULT_ROUND_SEGMENTS: usize = 8;
+
+type Point = (f64, f64);
+
+impl GdsPath {
+    /// Outline of the path, see `to_polygon_with`
+    pub fn to_polygon(&self) -> Option<GdsBoundary> {
+        self.to_polygon_with(DEFAULT_ROUND_SEGMENTS)
+    }
+
+    /// Outline of the path as a closed boundary on the same layer and datatype.
+    ///
+    /// Joins are mitred. Path type 2 extends both ends by half the width, path type 1 ends
+    /// with half circles made of `round_segments` segments. A negative (absolute) width is 
+    /// used as its magnitude. `None` if the path has no width or no length to outline.
+    pub fn to_polygon_with(&self, round_segments: usize) -> Option<GdsBoundary> {
+        let half_width = self.width?.unsigned_abs() as f64 / 2.0;
+        if half_width == 0.0 {
+            return None;
+        }
+
+        let mut points: Vec<Point> = Vec::with_capacity(self.xy.len());
+        for p in &self.xy {
+            let p = (p.x as f64, p.y as f64);
+            if points.last() != Some(&p) {
+                points.push(p);
+            }
+        }
+
+        let outline = match points.len() {
+            0 => return None,
+            1 => point_outline(points[0], half_width, self.path_type.unwrap_or_default(), round_segments)?,
+            _ => path_outline(&points, half_width, self.path_type.unwrap_or_default(), round_segments),
+        };
+
+        let mut xy: Vec<GdsDbCoord> = Vec::with_capacity(outline.len() + 1);
+        for (x, y) in outline {
+            let p = GdsDbCoord::new(round_to_db(x), round_to_db(y));
+            if xy.last() != Some(&p) {
+                xy.push(p);
+            }
+        }
+        if xy.len() > 1 && xy.first() == xy.last() {
+            xy.pop();
+        }
+        if xy.len() < 3 {
+            return None;
+        }
+        xy.push(xy[0]);
+
+        Some(GdsBoundary {
+            elf_flags: self.elf_flags,
+            plex: self.plex,
+            layer: self.layer,
+            data_type: self.data_type,
+            xy,
+            properties: self.properties.clone(),
+            extra_records: Vec::new(),
+        })
+    }
+}
+
+fn path_outline(points: &[Point], half_width: f64, path_type: GdsPathType, round_segments: usize) -> Vec<Point> {
+    let n = points.len();
+    let directions: Vec<Point> = points.windows(2).map(|w| unit(w[0], w[1])).collect();
+    let normal = |d: Point| (-d.1, d.0);
+
+    let mut start = points[0];
+    let mut end = points[n - 1];
+    if let GdsPathType::SquareEndExtend = path_type {
+        let (d0, d1) = (directions[0], directions[n - 2]);
+        start = (start.0 - d0.0 * half_width, start.1 - d0.1 * half_width);
+        end = (end.0 + d1.0 * half_width, end.1 + d1.1 * half_width);
+    }
+
+    // Offset of every vertex along the left side, the right side is the opposite
+    let mut left: Vec<Point> = Vec::with_capacity(n + 2);
+    let mut right: Vec<Point> = Vec::with_capacity(n + 2);
+    let n0 = normal(directions[0]);
+    left.push((start.0 + n0.0 * half_width, start.1 + n0.1 * half_width));
+    right.push((start.0 - n0.0 * half_width, start.1 - n0.1 * half_width));
+
+    for i in 1..n - 1 {
+        let p = points[i];
+        let (a, b) = (normal(directions[i - 1]), normal(directions[i]));
+        let cos = a.0 * b.0 + a.1 * b.1;
+        if 1.0 + cos < 1e-9 {
+            // The path turns back on itself, the mitre would be infinite
+            left.push((p.0 + a.0 * half_width, p.1 + a.1 * half_width));
+            left.push((p.0 + b.0 * half_width, p.1 + b.1 * half_width));
+            right.push((p.0 - a.0 * half_width, p.1 - a.1 * half_width));
+            right.push((p.0 - b.0 * half_width, p.1 - b.1 * half_width));
+        } else {
+            let k = half_width / (1.0 + cos);
+            let m = ((a.0 + b.0) * k, (a.1 + b.1) * k);
+            left.push((p.0 + m.0, p.1 + m.1));
+            right.push((p.0 - m.0, p.1 - m.1));
+        }
+    }
+
+    let nl = normal(directions[n - 2]);
+    left.push((end.0 + nl.0 * half_width, end.1 + nl.1 * half_width));
+    right.push((end.0 - nl.0 * half_width, end.1 - nl.1 * half_width));
+
+    let mut outline = left;
+    if let GdsPathType::RoundEnd = path_type {
+        outline.extend(half_circle(end, nl, half_width, round_segments));
+    }
+    outline.extend(right.into_iter().rev());
+    if let GdsPathType::RoundEnd = path_type {
+        outline.extend(half_circle(start, (-n0.0, -n0.1), half_width, round_segments));
+    }
+    outline
+}
+
+/// Outline of a path made of a single point: a square or a circle of the path width
+fn point_outline(p: Point, half_width: f64, path_type: GdsPathType, round_segments: usize) -> Option<Vec<Point>> {
+    match path_type {
+        GdsPathType::SquareEnd => None,
+        GdsPathType::SquareEndExtend => Some(vec![
+            (p.0 - half_width, p.1 - half_width),
+            (p.0 + half_width, p.1 - half_width),
+            (p.0 + half_width, p.1 + half_width),
+            (p.0 - half_width, p.1 + half_width),
+        ]),
+        GdsPathType::RoundEnd => {
+            let mut outline = vec![(p.0, p.1 + half_width)];
+            outline.extend(half_circle(p, (0.0, 1.0), half_width, round_segments));
+            outline.push((p.0, p.1 - half_width));
+            outline.extend(half_circle(p, (0.0, -1.0), half_width, round_segments));
+            Some(outline)
+        }
+    }
+}
+
+/// Inner points of the clockwise half circle around `center` starting in direction `from`
+fn half_circle(center: Point, from: Point, radius: f64, segments: usize) -> Vec<Point> {
+    let start = from.1.atan2(from.0);
+    let segments = segments.max(1);
+    (1..segments)
+        .map(|k| {
+            let angle = start - PI * k as f64 / segments as f64;
+            (center.0 + radius * angle.cos(), center.1 + radius * angle.sin())
+        })
+        .collect()
+}
+
+fn unit(from: Point, to: Point) -> Point {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let length = (dx * dx + dy * dy).sqrt();
+    (dx / length, dy / length)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::GdsPathBuilder;
+    use super::*;
+
+    fn path(path_type: GdsPathType, width: i32, xy: &[(i32, i32)]) -> GdsPath {
+        GdsPathBuilder::default()
+            .layer(1)
+            .data_type(2)
+            .path_type(path_type)
+            .width(width)
+            .xy(xy.iter().map(|&(x, y)| GdsDbCoord::new(x, y)).collect())
+            .build().unwrap()
+    }
+
+    fn coords(xy: &[(i32, i32)]) -> Vec<GdsDbCoord> {
+        xy.iter().map(|&(x, y)| GdsDbCoord::new(x, y)).collect()
+    }
+
+    #[test]
+    fn test_square_end() {
+        let polygon = path(GdsPathType::SquareEnd, 10, &[(0, 0), (100, 0)]).to_polygon().unwrap();
+        assert_eq!(polygon.layer_key().data_type, 2);
+        assert_eq!(polygon.xy, coords(&[(0, 5), (100, 5), (100, -5), (0, -5), (0, 5)]));
+    }
+
+    #[test]
+    fn test_extended_mitred() {
+        let polygon = path(GdsPathType::SquareEndExtend, -10, &[(0, 0), (100, 0), (100, 100)]).to_polygon().unwrap();
+        assert_eq!(polygon.xy, coords(&[(-5, 5), (95, 5), (95, 105), (105, 105), (105, -5), (-5, -5), (-5, 5)]));
+    }
+
+    #[test]
+    fn test_round_end() {
+        let polygon = path(GdsPathType::RoundEnd, 20, &[(0, 0), (100, 0)]).to_polygon_with(2).unwrap();
+        assert_eq!(polygon.xy, coords(&[(0, 10), (100, 10), (110, 0), (100, -10), (0, -10), (-10, 0), (0, 10)]));
+    }
+
+    #[test]
+    fn test_degenerate() {
+        assert!(path(GdsPathType::SquareEnd, 0, &[(0, 0), (100, 0)]).to_polygon().is_none());
+        assert!(path(GdsPathType::SquareEnd, 10, &[(0, 0), (0, 0)]).to_polygon().is_none());
+        let square = path(GdsPathType::SquareEndExtend, 10, &[(0, 0)]).to_polygon().unwrap();
+        assert_eq!(square.xy.len(), 5);
+    }
+}
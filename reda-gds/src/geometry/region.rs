@@ -0,0 +1,519 @@
+use crate::{GdsBoundary, GdsBox, GdsDbCoord, GdsLayerKey, GdsPath, GdsRect, MAX_XY_POINTS, round_to_db};
+use super::scanline::{evaluate, signed_area2, winding};
+
+/// Boolean operation between two regions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GdsBooleanOp {
+    And,
+    Or,
+    Xor,
+    /// Parts of the first region outside the second one
+    Not,
+}
+
+impl GdsBooleanOp {
+    pub fn apply(&self, a: bool, b: bool) -> bool {
+        match self {
+            Self::And => a && b,
+            Self::Or => a || b,
+            Self::Xor => a != b,
+            Self::Not => a && !b,
+        }
+    }
+}
+
+/// An area made of polygons on the database grid.
+///
+/// Rings are stored without closing point, the interior is where the winding number is not zero.
+/// Pushed polygons are oriented counter clockwise, results of operations are merged and hold
+/// counter clockwise outlines with clockwise holes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GdsRegion {
+    polygons: Vec<Vec<GdsDbCoord>>,
+}
+
+impl GdsRegion {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn polygons(&self) -> &[Vec<GdsDbCoord>] {
+        &self.polygons
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.polygons.is_empty()
+    }
+
+    /// Add a polygon, with or without closing point
+    pub fn push_polygon(&mut self, points: &[GdsDbCoord]) {
+        let mut ring = points.to_vec();
+        if ring.len() > 1 && ring.first() == ring.last() {
+            ring.pop();
+        }
+        if ring.len() < 3 {
+            return;
+        }
+        match signed_area2(&ring) {
+            // A figure eight has no net area but still covers its loops
+            0 if ring.iter().all(|p| area(ring[0], ring[1], *p) == 0) => return,
+            area if area < 0 => ring.reverse(),
+            _ => {}
+        }
+        self.polygons.push(ring);
+    }
+
+    pub fn push_boundary(&mut self, boundary: &GdsBoundary) {
+        self.push_polygon(&boundary.xy);
+    }
+
+    pub fn push_box(&mut self, boxx: &GdsBox) {
+        self.push_polygon(&boxx.xy);
+    }
+
+    pub fn push_path(&mut self, path: &GdsPath) {
+        if let Some(polygon) = path.to_polygon() {
+            self.push_polygon(&polygon.xy);
+        }
+    }
+
+    pub fn push_rect(&mut self, rect: &GdsRect) {
+        self.push_polygon(&rect.corners());
+    }
+
+    pub fn from_rect(rect: &GdsRect) -> Self {
+        let mut region = Self::new();
+        region.push_rect(rect);
+        region
+    }
+
+    /// Area in square database units, overlaps are counted twice unless the region is merged
+    pub fn area(&self) -> f64 {
+        self.polygons.iter().map(|ring| signed_area2(ring) as f64 / 2.0).sum()
+    }
+
+    pub fn bbox(&self) -> Option<GdsRect> {
+        GdsRect::from_points(self.polygons.iter().flatten())
+    }
+
+    pub fn boolean(&self, other: &Self, op: GdsBooleanOp) -> Self {
+        Self { polygons: evaluate(&self.polygons, &other.polygons, |a, b| op.apply(a, b)) }
+    }
+
+    pub fn and(&self, other: &Self) -> Self {
+        self.boolean(other, GdsBooleanOp::And)
+    }
+
+    pub fn or(&self, other: &Self) -> Self {
+        self.boolean(other, GdsBooleanOp::Or)
+    }
+
+    pub fn xor(&self, other: &Self) -> Self {
+        self.boolean(other, GdsBooleanOp::Xor)
+    }
+
+    pub fn not(&self, other: &Self) -> Self {
+        self.boolean(other, GdsBooleanOp::Not)
+    }
+
+    /// Overlapping and touching polygons joined into outlines with holes
+    pub fn merged(&self) -> Self {
+        self.or(&Self::new())
+    }
+
+    /// Grow the region by `distance` on every side, or shrink it for a negative distance.
+    ///
+    /// Convex corners are mitred, corners sharper than 90 degrees are bevelled.
+    pub fn sized(&self, distance: i32) -> Self {
+        if distance == 0 {
+            return self.merged();
+        }
+        if distance < 0 {
+            // Shrinking is growing the complement
+            let Some(bbox) = self.bbox() else { return Self::new() };
+            let frame = Self::from_rect(&bbox.expanded(2 - distance));
+            let outside = frame.not(self).sized(-distance);
+            return self.not(&outside);
+        }
+
+        let merged = self.merged();
+        let d = distance as f64;
+        let mut grown = Self { polygons: merged.polygons.clone() };
+        for ring in &merged.polygons {
+            let n = ring.len();
+            for i in 0..n {
+                let (p, q, r) = (ring[i], ring[(i + 1) % n], ring[(i + 2) % n]);
+                let n1 = outward_normal(p, q);
+                let n2 = outward_normal(q, r);
+                let offset = |c: GdsDbCoord, (nx, ny): (f64, f64)| {
+                    GdsDbCoord::new(round_to_db(c.x as f64 + nx * d), round_to_db(c.y as f64 + ny * d))
+                };
+                grown.push_polygon(&[p, q, offset(q, n1), offset(p, n1)]);
+
+                let turn = (q.x - p.x) as i64 * (r.y - q.y) as i64 - (q.y - p.y) as i64 * (r.x - q.x) as i64;
+                if turn > 0 {
+                    let cos = n1.0 * n2.0 + n1.1 * n2.1;
+                    if cos >= -EPSILON_COS {
+                        let k = 1.0 / (1.0 + cos);
+                        let mitre = ((n1.0 + n2.0) * k, (n1.1 + n2.1) * k);
+                        grown.push_polygon(&[q, offset(q, n1), offset(q, mitre), offset(q, n2)]);
+                    } else {
+                        grown.push_polygon(&[q, offset(q, n1), offset(q, n2)]);
+                    }
+                }
+            }
+        }
+        grown.merged()
+    }
+
+    /// The region as boundaries, holes are joined to their outline by a zero width cut.
+    /// Polygons with more points than a boundary holds are cut into pieces that fit.
+    pub fn to_boundaries(&self, key: GdsLayerKey) -> Vec<GdsBoundary> {
+        // One point is left for the closing one
+        self.merged().fitted_rings(MAX_XY_POINTS - 1)
+            .into_iter()
+            .map(|mut xy| {
+                xy.push(xy[0]);
+                GdsBoundary {
+                    elf_flags: None,
+                    plex: None,
+                    layer: key.layer,
+                    data_type: key.data_type,
+                    xy,
+                    properties: Vec::new(),
+                    extra_records: Vec::new(),
+                }
+            })
+            .collect()
+    }
+}
+
+impl GdsRegion {
+    /// Keyholed rings of a merged region with at most `max_points` points each, the rings
+    /// above it are cut in halves across the longer side of their bounding box
+    fn fitted_rings(&self, max_points: usize) -> Vec<Vec<GdsDbCoord>> {
+        let mut rings = Vec::new();
+        for ring in keyholes(&self.polygons) {
+            if ring.len() <= max_points {
+                rings.push(ring);
+                continue;
+            }
+            let Some(bbox) = GdsRect::from_points(&ring) else { continue };
+            let (min, max) = (bbox.min, bbox.max);
+            let halves = match max.x - min.x >= max.y - min.y {
+                true => {
+                    let middle = min.x + (max.x - min.x) / 2;
+                    [GdsRect::new(min.x, min.y, middle, max.y), GdsRect::new(middle, min.y, max.x, max.y)]
+                }
+                false => {
+                    let middle = min.y + (max.y - min.y) / 2;
+                    [GdsRect::new(min.x, min.y, max.x, middle), GdsRect::new(min.x, middle, max.x, max.y)]
+                }
+            };
+            let mut piece = Self::new();
+            piece.push_polygon(&ring);
+            for half in halves {
+                rings.extend(piece.and(&Self::from_rect(&half)).fitted_rings(max_points));
+            }
+        }
+        rings
+    }
+}
+
+/// Mitres are used up to right angles
+const EPSILON_COS: f64 = 1e-9;
+
+/// Unit normal on the right of `p -> q`, outside of a ring with its interior on the left
+fn outward_normal(p: GdsDbCoord, q: GdsDbCoord) -> (f64, f64) {
+    let (dx, dy) = ((q.x - p.x) as f64, (q.y - p.y) as f64);
+    let length = (dx * dx + dy * dy).sqrt();
+    (dy / length, -dx / length)
+}
+
+
+fn leftmost(ring: &[GdsDbCoord]) -> usize {
+    (0..ring.len()).min_by_key(|&i| (ring[i].x, ring[i].y)).unwrap()
+}
+
+/// Join every hole to the outline around it, giving simple rings without holes
+fn keyholes(rings: &[Vec<GdsDbCoord>]) -> Vec<Vec<GdsDbCoord>> {
+    let (outlines, holes): (Vec<_>, Vec<_>) = rings.iter().partition(|ring| signed_area2(ring) > 0);
+    let mut outlines: Vec<(Vec<GdsDbCoord>, Vec<&Vec<GdsDbCoord>>)> =
+        outlines.into_iter().map(|ring| (ring.clone(), Vec::new())).collect();
+
+    for hole in holes {
+        // A point just left of the hole lies inside its outline
+        let h = hole[leftmost(hole)];
+        let (x, y) = (h.x as f64 - 1e-3, h.y as f64 + 1e-6);
+        let owner = outlines.iter_mut()
+            .filter(|(ring, _)| winding(ring, x, y) != 0)
+            .min_by_key(|(ring, _)| signed_area2(ring));
+        if let Some((_, owned)) = owner {
+            owned.push(hole);
+        }
+    }
+
+    outlines.into_iter()
+        .map(|(mut outline, mut owned)| {
+            owned.sort_by_key(|hole| (hole[leftmost(hole)].x, hole[leftmost(hole)].y));
+            for hole in owned {
+                let start = leftmost(hole);
+                let Some(m) = find_bridge(&outline, hole[start]) else { continue };
+                let mut ring = Vec::with_capacity(outline.len() + hole.len() + 2);
+                ring.extend_from_slice(&outline[..=m]);
+                ring.extend_from_slice(&hole[start..]);
+                ring.extend_from_slice(&hole[..=start]);
+                ring.extend_from_slice(&outline[m..]);
+                outline = ring;
+            }
+            outline
+        })
+        .collect()
+}
+
+/// Twice the signed area of the triangle, negative when counter clockwise
+fn area(p: GdsDbCoord, q: GdsDbCoord, r: GdsDbCoord) -> i128 {
+    (q.y - p.y) as i128 * (r.x - q.x) as i128 - (q.x - p.x) as i128 * (r.y - q.y) as i128
+}
+
+fn point_in_triangle(a: (f64, f64), b: (f64, f64), c: (f64, f64), p: (f64, f64)) -> bool {
+    (c.0 - p.0) * (a.1 - p.1) >= (a.0 - p.0) * (c.1 - p.1)
+        && (a.0 - p.0) * (b.1 - p.1) >= (b.0 - p.0) * (a.1 - p.1)
+        && (b.0 - p.0) * (c.1 - p.1) >= (c.0 - p.0) * (b.1 - p.1)
+}
+
+/// Index of the outline point a hole starting at `h`, its leftmost point, can be joined to
+fn find_bridge(outline: &[GdsDbCoord], h: GdsDbCoord) -> Option<usize> {
+    let n = outline.len();
+    let (hx, hy) = (h.x as f64, h.y as f64);
+
+    // Nearest edge on the left of the hole, crossed by a horizontal ray
+    let mut qx = f64::NEG_INFINITY;
+    let mut m = None;
+    for i in 0..n {
+        let (p, q) = (outline[i], outline[(i + 1) % n]);
+        if h.y <= p.y && h.y >= q.y && q.y != p.y {
+            let x = p.x as f64 + (hy - p.y as f64) * (q.x - p.x) as f64 / (q.y - p.y) as f64;
+            if x <= hx && x > qx {
+                qx = x;
+                m = Some(if p.x < q.x { i } else { (i + 1) % n });
+                if x == hx {
+                    return m;
+                }
+            }
+        }
+    }
+    let mut m = m?;
+
+    // Points inside the triangle between the hole, the ray hit and the edge end would block
+    // the cut, the visible one closest to the ray is taken instead
+    let (mx, my) = (outline[m].x as f64, outline[m].y as f64);
+    let (a, c) = if hy < my { ((hx, hy), (qx, hy)) } else { ((qx, hy), (hx, hy)) };
+    let mut tan_min = f64::INFINITY;
+    for i in 0..n {
+        let p = outline[i];
+        let (px, py) = (p.x as f64, p.y as f64);
+        if hx >= px && px >= mx && hx != px && point_in_triangle(a, (mx, my), c, (px, py)) {
+            let tan = (hy - py).abs() / (hx - px);
+            if locally_inside(outline, i, h) && (tan < tan_min || (tan == tan_min && px > outline[m].x as f64)) {
+                m = i;
+                tan_min = tan;
+            }
+        }
+    }
+    Some(m)
+}
+
+/// Whether `b` lies inside the corner of the outline at `i`
+fn locally_inside(outline: &[GdsDbCoord], i: usize, b: GdsDbCoord) -> bool {
+    let n = outline.len();
+    let (prev, a, next) = (outline[(i + n - 1) % n], outline[i], outline[(i + 1) % n]);
+    if area(prev, a, next) < 0 {
+        area(a, b, next) >= 0 && area(a, prev, b) >= 0
+    } else {
+        area(a, b, prev) < 0 || area(a, next, b) < 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x0: i32, y0: i32, x1: i32, y1: i32) -> GdsRegion {
+        GdsRegion::from_rect(&GdsRect::new(x0, y0, x1, y1))
+    }
+
+    fn polygon(points: &[(i32, i32)]) -> GdsRegion {
+        let mut region = GdsRegion::new();
+        region.push_polygon(&points.iter().map(|&(x, y)| GdsDbCoord::new(x, y)).collect::<Vec<_>>());
+        region
+    }
+
+    #[test]
+    fn test_rect_booleans() {
+        let a = rect(0, 0, 10, 10);
+        let b = rect(5, 5, 15, 15);
+
+        let and = a.and(&b);
+        assert_eq!(and.polygons().len(), 1);
+        assert_eq!(and.area(), 25.0);
+        assert_eq!(and.bbox(), Some(GdsRect::new(5, 5, 10, 10)));
+
+        let or = a.or(&b);
+        assert_eq!(or.polygons().len(), 1);
+        assert_eq!(or.polygons()[0].len(), 8);
+        assert_eq!(or.area(), 175.0);
+
+        assert_eq!(a.xor(&b).area(), 150.0);
+        assert_eq!(a.xor(&b).polygons().len(), 2);
+        assert_eq!(a.not(&b).area(), 75.0);
+        assert_eq!(a.not(&b).polygons()[0].len(), 6);
+    }
+
+    #[test]
+    fn test_diagonal_edges() {
+        let triangle = polygon(&[(0, 0), (20, 0), (0, 20)]);
+        let square = rect(0, 0, 10, 10);
+        assert_eq!(triangle.and(&square).area(), 100.0);
+        assert_eq!(triangle.not(&square).area(), 100.0);
+
+        let diamond = polygon(&[(10, 0), (20, 10), (10, 20), (0, 10)]);
+        assert_eq!(diamond.and(&square).area(), 50.0);
+        assert_eq!(diamond.or(&square).area(), 250.0);
+    }
+
+    /// Whether `op` holds at every sample point farther than a pixel from the edges of `a`
+    /// and `b`, where snap rounding cannot have moved the result
+    fn check_samples(a: &GdsRegion, b: &GdsRegion, op: GdsBooleanOp, result: &GdsRegion) {
+        let inside = |region: &GdsRegion, x: f64, y: f64| region.polygons().iter().map(|r| winding(r, x, y)).sum::<i32>() != 0;
+        let near_edge = |x: f64, y: f64| a.polygons().iter().chain(b.polygons()).any(|ring| {
+            (0..ring.len()).any(|i| {
+                let (p, q) = (ring[i], ring[(i + 1) % ring.len()]);
+                let (px, py, dx, dy) = (p.x as f64, p.y as f64, (q.x - p.x) as f64, (q.y - p.y) as f64);
+                let t = (((x - px) * dx + (y - py) * dy) / (dx * dx + dy * dy)).clamp(0.0, 1.0);
+                (px + t * dx - x).hypot(py + t * dy - y) < 1.5
+            })
+        });
+        for i in 0..60 {
+            for j in 0..60 {
+                let (x, y) = (i as f64 * 17.3 + 0.25, j as f64 * 17.3 + 0.25);
+                if !near_edge(x, y) {
+                    assert_eq!(inside(result, x, y), op.apply(inside(a, x, y), inside(b, x, y)), "{:?} at ({}, {})", op, x, y);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_crossing_triangles() {
+        let a = polygon(&[(500, 0), (400, 500), (100, 0)]);
+        let b = polygon(&[(400, 100), (0, 300), (400, 0)]);
+        let mut both = a.clone();
+        both.push_polygon(&b.polygons()[0]);
+
+        let merged = both.merged();
+        assert_eq!(merged, a.or(&b));
+        assert_eq!(merged.merged(), merged);
+        check_samples(&a, &b, GdsBooleanOp::Or, &merged);
+        // Crossings move by less than a pixel along edges a few hundred units long
+        let and = a.and(&b);
+        assert!((merged.area() + and.area() - a.area() - b.area()).abs() < 100.0);
+    }
+
+    #[test]
+    fn test_self_intersecting_polygon() {
+        let bowtie = polygon(&[(0, 0), (10, 10), (10, 0), (0, 10)]);
+        let merged = bowtie.merged();
+        assert_eq!(merged.polygons().len(), 2);
+        assert!(merged.polygons().iter().all(|ring| ring.len() == 3));
+        assert_eq!(merged.area(), 50.0);
+    }
+
+    #[test]
+    fn test_random_triangles() {
+        let mut seed = 12345u64;
+        let mut next = || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            ((seed >> 33) % 1000) as i32
+        };
+        for _ in 0..40 {
+            let a = polygon(&[(next(), next()), (next(), next()), (next(), next())]);
+            let b = polygon(&[(next(), next()), (next(), next()), (next(), next())]);
+            for op in [GdsBooleanOp::And, GdsBooleanOp::Or, GdsBooleanOp::Xor, GdsBooleanOp::Not] {
+                let result = a.boolean(&b, op);
+                check_samples(&a, &b, op, &result);
+                assert_eq!(result.merged(), result, "{:?} of {:?} and {:?}", op, a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_merge_overlaps() {
+        let mut region = rect(0, 0, 10, 10);
+        region.push_rect(&GdsRect::new(0, 0, 10, 10));
+        region.push_rect(&GdsRect::new(10, 0, 20, 10));
+        assert_eq!(region.area(), 300.0);
+        let merged = region.merged();
+        assert_eq!(merged.polygons().len(), 1);
+        assert_eq!(merged.polygons()[0].len(), 4);
+        assert_eq!(merged.area(), 200.0);
+
+        let corners = rect(0, 0, 10, 10).or(&rect(10, 10, 20, 20));
+        assert_eq!(corners.polygons().len(), 2);
+    }
+
+    #[test]
+    fn test_holes_to_boundaries() {
+        let frame = rect(0, 0, 30, 30).not(&rect(10, 10, 20, 20));
+        assert_eq!(frame.polygons().len(), 2);
+        assert_eq!(frame.area(), 800.0);
+
+        let key = GdsLayerKey::new(3, 1);
+        let boundaries = frame.to_boundaries(key);
+        assert_eq!(boundaries.len(), 1);
+        assert_eq!(boundaries[0].layer_key(), key);
+        assert_eq!(boundaries[0].area(), 800.0);
+        assert_eq!(boundaries[0].xy.first(), boundaries[0].xy.last());
+
+        // The boundaries give back the same region
+        let mut back = GdsRegion::new();
+        boundaries.iter().for_each(|b| back.push_boundary(b));
+        assert!(back.xor(&frame).is_empty());
+    }
+
+    #[test]
+    fn test_split_large_polygons() {
+        // A comb of 50 teeth with 2 slots cut in each, over 200 points once keyholed
+        let mut comb = rect(0, 0, 1000, 10);
+        let mut slots = GdsRegion::new();
+        for i in 0..50 {
+            comb = comb.or(&rect(i * 20, 10, i * 20 + 10, 100));
+            slots = slots.or(&rect(i * 20 + 3, 20, i * 20 + 7, 40)).or(&rect(i * 20 + 3, 60, i * 20 + 7, 80));
+        }
+        let comb = comb.not(&slots);
+
+        let rings = comb.fitted_rings(40);
+        assert!(rings.len() > 1);
+        assert!(rings.iter().all(|ring| ring.len() <= 40));
+        let mut back = GdsRegion::new();
+        rings.iter().for_each(|ring| back.push_polygon(ring));
+        assert!(back.xor(&comb).is_empty());
+        assert_eq!(back.area(), comb.area());
+    }
+
+    #[test]
+    fn test_sizing() {
+        let grown = rect(0, 0, 10, 10).sized(5);
+        assert_eq!(grown.polygons().len(), 1);
+        assert_eq!(grown.bbox(), Some(GdsRect::new(-5, -5, 15, 15)));
+        assert_eq!(grown.area(), 400.0);
+
+        let shrunk = rect(0, 0, 30, 10).or(&rect(0, 0, 10, 30)).sized(-2);
+        assert_eq!(shrunk.area(), (26 * 6 + 6 * 20) as f64);
+        assert!(rect(0, 0, 10, 10).sized(-5).is_empty());
+
+        let notch = rect(0, 0, 30, 10).not(&rect(10, 5, 20, 10));
+        assert_eq!(notch.sized(3).bbox(), Some(GdsRect::new(-3, -3, 33, 13)));
+        assert_eq!(notch.sized(3).area(), 36.0 * 16.0 - 4.0 * 5.0);
+    }
+}
@@ -0,0 +1,399 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use crate::GdsDbCoord;
+
+type Point = (i64, i64);
+
+/// Ring edge from `p` to `q`, the interior of its ring on the left
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    p: Point,
+    q: Point,
+    operand: usize,
+}
+
+/// Twice the signed area of a ring, positive when counter clockwise
+pub(crate) fn signed_area2(ring: &[GdsDbCoord]) -> i128 {
+    let n = ring.len();
+    (0..n)
+        .map(|i| {
+            let (p, q) = (ring[i], ring[(i + 1) % n]);
+            p.x as i128 * q.y as i128 - q.x as i128 * p.y as i128
+        })
+        .sum()
+}
+
+/// Winding number of the ring around the point, zero outside
+pub(crate) fn winding(ring: &[GdsDbCoord], x: f64, y: f64) -> i32 {
+    let n = ring.len();
+    let mut winding = 0;
+    for i in 0..n {
+        let (p, q) = (ring[i], ring[(i + 1) % n]);
+        let (px, py, qx, qy) = (p.x as f64, p.y as f64, q.x as f64, q.y as f64);
+        let side = (qx - px) * (y - py) - (x - px) * (qy - py);
+        if py <= y && qy > y && side > 0.0 {
+            winding += 1;
+        } else if py > y && qy <= y && side < 0.0 {
+            winding -= 1;
+        }
+    }
+    winding
+}
+
+fn cross(u: Point, v: Point) -> i128 {
+    u.0 as i128 * v.1 as i128 - u.1 as i128 * v.0 as i128
+}
+
+fn dot(u: Point, v: Point) -> i128 {
+    u.0 as i128 * v.0 as i128 + u.1 as i128 * v.1 as i128
+}
+
+fn sub(a: Point, b: Point) -> Point {
+    (a.0 - b.0, a.1 - b.1)
+}
+
+/// Positive when `c` is on the left of `a -> b`
+fn orient(a: Point, b: Point, c: Point) -> i128 {
+    cross(sub(b, a), sub(c, a))
+}
+
+/// `n / d` rounded to the nearest integer, halves upwards, for `d > 0`
+fn round_div(n: i128, d: i128) -> i64 {
+    (2 * n + d).div_euclid(2 * d) as i64
+}
+
+/// Evaluates `op` over the non-zero interiors of two sets of rings.
+///
+/// The interior of the result is the set of points for which `op` holds. It is returned as
+/// rings without closing point, counter clockwise outlines and clockwise holes, with
+/// collinear points removed, in a canonical order so evaluating a result again gives it back.
+///
+/// Edge crossings are snap rounded: every crossing marks the grid pixel around it as hot, and
+/// every edge is routed through the centres of the hot pixels it passes, so the edges of the
+/// result never cross and move by less than a pixel.
+pub(crate) fn evaluate<F: Fn(bool, bool) -> bool>(
+    a: &[Vec<GdsDbCoord>],
+    b: &[Vec<GdsDbCoord>],
+    op: F,
+) -> Vec<Vec<GdsDbCoord>> {
+    let mut segments = Vec::new();
+    for (operand, rings) in [a, b].into_iter().enumerate() {
+        for ring in rings {
+            let n = ring.len();
+            for i in 0..n {
+                let (p, q) = (ring[i], ring[(i + 1) % n]);
+                if p != q {
+                    segments.push(Segment { p: (p.x as i64, p.y as i64), q: (q.x as i64, q.y as i64), operand });
+                }
+            }
+        }
+    }
+
+    let edges = arrangement(snap_round(segments));
+    let boundary = boundary(edges, op);
+    chain(&boundary)
+}
+
+/// Route the segments through hot pixels until none passes a hot pixel it is not routed through
+fn snap_round(mut segments: Vec<Segment>) -> Vec<Segment> {
+    loop {
+        let mut hot = crossings(&segments);
+        hot.extend(segments.iter().flat_map(|s| [s.p, s.q]));
+        hot.sort_unstable();
+        hot.dedup();
+
+        let snapped = snap(&segments, &hot);
+        // Routing only ever splits a segment
+        let done = snapped.len() == segments.len();
+        segments = snapped;
+        if done {
+            return segments;
+        }
+    }
+}
+
+/// Proper crossings between segments, rounded to the nearest grid point
+fn crossings(segments: &[Segment]) -> Vec<Point> {
+    let x_range = |s: &Segment| (s.p.0.min(s.q.0), s.p.0.max(s.q.0));
+    let mut order: Vec<usize> = (0..segments.len()).collect();
+    order.sort_unstable_by_key(|&i| x_range(&segments[i]).0);
+
+    let mut points = Vec::new();
+    let mut active: Vec<usize> = Vec::new();
+    for i in order {
+        let s = &segments[i];
+        let x0 = x_range(s).0;
+        active.retain(|&j| x_range(&segments[j]).1 >= x0);
+        for &j in &active {
+            if let Some(point) = crossing(s, &segments[j]) {
+                points.push(point);
+            }
+        }
+        active.push(i);
+    }
+    points
+}
+
+fn crossing(s: &Segment, t: &Segment) -> Option<Point> {
+    if s.p.1.max(s.q.1) < t.p.1.min(t.q.1) || t.p.1.max(t.q.1) < s.p.1.min(s.q.1) {
+        return None;
+    }
+    let (d1, d2) = (orient(s.p, s.q, t.p), orient(s.p, s.q, t.q));
+    let (d3, d4) = (orient(t.p, t.q, s.p), orient(t.p, t.q, s.q));
+    // Segments touching at a grid point already have it as an end point
+    if d1.signum() * d2.signum() >= 0 || d3.signum() * d4.signum() >= 0 {
+        return None;
+    }
+
+    let (r, u) = (sub(s.q, s.p), sub(t.q, t.p));
+    let (mut num, mut den) = (cross(sub(t.p, s.p), u), cross(r, u));
+    if den < 0 {
+        (num, den) = (-num, -den);
+    }
+    let x = round_div(s.p.0 as i128 * den + r.0 as i128 * num, den);
+    let y = round_div(s.p.1 as i128 * den + r.1 as i128 * num, den);
+    Some((x, y))
+}
+
+/// Whether the segment meets the closed unit square centred on `h`
+fn meets_pixel(p: Point, q: Point, h: Point) -> bool {
+    // Doubled coordinates put the pixel corners on the grid
+    let (p, q) = ((2 * p.0, 2 * p.1), (2 * q.0, 2 * q.1));
+    let (x0, x1, y0, y1) = (2 * h.0 - 1, 2 * h.0 + 1, 2 * h.1 - 1, 2 * h.1 + 1);
+    if p.0.max(q.0) < x0 || p.0.min(q.0) > x1 || p.1.max(q.1) < y0 || p.1.min(q.1) > y1 {
+        return false;
+    }
+    let sides = [(x0, y0), (x1, y0), (x1, y1), (x0, y1)].map(|c| orient(p, q, c).signum());
+    !(sides.iter().all(|s| *s > 0) || sides.iter().all(|s| *s < 0))
+}
+
+/// Split every segment at the centres of the hot pixels it meets, `hot` being sorted
+fn snap(segments: &[Segment], hot: &[Point]) -> Vec<Segment> {
+    let mut snapped = Vec::with_capacity(segments.len());
+    for s in segments {
+        // Pixels met by a segment between grid points are centred in its bounding box
+        let (x0, x1) = (s.p.0.min(s.q.0), s.p.0.max(s.q.0));
+        let (y0, y1) = (s.p.1.min(s.q.1), s.p.1.max(s.q.1));
+        let first = hot.partition_point(|h| h.0 < x0);
+        let mut through: Vec<Point> = hot[first..].iter()
+            .take_while(|h| h.0 <= x1)
+            .filter(|h| h.1 >= y0 && h.1 <= y1 && meets_pixel(s.p, s.q, **h))
+            .copied()
+            .collect();
+
+        let direction = sub(s.q, s.p);
+        through.sort_unstable_by_key(|h| (dot(sub(*h, s.p), direction), *h));
+        through.dedup();
+        snapped.extend(through.windows(2).map(|w| Segment { p: w[0], q: w[1], operand: s.operand }));
+    }
+    snapped
+}
+
+/// Edge of the arrangement, from its lowest end point to its highest one, left to right
+/// when horizontal
+#[derive(Debug, Clone)]
+struct Edge {
+    a: Point,
+    b: Point,
+    /// Winding change of each operand when the edge is crossed from right to left
+    delta: [i32; 2],
+    /// Winding of each operand on the left side
+    left: [i32; 2],
+}
+
+impl Edge {
+    fn right(&self) -> [i32; 2] {
+        [self.left[0] - self.delta[0], self.left[1] - self.delta[1]]
+    }
+
+    /// Numerator and denominator of the abscissa at `y`, for a non horizontal edge
+    fn x_at(&self, y: i64) -> (i128, i128) {
+        let (dx, dy) = ((self.b.0 - self.a.0) as i128, (self.b.1 - self.a.1) as i128);
+        (self.a.0 as i128 * dy + (y - self.a.1) as i128 * dx, dy)
+    }
+
+    /// Order of two non horizontal edges just above `y`
+    fn cmp_above(&self, other: &Self, y: i64) -> Ordering {
+        let ((n1, d1), (n2, d2)) = (self.x_at(y), other.x_at(y));
+        (n1 * d2).cmp(&(n2 * d1)).then_with(|| {
+            let (dx1, dy1) = ((self.b.0 - self.a.0) as i128, (self.b.1 - self.a.1) as i128);
+            let (dx2, dy2) = ((other.b.0 - other.a.0) as i128, (other.b.1 - other.a.1) as i128);
+            (dx1 * dy2).cmp(&(dx2 * dy1))
+        })
+    }
+}
+
+/// Merge the snapped segments into edges and sweep them bottom-up to find the windings
+/// on both sides of each edge
+fn arrangement(segments: Vec<Segment>) -> Vec<Edge> {
+    let key = |p: Point| (p.1, p.0);
+    let mut deltas: HashMap<(Point, Point), [i32; 2]> = HashMap::new();
+    for s in segments {
+        let (a, b, sign) = if key(s.p) < key(s.q) { (s.p, s.q, 1) } else { (s.q, s.p, -1) };
+        deltas.entry((a, b)).or_default()[s.operand] += sign;
+    }
+    let mut edges: Vec<Edge> = deltas.into_iter()
+        .filter(|(_, delta)| *delta != [0, 0])
+        .map(|((a, b), delta)| Edge { a, b, delta, left: [0, 0] })
+        .collect();
+    edges.sort_unstable_by_key(|e| (key(e.a), key(e.b)));
+
+    let mut ys: Vec<i64> = edges.iter().flat_map(|e| [e.a.1, e.b.1]).collect();
+    ys.sort_unstable();
+    ys.dedup();
+
+    let mut active: Vec<usize> = Vec::new();
+    let mut next = 0;
+    for y in ys {
+        let mut starting = Vec::new();
+        while next < edges.len() && edges[next].a.1 == y {
+            if edges[next].b.1 == y {
+                // Nothing crosses a horizontal edge, the winding below is the one right of
+                // the last edge reaching the scanline at or before its start
+                let x = edges[next].a.0 as i128;
+                let before = active.partition_point(|&j| {
+                    let (n, d) = edges[j].x_at(y);
+                    n <= x * d
+                });
+                let below = before.checked_sub(1).map_or([0, 0], |j| edges[active[j]].right());
+                let delta = edges[next].delta;
+                edges[next].left = [below[0] + delta[0], below[1] + delta[1]];
+            } else {
+                starting.push(next);
+            }
+            next += 1;
+        }
+
+        active.retain(|&j| edges[j].b.1 != y);
+        starting.sort_by(|&i, &j| edges[i].cmp_above(&edges[j], y));
+        for i in starting {
+            let at = active.partition_point(|&j| edges[j].cmp_above(&edges[i], y) == Ordering::Less);
+            edges[i].left = at.checked_sub(1).map_or([0, 0], |j| edges[active[j]].right());
+            active.insert(at, i);
+        }
+    }
+    edges
+}
+
+/// Directed edges between the inside and the outside of the result, inside on the left
+fn boundary<F: Fn(bool, bool) -> bool>(edges: Vec<Edge>, op: F) -> Vec<(Point, Point)> {
+    let inside = |w: [i32; 2]| op(w[0] != 0, w[1] != 0);
+    edges.into_iter()
+        .filter_map(|e| match (inside(e.left), inside(e.right())) {
+            (true, false) => Some((e.a, e.b)),
+            (false, true) => Some((e.b, e.a)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Orders directions by how far they turn left from `incoming`, right turns first
+fn turn_cmp(incoming: Point, u: Point, v: Point) -> Ordering {
+    let half = |w: Point| {
+        let (c, d) = (cross(incoming, w), dot(incoming, w));
+        match c.cmp(&0) {
+            Ordering::Less => 0,
+            Ordering::Equal if d > 0 => 1,
+            Ordering::Greater => 2,
+            Ordering::Equal => 3,
+        }
+    };
+    half(u).cmp(&half(v)).then_with(|| 0.cmp(&cross(u, v)))
+}
+
+/// Links directed segments into rings, taking the leftmost turn where several continue
+fn chain(segments: &[(Point, Point)]) -> Vec<Vec<GdsDbCoord>> {
+    let mut outgoing: HashMap<Point, Vec<usize>> = HashMap::new();
+    for (i, (p, _)) in segments.iter().enumerate() {
+        outgoing.entry(*p).or_default().push(i);
+    }
+
+    let mut used = vec![false; segments.len()];
+    let mut rings = Vec::new();
+    for first in 0..segments.len() {
+        if used[first] {
+            continue;
+        }
+        used[first] = true;
+        let origin = segments[first].0;
+        let mut ring = vec![origin];
+        let mut current = first;
+        loop {
+            let (p, q) = segments[current];
+            if q == origin {
+                break;
+            }
+            ring.push(q);
+            let incoming = sub(q, p);
+            let next = outgoing.get(&q).and_then(|candidates| {
+                candidates.iter()
+                    .filter(|i| !used[**i])
+                    .max_by(|i, j| turn_cmp(incoming, sub(segments[**i].1, q), sub(segments[**j].1, q)))
+                    .copied()
+            });
+            match next {
+                Some(i) => {
+                    used[i] = true;
+                    current = i;
+                }
+                None => break,
+            }
+        }
+
+        let ring = simplify(ring);
+        if ring.len() >= 3 {
+            rings.push(canonical(ring));
+        }
+    }
+
+    let key = |p: &Point| (p.1, p.0);
+    rings.sort_by(|r, s| r.iter().map(key).cmp(s.iter().map(key)));
+    rings.into_iter()
+        .map(|ring| ring.into_iter().map(|(x, y)| GdsDbCoord::new(x as i32, y as i32)).collect::<Vec<_>>())
+        .filter(|ring| signed_area2(ring) != 0)
+        .collect()
+}
+
+/// The ring rotated to start at its lowest, then leftmost, point
+fn canonical(mut ring: Vec<Point>) -> Vec<Point> {
+    let n = ring.len();
+    let key = |i: usize| ((ring[i].1, ring[i].0), (ring[(i + 1) % n].1, ring[(i + 1) % n].0));
+    let start = (0..n).min_by_key(|&i| key(i)).unwrap_or(0);
+    ring.rotate_left(start);
+    ring
+}
+
+fn collinear(a: Point, b: Point, c: Point) -> bool {
+    orient(a, b, c) == 0
+}
+
+/// Drops repeated points, collinear points and zero width spikes
+fn simplify(ring: Vec<Point>) -> Vec<Point> {
+    let mut ring = ring;
+    loop {
+        let before = ring.len();
+        let mut kept: Vec<Point> = Vec::with_capacity(ring.len());
+        for p in ring {
+            while kept.len() >= 2 && collinear(kept[kept.len() - 2], kept[kept.len() - 1], p) {
+                kept.pop();
+            }
+            if kept.last() != Some(&p) {
+                kept.push(p);
+            }
+        }
+        while kept.len() >= 3 {
+            let n = kept.len();
+            if collinear(kept[n - 2], kept[n - 1], kept[0]) || kept[n - 1] == kept[0] {
+                kept.pop();
+            } else if collinear(kept[n - 1], kept[0], kept[1]) {
+                kept.remove(0);
+            } else {
+                break;
+            }
+        }
+        ring = kept;
+        if ring.len() < 3 || ring.len() == before {
+            return ring;
+        }
+    }
+}
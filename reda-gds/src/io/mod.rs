@@ -1,6 +1,295 @@
 mod read;
 mod write;
 mod record;
+mod oasis;
 
 pub use read::*;
-pub use write::*;
\ No newline at end of file
+pub use write::*;
+pub use oasis::*;
+pub use record::*;
+
+#[cfg(test)]
+mod tests {
+    use indexmap::IndexMap;
+    use std::sync::{Arc, RwLock};
+    use std::io::Read;
+    use crate::*;
+
+    fn library_with_properties() -> GdsLibrary {
+        let mut structure = GdsStructure::new("top");
+        structure.boundarys.push(GdsBoundaryBuilder::default()
+            .layer(1)
+            .data_type(0)
+            .xy(vec![GdsDbCoord::new(0, 0), GdsDbCoord::new(10, 0), GdsDbCoord::new(10, 10), GdsDbCoord::new(0, 0)])
+            .properties(vec![(1, "net=vdd".to_string()), (2, "odd".to_string())])
+            .build().unwrap());
+        structure.paths.push(GdsPathBuilder::default()
+            .layer(2)
+            .data_type(0)
+            .width(4)
+            .xy(vec![GdsDbCoord::new(0, 0), GdsDbCoord::new(10, 0)])
+            .properties(vec![(5, "path".to_string())])
+            .build().unwrap());
+        structure.arefs.push(GdsArefBuilder::default()
+            .s_name("child".to_string())
+            .col(2)
+            .row(3)
+            .xy(vec![GdsDbCoord::new(0, 0), GdsDbCoord::new(20, 0), GdsDbCoord::new(0, 30)])
+            .properties(vec![(7, "array".to_string())])
+            .build().unwrap());
+        structure.nodes.push(GdsNodeBuilder::default()
+            .layer(3)
+            .node_type(1)
+            .xy(vec![GdsDbCoord::new(5, 5)])
+            .properties(vec![(9, "n".to_string())])
+            .build().unwrap());
+
+        let date = GdsDateTime { year: 2025, month: 1, day: 2, hour: 3, minute: 4, second: 5 };
+        structure.create_date = date.clone();
+        structure.modify_date = date.clone();
+
+        let mut structures = IndexMap::new();
+        structures.insert(structure.name.clone(), Arc::new(RwLock::new(structure)));
+        GdsLibraryBuilder::default()
+            .version(600)
+            .create_date(date.clone())
+            .modify_date(date)
+            .name("lib".to_string())
+            .usrunits_per_dbunit(1e-3)
+            .meters_per_dbunit(1e-9)
+            .structures(structures)
+            .build().unwrap()
+    }
+
+    #[test]
+    fn test_properties_round_trip() {
+        let bytes = library_with_properties().to_bytes().unwrap();
+        let lib = GdsLibrary::from_bytes(&bytes).unwrap();
+
+        let top = lib.structures["top"].read().unwrap();
+        assert_eq!(top.boundarys[0].properties, vec![(1, "net=vdd".to_string()), (2, "odd".to_string())]);
+        assert_eq!(top.paths[0].width, Some(4));
+        assert_eq!(top.paths[0].properties, vec![(5, "path".to_string())]);
+        assert_eq!(top.arefs[0].s_name, "child");
+        assert_eq!(top.arefs[0].properties, vec![(7, "array".to_string())]);
+        assert_eq!(top.nodes[0].properties, vec![(9, "n".to_string())]);
+    }
+
+    #[test]
+    fn test_read_non_seekable_stream() {
+        let bytes = library_with_properties().to_bytes().unwrap();
+
+        // A chained reader is neither seekable nor able to fill more than a few bytes at a time
+        let (head, tail) = bytes.split_at(7);
+        let mut reader = GdsReader::new(head.chain(tail));
+        let lib = reader.read().unwrap();
+
+        assert_eq!(reader.position(), bytes.len() as u64);
+        assert_eq!(lib.name, "lib");
+        assert_eq!(lib.structures["top"].read().unwrap().boundarys.len(), 1);
+    }
+
+    #[test]
+    fn test_stream_events() {
+        let bytes = library_with_properties().to_bytes().unwrap();
+        let mut reader = GdsReader::new(bytes.as_slice());
+        let mut writer = GdsWriter::new(Vec::new());
+
+        let mut elements = 0;
+        while let Some(event) = reader.next_event().unwrap() {
+            match event {
+                GdsEvent::Library(lib) => {
+                    assert!(lib.structures.is_empty());
+                    writer.write_header(&lib).unwrap();
+                    writer.write_begin_library(&lib).unwrap();
+                    writer.write_library_name(&lib).unwrap();
+                    writer.write_library_options(&lib).unwrap();
+                    writer.write_units(&lib).unwrap();
+                }
+                GdsEvent::BeginStructure(s) => {
+                    writer.write_structure_begin(&s).unwrap();
+                    writer.write_structure_name(&s).unwrap();
+                }
+                GdsEvent::Element(e) => {
+                    elements += 1;
+                    writer.write_element(&e).unwrap();
+                }
+                GdsEvent::EndStructure => writer.write_structure_end().unwrap(),
+                GdsEvent::EndLibrary => writer.write_end_library().unwrap(),
+            }
+        }
+
+        assert_eq!(elements, 4);
+        assert!(reader.next_event().unwrap().is_none());
+        assert!(matches!(reader.read(), Err(GdsReadError::NotAtStart)));
+        assert_eq!(writer.into_inner(), GdsLibrary::from_bytes(&bytes).unwrap().to_bytes().unwrap());
+    }
+
+    #[test]
+    fn test_write_with_validation() {
+        let lib = library_with_properties();
+        assert!(GdsWriter::new(Vec::new()).write(&lib).is_ok());
+
+        let mut writer = GdsWriter::new(Vec::new()).with_validation(true);
+        match writer.write(&lib) {
+            Err(GdsWriteError::Invalid(diagnostics)) => {
+                assert_eq!(diagnostics.len(), 1);
+                assert_eq!(diagnostics[0].issue, GdsIssue::MissingStructure("child".to_string()));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(writer.into_inner().is_empty());
+    }
+
+    #[test]
+    fn test_write_oversized_record() {
+        let lib = library_with_properties();
+        lib.structures["top"].write().unwrap().boundarys[0].xy = vec![GdsDbCoord::new(0, 0); MAX_XY_POINTS + 1];
+        assert!(matches!(lib.to_bytes(), Err(GdsWriteError::RecordTooLong(GdsRecordType::Xy, 65540))));
+    }
+
+    #[test]
+    fn test_real_encoding() {
+        let lib = library_with_properties();
+        let mut writer = GdsWriter::new(Vec::new());
+        writer.write_units(&lib).unwrap();
+        let bytes = writer.into_inner();
+        assert_eq!(bytes[4..12], [0x3E, 0x41, 0x89, 0x37, 0x4B, 0xC6, 0xA7, 0xF0]);
+        assert_eq!(bytes[12..20], [0x39, 0x44, 0xB8, 0x2F, 0xA0, 0x9B, 0x5A, 0x54]);
+
+        // Reals written by other tools may carry more bits than an `f64`, they are rounded
+        let mut library_bytes = lib.to_bytes().unwrap();
+        let at = library_bytes.windows(8).position(|w| w == &bytes[4..12]).unwrap();
+        library_bytes[at + 7] = 0xEF;
+        let read = GdsLibrary::from_bytes(&library_bytes).unwrap();
+        assert_eq!(read.usrunits_per_dbunit, 1e-3);
+        assert_eq!(read.meters_per_dbunit, 1e-9);
+    }
+
+    #[test]
+    fn test_byte_identical_round_trip() {
+        for name in ["dff", "cell_1rw", "sense_amp"] {
+            let path = format!("{}/data/cells/{}.gds", env!("CARGO_MANIFEST_DIR"), name);
+            let bytes = std::fs::read(path).unwrap();
+            assert_eq!(GdsLibrary::from_bytes(&bytes).unwrap().to_bytes().unwrap(), bytes, "{}", name);
+        }
+
+        // Paths with and without PATHTYPE and extensions, an explicit identity MAG and ANGLE
+        let record = |record_type: u16, data: &[u8]| {
+            let mut record = ((data.len() + 4) as u16).to_be_bytes().to_vec();
+            record.extend_from_slice(&record_type.to_be_bytes());
+            record.extend_from_slice(data);
+            record
+        };
+        let xy: Vec<u8> = [0i32, 0, 100, 0, 100, 50].iter().flat_map(|v| v.to_be_bytes()).collect();
+        let elements = [
+            record(0x0900, &[]), record(0x0D02, &[0, 1]), record(0x0E02, &[0, 0]),
+            record(0x0F03, &10i32.to_be_bytes()), record(0x1003, &xy), record(0x1100, &[]),
+            record(0x0900, &[]), record(0x0D02, &[0, 1]), record(0x0E02, &[0, 0]), record(0x2102, &[0, 0]),
+            record(0x0F03, &10i32.to_be_bytes()), record(0x3003, &3i32.to_be_bytes()), record(0x3103, &(-2i32).to_be_bytes()),
+            record(0x1003, &xy), record(0x1100, &[]),
+            record(0x0A00, &[]), record(0x1206, b"leaf"), record(0x1A01, &[0, 0]),
+            record(0x1B05, &[0x41, 0x10, 0, 0, 0, 0, 0, 0]), record(0x1C05, &[0; 8]),
+            record(0x1003, &xy[..8]), record(0x1100, &[]),
+        ].concat();
+        let path = format!("{}/data/cells/dff.gds", env!("CARGO_MANIFEST_DIR"));
+        let mut bytes = std::fs::read(path).unwrap();
+        let endstr = bytes.windows(4).rposition(|w| w == [0, 4, 0x07, 0x00]).unwrap();
+        bytes.splice(endstr..endstr, elements);
+        let library = GdsLibrary::from_bytes(&bytes).unwrap();
+        assert_eq!(library.to_bytes().unwrap(), bytes);
+        let structure = library.structures.values().last().unwrap().read().unwrap();
+        let paths = &structure.paths[structure.paths.len() - 2..];
+        assert_eq!((paths[0].path_type, paths[0].bgn_extn), (None, None));
+        assert_eq!((paths[1].path_type, paths[1].bgn_extn, paths[1].end_extn), (Some(GdsPathType::SquareEnd), Some(3), Some(-2)));
+        let transform = structure.srefs.last().unwrap().transform.unwrap();
+        assert_eq!((transform.magnification, transform.angle), (Some(1.0), Some(0.0)));
+    }
+
+    #[test]
+    fn test_element_and_structure_order() {
+        let mut lib = library_with_properties();
+        let mut first = GdsStructure::new("first");
+        first.push_element(GdsTextBuilder::default()
+            .layer(1).text_type(0).string("a".to_string())
+            .xy(vec![GdsDbCoord::new(0, 0)])
+            .build().unwrap());
+        first.push_element(GdsSrefBuilder::default()
+            .s_name("top".to_string())
+            .xy(vec![GdsDbCoord::new(0, 0)])
+            .build().unwrap());
+        first.push_element(GdsTextBuilder::default()
+            .layer(2).text_type(0).string("b".to_string())
+            .xy(vec![GdsDbCoord::new(0, 0)])
+            .build().unwrap());
+        lib.structures.insert("first".to_string(), Arc::new(RwLock::new(first)));
+
+        let bytes = lib.to_bytes().unwrap();
+        let read = GdsLibrary::from_bytes(&bytes).unwrap();
+        assert_eq!(read.structures.keys().collect::<Vec<_>>(), ["top", "first"]);
+        let first = read.structures["first"].read().unwrap();
+        assert_eq!(first.element_order, [GdsElementKind::Text, GdsElementKind::Sref, GdsElementKind::Text]);
+        assert_eq!(first.texts[0].path_type, None);
+        assert_eq!(read.to_bytes().unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_tolerant_reader() {
+        let raw = |record_type: u16, data: &[u8]| GdsRawRecord { record_type, data: data.to_vec(), position: None };
+        let mut lib = library_with_properties();
+        lib.extra_records = vec![raw(0x3902, &[0, 1]), raw(0x3706, b"ME")];
+        {
+            let mut top = lib.structures["top"].write().unwrap();
+            top.extra_records = vec![raw(0x3401, &[0, 0])];
+            // ELKEY is not modelled, WIDTH is not expected in a boundary. An unknown record
+            // between LAYER and DATATYPE keeps its place.
+            top.boundarys[0].extra_records = vec![raw(0x2703, &[0, 0, 0, 7]), raw(0x0F03, &[0, 0, 0, 2])];
+            top.boundarys[0].extra_records.push(GdsRawRecord { position: Some(2), ..raw(0x7702, &[0, 1]) });
+        }
+        let bytes = lib.to_bytes().unwrap();
+
+        assert!(GdsLibrary::from_bytes(&bytes).is_err());
+
+        let mut reader = GdsReader::new(bytes.as_slice()).with_tolerance(true);
+        let read = reader.read().unwrap();
+        let warnings = reader.warnings();
+        assert_eq!(warnings.len(), 6);
+        assert_eq!(warnings[0].offset, 6 + 28);
+        assert_eq!((warnings[0].record_type, warnings[0].size), (0x3902, 6));
+        assert_eq!(warnings[4].to_string(), format!("skipped unknown record 0x2703 of 8 bytes at offset {}", warnings[4].offset));
+        for warning in warnings {
+            let at = warning.offset as usize;
+            assert_eq!(u16::from_be_bytes([bytes[at + 2], bytes[at + 3]]), warning.record_type);
+        }
+
+        assert_eq!(read.extra_records, lib.extra_records);
+        let positions: Vec<_> = read.structures["top"].read().unwrap().boundarys[0].extra_records.iter()
+            .map(|record| record.position)
+            .collect();
+        assert_eq!(positions, [Some(2), Some(9), Some(10)]);
+        assert_eq!(read.to_bytes().unwrap(), bytes);
+
+        lib.extra_records = vec![raw(0x3902, &[0; 65532])];
+        assert!(matches!(lib.to_bytes(), Err(GdsWriteError::RawRecordTooLong(0x3902, 65532))));
+    }
+
+    #[test]
+    fn test_error_location() {
+        let bytes = library_with_properties().to_bytes().unwrap();
+        // XY header of the boundary, the first element of `top`
+        let xy = bytes.windows(4).position(|w| w == [0x00, 0x24, 0x10, 0x03]).unwrap();
+
+        let error = GdsLibrary::from_bytes(&bytes[..xy + 10]).unwrap_err();
+        let location = error.location().unwrap();
+        assert_eq!(location.offset, xy as u64);
+        assert_eq!(location.record_type, Some(GdsRecordType::Xy));
+        assert_eq!(location.structure.as_deref(), Some("top"));
+        assert_eq!(location.element, Some(0));
+        assert!(error.to_string().starts_with(&format!("At offset {} (Xy record) in structure 'top' element #0", xy)));
+
+        let mut corrupted = bytes.clone();
+        corrupted[2..4].copy_from_slice(&[0x77, 0x77]);
+        let error = GdsLibrary::from_bytes(&corrupted).unwrap_err();
+        assert_eq!(error.location(), Some(&GdsReadLocation::default()));
+    }
+}
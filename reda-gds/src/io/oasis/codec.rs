@@ -0,0 +1,538 @@
+use super::{OasisReadError, OasisReadResult};
+
+pub(super) type Delta = (i64, i64);
+
+/// Offsets of the elements of a repetition
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Repetition {
+    /// `cols` x `rows` lattice
+    Grid { cols: u64, rows: u64, col_step: Delta, row_step: Delta },
+    /// Any list of offsets, the first one is `(0, 0)`
+    Positions(Vec<Delta>),
+}
+
+impl Repetition {
+    /// Number of elements, the count of a grid is checked not to overflow when read
+    pub fn len(&self) -> u64 {
+        match self {
+            Self::Grid { cols, rows, .. } => cols * rows,
+            Self::Positions(offsets) => offsets.len() as u64,
+        }
+    }
+
+    pub fn offsets(&self) -> Vec<Delta> {
+        match self {
+            Self::Grid { cols, rows, col_step, row_step } => {
+                // Not preallocated, the counts come from the file
+                let mut offsets = Vec::new();
+                for r in 0..*rows as i64 {
+                    for c in 0..*cols as i64 {
+                        offsets.push((c * col_step.0 + r * row_step.0, c * col_step.1 + r * row_step.1));
+                    }
+                }
+                offsets
+            }
+            Self::Positions(offsets) => offsets.clone(),
+        }
+    }
+}
+
+/// Direction of octangular deltas: east, north, west, south, north-east, north-west,
+/// south-west, south-east
+const DIRECTIONS: [Delta; 8] = [(1, 0), (0, 1), (-1, 0), (0, -1), (1, 1), (-1, 1), (-1, -1), (1, -1)];
+
+/// Reads the OASIS primitive types from a byte slice
+pub(super) struct OasisBytes<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> OasisBytes<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.position >= self.data.len()
+    }
+
+    fn remaining(&self) -> usize {
+        self.data.len().saturating_sub(self.position)
+    }
+
+    pub fn byte(&mut self) -> OasisReadResult<u8> {
+        let byte = *self.data.get(self.position).ok_or(OasisReadError::UnexpectedEnd(self.position))?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    pub fn bytes(&mut self, count: usize) -> OasisReadResult<&'a [u8]> {
+        let end = self.position.checked_add(count)
+            .filter(|end| *end <= self.data.len())
+            .ok_or(OasisReadError::UnexpectedEnd(self.data.len()))?;
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    /// Little endian groups of 7 bits, the high bit of a byte flags a following byte
+    pub fn uint(&mut self) -> OasisReadResult<u64> {
+        let start = self.position;
+        let mut value = 0u64;
+        let mut shift = 0;
+        loop {
+            let byte = self.byte()?;
+            let bits = (byte & 0x7f) as u64;
+            if shift >= 64 || (shift > 0 && bits >> (64 - shift) != 0) {
+                return Err(OasisReadError::InvalidInteger(start));
+            }
+            value |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+
+    /// Unsigned integer holding the sign in its lowest bit
+    pub fn sint(&mut self) -> OasisReadResult<i64> {
+        let value = self.uint()?;
+        let magnitude = (value >> 1) as i64;
+        Ok(if value & 1 == 1 { -magnitude } else { magnitude })
+    }
+
+    pub fn real(&mut self) -> OasisReadResult<f64> {
+        let tp = self.uint()?;
+        self.real_of_type(tp)
+    }
+
+    pub fn real_of_type(&mut self, tp: u64) -> OasisReadResult<f64> {
+        let value = match tp {
+            0 => self.uint()? as f64,
+            1 => -(self.uint()? as f64),
+            2 => 1.0 / self.uint()? as f64,
+            3 => -1.0 / self.uint()? as f64,
+            4 => self.uint()? as f64 / self.uint()? as f64,
+            5 => -(self.uint()? as f64 / self.uint()? as f64),
+            6 => f32::from_le_bytes(self.bytes(4)?.try_into().unwrap()) as f64,
+            7 => f64::from_le_bytes(self.bytes(8)?.try_into().unwrap()),
+            _ => return Err(OasisReadError::InvalidType("real", tp)),
+        };
+        Ok(value)
+    }
+
+    pub fn bstring(&mut self) -> OasisReadResult<Vec<u8>> {
+        let length = self.uint()? as usize;
+        Ok(self.bytes(length)?.to_vec())
+    }
+
+    pub fn string(&mut self) -> OasisReadResult<String> {
+        Ok(String::from_utf8(self.bstring()?)?)
+    }
+
+    /// Displacement of a repetition or of an all-angle point list
+    pub fn g_delta(&mut self) -> OasisReadResult<Delta> {
+        let value = self.uint()?;
+        if value & 1 == 0 {
+            let (dx, dy) = DIRECTIONS[((value >> 1) & 7) as usize];
+            let magnitude = (value >> 4) as i64;
+            Ok((dx * magnitude, dy * magnitude))
+        } else {
+            let magnitude = (value >> 2) as i64;
+            let x = if value & 2 == 2 { -magnitude } else { magnitude };
+            Ok((x, self.sint()?))
+        }
+    }
+
+    /// Vertices relative to the first one, which is `(0, 0)` and included.
+    ///
+    /// Polygons close implicitly, the manhattan lists of a polygon also omit the vertex
+    /// before the closing edge.
+    pub fn point_list(&mut self, polygon: bool) -> OasisReadResult<Vec<Delta>> {
+        let tp = self.uint()?;
+        let start = self.position;
+        let count = self.uint()? as usize;
+        // Every point takes at least one byte
+        let mut points = Vec::with_capacity(count.min(self.remaining()) + 2);
+        points.push((0, 0));
+        let mut current = (0i64, 0i64);
+        let mut last_delta = (0i64, 0i64);
+        for i in 0..count {
+            let delta = match tp {
+                0 | 1 => {
+                    let horizontal = (i % 2 == 0) == (tp == 0);
+                    let value = self.sint()?;
+                    if horizontal { (value, 0) } else { (0, value) }
+                }
+                2 => {
+                    let value = self.uint()?;
+                    let (dx, dy) = DIRECTIONS[(value & 3) as usize];
+                    ((value >> 2) as i64 * dx, (value >> 2) as i64 * dy)
+                }
+                3 => {
+                    let value = self.uint()?;
+                    let (dx, dy) = DIRECTIONS[(value & 7) as usize];
+                    ((value >> 3) as i64 * dx, (value >> 3) as i64 * dy)
+                }
+                4 => self.g_delta()?,
+                5 => add(last_delta, self.g_delta()?).ok_or(OasisReadError::InvalidInteger(start))?,
+                _ => return Err(OasisReadError::InvalidType("point list", tp)),
+            };
+            last_delta = delta;
+            current = add(current, delta).ok_or(OasisReadError::InvalidInteger(start))?;
+            points.push(current);
+        }
+
+        if polygon && (tp == 0 || tp == 1) {
+            let horizontal = count.is_multiple_of(2) == (tp == 0);
+            points.push(if horizontal { (0, current.1) } else { (current.0, 0) });
+        }
+        Ok(points)
+    }
+
+    /// `None` for the type 0 repetition, which reuses the previous one
+    pub fn repetition(&mut self) -> OasisReadResult<Option<Repetition>> {
+        let start = self.position;
+        let overflow = || OasisReadError::InvalidInteger(start);
+        let tp = self.uint()?;
+        let repetition = match tp {
+            0 => return Ok(None),
+            1 => {
+                let (cols, rows) = (self.count(2)?, self.count(2)?);
+                let (dx, dy) = (self.uint()? as i64, self.uint()? as i64);
+                Repetition::Grid { cols, rows, col_step: (dx, 0), row_step: (0, dy) }
+            }
+            2 => {
+                let cols = self.count(2)?;
+                Repetition::Grid { cols, rows: 1, col_step: (self.uint()? as i64, 0), row_step: (0, 0) }
+            }
+            3 => {
+                let rows = self.count(2)?;
+                Repetition::Grid { cols: 1, rows, col_step: (0, 0), row_step: (0, self.uint()? as i64) }
+            }
+            4..=7 => {
+                let count = self.count(1)?;
+                let grid = match tp { 5 | 7 => self.uint()? as i64, _ => 1 };
+                let mut offsets = vec![(0, 0)];
+                let mut position = 0i64;
+                for _ in 0..count {
+                    position = (self.uint()? as i64).checked_mul(grid)
+                        .and_then(|step| position.checked_add(step))
+                        .ok_or_else(overflow)?;
+                    offsets.push(if tp <= 5 { (position, 0) } else { (0, position) });
+                }
+                Repetition::Positions(offsets)
+            }
+            8 => {
+                let (cols, rows) = (self.count(2)?, self.count(2)?);
+                Repetition::Grid { cols, rows, col_step: self.g_delta()?, row_step: self.g_delta()? }
+            }
+            9 => {
+                let cols = self.count(2)?;
+                Repetition::Grid { cols, rows: 1, col_step: self.g_delta()?, row_step: (0, 0) }
+            }
+            10 | 11 => {
+                let count = self.count(1)?;
+                let grid = if tp == 11 { self.uint()? as i64 } else { 1 };
+                let mut offsets = vec![(0, 0)];
+                let mut position = (0, 0);
+                for _ in 0..count {
+                    let (dx, dy) = self.g_delta()?;
+                    position = dx.checked_mul(grid).zip(dy.checked_mul(grid))
+                        .and_then(|step| add(position, step))
+                        .ok_or_else(overflow)?;
+                    offsets.push(position);
+                }
+                Repetition::Positions(offsets)
+            }
+            _ => return Err(OasisReadError::InvalidType("repetition", tp)),
+        };
+        if let Repetition::Grid { cols, rows, col_step, row_step } = repetition {
+            // The count and every offset of the lattice must fit in 64 bits
+            let extent = |c: i64, r: i64| (cols as u128 - 1) * c.unsigned_abs() as u128 + (rows as u128 - 1) * r.unsigned_abs() as u128;
+            let fits = cols.checked_mul(rows).is_some()
+                && extent(col_step.0, row_step.0) <= i64::MAX as u128
+                && extent(col_step.1, row_step.1) <= i64::MAX as u128;
+            if !fits {
+                return Err(overflow());
+            }
+        }
+        Ok(Some(repetition))
+    }
+
+    /// Repetition count stored `offset` less than its value
+    fn count(&mut self, offset: u64) -> OasisReadResult<u64> {
+        let start = self.position;
+        self.uint()?.checked_add(offset).ok_or(OasisReadError::InvalidInteger(start))
+    }
+
+    /// Layer or datatype interval of a LAYERNAME record, only skipped
+    pub fn interval(&mut self) -> OasisReadResult<()> {
+        match self.uint()? {
+            0 => {}
+            1..=3 => { self.uint()?; }
+            4 => { self.uint()?; self.uint()?; }
+            tp => return Err(OasisReadError::InvalidType("interval", tp)),
+        }
+        Ok(())
+    }
+}
+
+pub(super) fn put_uint(buf: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buf.push(byte);
+            return;
+        }
+        buf.push(byte | 0x80);
+    }
+}
+
+pub(super) fn put_sint(buf: &mut Vec<u8>, value: i64) {
+    put_uint(buf, (value.unsigned_abs() << 1) | (value < 0) as u64);
+}
+
+/// Whole numbers are written as integers, other values as IEEE doubles
+pub(super) fn put_real(buf: &mut Vec<u8>, value: f64) {
+    if value.fract() == 0.0 && value.abs() < (1u64 << 53) as f64 {
+        put_uint(buf, (value < 0.0) as u64);
+        put_uint(buf, value.abs() as u64);
+    } else {
+        put_uint(buf, 7);
+        buf.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+pub(super) fn put_string(buf: &mut Vec<u8>, value: &[u8]) {
+    put_uint(buf, value.len() as u64);
+    buf.extend_from_slice(value);
+}
+
+fn add(a: Delta, b: Delta) -> Option<Delta> {
+    Some((a.0.checked_add(b.0)?, a.1.checked_add(b.1)?))
+}
+
+/// Index in `DIRECTIONS` and length of an octangular delta
+fn octangular(delta: Delta) -> Option<(u64, u64)> {
+    let magnitude = delta.0.unsigned_abs().max(delta.1.unsigned_abs());
+    if magnitude == 0 {
+        return Some((0, 0));
+    }
+    let unit = (delta.0.signum(), delta.1.signum());
+    let scaled = (unit.0 * magnitude as i64, unit.1 * magnitude as i64);
+    match scaled == delta {
+        true => DIRECTIONS.iter().position(|d| *d == unit).map(|i| (i as u64, magnitude)),
+        false => None,
+    }
+}
+
+pub(super) fn put_g_delta(buf: &mut Vec<u8>, delta: Delta) {
+    match octangular(delta) {
+        Some((direction, magnitude)) => put_uint(buf, (magnitude << 4) | (direction << 1)),
+        None => {
+            put_uint(buf, (delta.0.unsigned_abs() << 2) | (((delta.0 < 0) as u64) << 1) | 1);
+            put_sint(buf, delta.1);
+        }
+    }
+}
+
+/// Point list of the vertices, polygons are closed implicitly and must not repeat the
+/// first vertex. The most compact of the manhattan, octangular and all-angle lists is used.
+pub(super) fn put_point_list(buf: &mut Vec<u8>, points: &[Delta]) {
+    let deltas: Vec<Delta> = points.windows(2).map(|w| (w[1].0 - w[0].0, w[1].1 - w[0].1)).collect();
+    let octangulars: Option<Vec<(u64, u64)>> = deltas.iter().map(|d| octangular(*d)).collect();
+    match octangulars {
+        Some(octangulars) if octangulars.iter().all(|(direction, _)| *direction < 4) => {
+            put_uint(buf, 2);
+            put_uint(buf, deltas.len() as u64);
+            for (direction, magnitude) in octangulars {
+                put_uint(buf, (magnitude << 2) | direction);
+            }
+        }
+        Some(octangulars) => {
+            put_uint(buf, 3);
+            put_uint(buf, deltas.len() as u64);
+            for (direction, magnitude) in octangulars {
+                put_uint(buf, (magnitude << 3) | direction);
+            }
+        }
+        None => {
+            put_uint(buf, 4);
+            put_uint(buf, deltas.len() as u64);
+            for delta in deltas {
+                put_g_delta(buf, delta);
+            }
+        }
+    }
+}
+
+/// The repetition must have at least two elements
+pub(super) fn put_repetition(buf: &mut Vec<u8>, repetition: &Repetition) {
+    match repetition {
+        Repetition::Grid { cols, rows, col_step, row_step } => {
+            let orthogonal = col_step.1 == 0 && row_step.0 == 0 && col_step.0 >= 0 && row_step.1 >= 0;
+            match (orthogonal, *cols, *rows) {
+                (true, 2.., 2..) => {
+                    put_uint(buf, 1);
+                    put_uint(buf, cols - 2);
+                    put_uint(buf, rows - 2);
+                    put_uint(buf, col_step.0 as u64);
+                    put_uint(buf, row_step.1 as u64);
+                }
+                (true, _, 1) => {
+                    put_uint(buf, 2);
+                    put_uint(buf, cols - 2);
+                    put_uint(buf, col_step.0 as u64);
+                }
+                (true, 1, _) => {
+                    put_uint(buf, 3);
+                    put_uint(buf, rows - 2);
+                    put_uint(buf, row_step.1 as u64);
+                }
+                (false, 2.., 2..) => {
+                    put_uint(buf, 8);
+                    put_uint(buf, cols - 2);
+                    put_uint(buf, rows - 2);
+                    put_g_delta(buf, *col_step);
+                    put_g_delta(buf, *row_step);
+                }
+                (false, _, 1) => {
+                    put_uint(buf, 9);
+                    put_uint(buf, cols - 2);
+                    put_g_delta(buf, *col_step);
+                }
+                _ => {
+                    put_uint(buf, 9);
+                    put_uint(buf, rows - 2);
+                    put_g_delta(buf, *row_step);
+                }
+            }
+        }
+        Repetition::Positions(offsets) => {
+            put_uint(buf, 10);
+            put_uint(buf, offsets.len() as u64 - 2);
+            for w in offsets.windows(2) {
+                put_g_delta(buf, (w[1].0 - w[0].0, w[1].1 - w[0].1));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_primitives_round_trip() {
+        let mut buf = Vec::new();
+        for value in [0, 127, 128, 16384, u64::MAX] {
+            put_uint(&mut buf, value);
+        }
+        for value in [0, -1, 63, -64, i64::MIN + 1] {
+            put_sint(&mut buf, value);
+        }
+        for value in [3.0, -2.0, 0.25, 1e-3] {
+            put_real(&mut buf, value);
+        }
+        for delta in [(5, 0), (-3, -3), (7, -2), (0, 0)] {
+            put_g_delta(&mut buf, delta);
+        }
+
+        let mut bytes = OasisBytes::new(&buf);
+        assert_eq!(bytes.uint().unwrap(), 0);
+        assert_eq!(bytes.uint().unwrap(), 127);
+        assert_eq!(bytes.uint().unwrap(), 128);
+        assert_eq!(bytes.uint().unwrap(), 16384);
+        assert_eq!(bytes.uint().unwrap(), u64::MAX);
+        for value in [0, -1, 63, -64, i64::MIN + 1] {
+            assert_eq!(bytes.sint().unwrap(), value);
+        }
+        for value in [3.0, -2.0, 0.25, 1e-3] {
+            assert_eq!(bytes.real().unwrap(), value);
+        }
+        for delta in [(5, 0), (-3, -3), (7, -2), (0, 0)] {
+            assert_eq!(bytes.g_delta().unwrap(), delta);
+        }
+        assert!(bytes.is_empty());
+        assert!(matches!(bytes.uint(), Err(OasisReadError::UnexpectedEnd(_))));
+    }
+
+    #[test]
+    fn test_point_lists() {
+        let polygons = [
+            vec![(0, 0), (10, 0), (10, 5), (0, 5)],
+            vec![(0, 0), (10, 0), (15, 5), (0, 5)],
+            vec![(0, 0), (10, 0), (12, 7), (0, 5)],
+        ];
+        for (tp, points) in polygons.iter().enumerate() {
+            let mut buf = Vec::new();
+            put_point_list(&mut buf, points);
+            assert_eq!(buf[0], tp as u8 + 2);
+            assert_eq!(&OasisBytes::new(&buf).point_list(true).unwrap(), points);
+        }
+
+        // Manhattan polygon list, horizontal first, the last vertex is implied
+        let mut buf = Vec::new();
+        put_uint(&mut buf, 0);
+        put_uint(&mut buf, 2);
+        put_sint(&mut buf, 10);
+        put_sint(&mut buf, 5);
+        let points = OasisBytes::new(&buf).point_list(true).unwrap();
+        assert_eq!(points, vec![(0, 0), (10, 0), (10, 5), (0, 5)]);
+    }
+
+    #[test]
+    fn test_repetitions() {
+        let repetitions = [
+            Repetition::Grid { cols: 3, rows: 2, col_step: (10, 0), row_step: (0, 20) },
+            Repetition::Grid { cols: 1, rows: 4, col_step: (0, 0), row_step: (0, 5) },
+            Repetition::Grid { cols: 2, rows: 2, col_step: (10, 3), row_step: (-1, 20) },
+            Repetition::Positions(vec![(0, 0), (4, 1), (9, -7)]),
+        ];
+        for repetition in repetitions {
+            let mut buf = Vec::new();
+            put_repetition(&mut buf, &repetition);
+            let read = OasisBytes::new(&buf).repetition().unwrap().unwrap();
+            assert_eq!(read.offsets(), repetition.offsets());
+        }
+
+        // Irregular x spacings on a grid of 5
+        let mut buf = Vec::new();
+        for value in [5, 1, 5, 1, 3] {
+            put_uint(&mut buf, value);
+        }
+        let read = OasisBytes::new(&buf).repetition().unwrap().unwrap();
+        assert_eq!(read.offsets(), vec![(0, 0), (5, 0), (20, 0)]);
+    }
+
+    #[test]
+    fn test_hostile_counts() {
+        // A point count far beyond the data
+        let mut buf = Vec::new();
+        put_uint(&mut buf, 4);
+        put_uint(&mut buf, u64::MAX);
+        assert!(matches!(OasisBytes::new(&buf).point_list(false), Err(OasisReadError::UnexpectedEnd(_))));
+
+        // Grid counts whose product or extent overflows
+        for values in [[1, u64::MAX, 0, 0, 0], [1, u64::MAX / 2, u64::MAX / 2, 0, 0], [1, 1 << 40, 0, 1 << 40, 0]] {
+            let mut buf = Vec::new();
+            for value in values {
+                put_uint(&mut buf, value);
+            }
+            assert!(matches!(OasisBytes::new(&buf).repetition(), Err(OasisReadError::InvalidInteger(_))));
+        }
+
+        // Irregular spacings summing past 64 bits
+        let mut buf = Vec::new();
+        for value in [4, 1, u64::MAX >> 1, u64::MAX >> 1] {
+            put_uint(&mut buf, value);
+        }
+        assert!(matches!(OasisBytes::new(&buf).repetition(), Err(OasisReadError::InvalidInteger(_))));
+    }
+}
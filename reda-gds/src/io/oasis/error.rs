@@ -0,0 +1,86 @@
+use std::string::FromUtf8Error;
+
+#[derive(Debug, thiserror::Error)]
+pub enum OasisReadError {
+    #[error("Io error '{0}'")]
+    Io(#[from] std::io::Error),
+
+    #[error("Parse utf8 failed '{0}'")]
+    Utf8(#[from] FromUtf8Error),
+
+    #[error("Missing OASIS magic string")]
+    InvalidMagic,
+
+    #[error("Unsupport OASIS version '{0}'")]
+    UnsupportVersion(String),
+
+    #[error("Unexpected end of data at '{0}' bytes")]
+    UnexpectedEnd(usize),
+
+    #[error("Integer too large at '{0}' bytes")]
+    InvalidInteger(usize),
+
+    #[error("Unsupport record id: '{0}'")]
+    UnsupportRecordId(u64),
+
+    #[error("Expect START record, but got '{0}'")]
+    MissingStart(u64),
+
+    #[error("Invalid {0} type '{1}'")]
+    InvalidType(&'static str, u64),
+
+    #[error("Unsupport compression type '{0}'")]
+    UnsupportCompression(u64),
+
+    #[error("Modal variable '{0}' used before being set")]
+    UndefinedModal(&'static str),
+
+    #[error("No {0} with reference number '{1}'")]
+    UnknownReference(&'static str, u64),
+
+    #[error("{0} '{1}' does not fit in GDSII")]
+    OutOfRange(&'static str, i64),
+
+    #[error("More than {0} elements once repetitions are expanded")]
+    TooManyElements(usize),
+
+    #[error("Element record outside of a cell")]
+    ElementOutsideCell,
+
+    #[error("Validation failed, expect signature '{0:#010x}', but got '{1:#010x}'")]
+    Validation(u32, u32),
+
+    #[error("When {0} >> {1}")]
+    Wrap(String, Box<OasisReadError>)
+}
+
+impl OasisReadError {
+    pub fn wrap<S: Into<String>>(self, context: S) -> Self {
+        Self::Wrap(context.into(), Box::new(self))
+    }
+}
+
+pub type OasisReadResult<T> = Result<T, OasisReadError>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum OasisWriteError {
+    #[error("Io error '{0}'")]
+    Io(#[from] std::io::Error),
+
+    #[error("{0} '{1}' can not be read back from OASIS")]
+    OutOfRange(&'static str, i64),
+
+    #[error("Path width '{0}' is odd, OASIS only stores half widths")]
+    OddPathWidth(i32),
+
+    #[error("When {0} >> {1}")]
+    Wrap(String, Box<OasisWriteError>)
+}
+
+impl OasisWriteError {
+    pub fn wrap<S: Into<String>>(self, context: S) -> Self {
+        Self::Wrap(context.into(), Box::new(self))
+    }
+}
+
+pub type OasisWriteResult<T> = Result<T, OasisWriteError>;
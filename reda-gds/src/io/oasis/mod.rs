@@ -0,0 +1,296 @@
+//! OASIS (SEMI P39) reader and writer, mapped to and from `GdsLibrary`.
+//!
+//! OASIS carries no library name, dates, nodes or text transforms, those are dropped on
+//! write and defaulted on read. GDSII properties travel as standard `S_GDS_PROPERTY`
+//! properties, other properties are ignored. Boxes are written as rectangles or polygons
+//! and read back as boundaries, round path ends are written as half-width extensions.
+//! Odd path widths and negative layers or types have no OASIS form and fail the write.
+mod error;
+mod codec;
+mod read;
+mod write;
+
+pub use error::*;
+pub use read::*;
+pub use write::*;
+
+const MAGIC: &[u8] = b"%SEMI-OASIS\r\n";
+const VERSION: &str = "1.0";
+/// Property name of the GDSII PROPATTR/PROPVALUE pairs
+const GDS_PROPERTY: &str = "S_GDS_PROPERTY";
+
+#[cfg(test)]
+mod tests {
+    use indexmap::IndexMap;
+    use std::sync::{Arc, RwLock};
+    use crate::*;
+    use super::codec::{put_real, put_sint, put_string, put_uint};
+    use super::MAGIC;
+
+    fn coords(xy: &[(i32, i32)]) -> Vec<GdsDbCoord> {
+        xy.iter().map(|&(x, y)| GdsDbCoord::new(x, y)).collect()
+    }
+
+    fn boundary(layer: i16, xy: &[(i32, i32)]) -> GdsBoundary {
+        GdsBoundaryBuilder::default().layer(layer).xy(coords(xy)).build().unwrap()
+    }
+
+    fn library() -> GdsLibrary {
+        let mut leaf = GdsStructure::new("leaf");
+        leaf.boundarys.push(boundary(1, &[(0, 0), (10, 0), (10, 10), (0, 10), (0, 0)]));
+
+        let mut top = GdsStructure::new("top");
+        let mut rect = boundary(1, &[(-5, 0), (15, 0), (15, 4), (-5, 4), (-5, 0)]);
+        rect.properties = vec![(1, "net=vdd".to_string()), (-2, "odd".to_string())];
+        top.boundarys.push(rect);
+        top.boundarys.push(boundary(2, &[(0, 0), (10, 0), (15, 5), (0, 5), (0, 0)]));
+        top.boundarys.push(boundary(2, &[(0, 0), (10, 0), (12, 7), (0, 5), (0, 0)]));
+        for path_type in [GdsPathType::SquareEnd, GdsPathType::SquareEndExtend] {
+            top.paths.push(GdsPathBuilder::default()
+                .layer(3).data_type(1).path_type(path_type).width(6)
+                .xy(coords(&[(0, 0), (100, 0), (100, 50)]))
+                .build().unwrap());
+        }
+        top.texts.push(GdsTextBuilder::default()
+            .layer(5).text_type(0).string("vdd".to_string())
+            .xy(coords(&[(3, 4)]))
+            .build().unwrap());
+
+        let mut rotated = GdsTransform::with_flag(0);
+        rotated.flag.reflect = true;
+        rotated.angle = Some(90.0);
+        let mut scaled = GdsTransform::with_flag(0);
+        scaled.magnification = Some(2.0);
+        scaled.angle = Some(30.0);
+        for (x, transform) in [(0, None), (100, Some(rotated)), (200, Some(scaled))] {
+            let mut sref = GdsSrefBuilder::default().s_name("leaf".to_string()).xy(coords(&[(x, -50)])).build().unwrap();
+            sref.transform = transform;
+            top.srefs.push(sref);
+        }
+        for xy in [[(0, 100), (60, 100), (0, 140)], [(0, 200), (30, 215), (-4, 240)]] {
+            top.arefs.push(GdsArefBuilder::default()
+                .s_name("leaf".to_string()).col(3).row(2).xy(coords(&xy))
+                .properties(vec![(7, "array".to_string())])
+                .build().unwrap());
+        }
+        top.boxes.push(GdsBoxBuilder::default().layer(6).box_type(2).xy(coords(&[(0, 0), (5, 0), (5, 5), (0, 5), (0, 0)])).build().unwrap());
+
+        let mut structures = IndexMap::new();
+        for s in [top, leaf] {
+            structures.insert(s.name.clone(), Arc::new(RwLock::new(s)));
+        }
+        GdsLibraryBuilder::default()
+            .version(600)
+            .create_date(GdsDateTime::now())
+            .modify_date(GdsDateTime::now())
+            .name("lib".to_string())
+            .usrunits_per_dbunit(1e-3)
+            .meters_per_dbunit(1e-9)
+            .structures(structures)
+            .build().unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let library = library();
+        let mut expected = library.structures["top"].read().unwrap().clone();
+        let bx = expected.boxes.remove(0);
+        expected.boundarys.push(GdsBoundaryBuilder::default().layer(6).data_type(2).xy(bx.xy).build().unwrap());
+
+        for cblocks in [false, true] {
+            let mut writer = OasisWriter::new(Vec::new()).with_cblocks(cblocks);
+            writer.write(&library).unwrap();
+            let bytes = writer.into_inner();
+            assert!(bytes.starts_with(MAGIC));
+            assert_eq!(bytes[bytes.len() - 256], 2);
+
+            let read = GdsLibrary::from_oasis_bytes(&bytes).unwrap();
+            assert_eq!(read.meters_per_dbunit, 1e-9);
+            assert!((read.usrunits_per_dbunit - 1e-3).abs() < 1e-15);
+            assert!(read.structures["top"].read().unwrap().same_content(&expected));
+            assert!(read.structures["leaf"].read().unwrap().same_content(&library.structures["leaf"].read().unwrap()));
+        }
+    }
+
+    #[test]
+    fn test_read_modal_and_repetitions() {
+        let mut file = MAGIC.to_vec();
+        let buf = &mut file;
+        put_uint(buf, 1);
+        put_string(buf, b"1.0");
+        put_real(buf, 1000.0);
+        put_uint(buf, 1);
+
+        // top, named in the CELL record, with relative coordinates
+        put_uint(buf, 14);
+        put_string(buf, b"top");
+        put_uint(buf, 16);
+        // Two rows of `leaf`, by reference number, then `leaf` again 5 to the right
+        buf.extend([17, 0xf8]);
+        for value in [0, 20, 0, 3, 0, 50] {
+            put_uint(buf, value);
+        }
+        buf.extend([17, 0x20]);
+        put_sint(buf, 5);
+        // Three squares with a property, then a fourth one that repeats it
+        buf.extend([20, 0xdf]);
+        for value in [1, 0, 4, 0, 0, 2, 1, 10] {
+            put_uint(buf, value);
+        }
+        buf.extend([28, 0x25]);
+        put_string(buf, b"S_GDS_PROPERTY");
+        for value in [8, 1, 13, 0] {
+            put_uint(buf, value);
+        }
+        buf.extend([20, 0x10]);
+        put_sint(buf, 100);
+        buf.push(29);
+        // Text and path
+        buf.extend([19, 0x7b]);
+        for value in [0, 5, 0] {
+            put_uint(buf, value);
+        }
+        put_sint(buf, 1);
+        put_sint(buf, 2);
+        buf.extend([22, 0xfb]);
+        for value in [2, 0, 2, 0b1111] {
+            put_uint(buf, value);
+        }
+        put_sint(buf, 1);
+        put_sint(buf, 3);
+        put_uint(buf, 0);
+        put_uint(buf, 2);
+        put_sint(buf, 10);
+        put_sint(buf, 10);
+        put_sint(buf, -100);
+        put_sint(buf, 0);
+
+        // leaf, named after its use, with trapezoids
+        put_uint(buf, 13);
+        put_uint(buf, 0);
+        buf.extend([21, 0x3b]);
+        for value in [1, 0, 0, 2] {
+            put_uint(buf, value);
+        }
+        put_sint(buf, 10);
+        put_sint(buf, 5);
+        put_sint(buf, 0);
+        put_sint(buf, 0);
+        buf.extend([26, 0xf8]);
+        for value in [0, 10, 4] {
+            put_uint(buf, value);
+        }
+        put_sint(buf, 20);
+        put_sint(buf, 0);
+        buf.extend([24, 0x18]);
+        put_sint(buf, 2);
+        put_sint(buf, 40);
+        put_sint(buf, 0);
+
+        for (id, name) in [(3, "leaf"), (5, "vdd"), (9, "net=vdd")] {
+            put_uint(buf, id);
+            put_string(buf, name.as_bytes());
+        }
+        put_uint(buf, 2);
+        for _ in 0..12 {
+            put_uint(buf, 0);
+        }
+        put_string(buf, &[0; 8]);
+        put_uint(buf, 2);
+        let checksum = buf.iter().fold(0u32, |sum, b| sum.wrapping_add(*b as u32));
+        buf.extend(checksum.to_le_bytes());
+
+        let library = GdsLibrary::from_oasis_bytes(&file).unwrap();
+        let top = library.structures["top"].read().unwrap();
+        assert_eq!(top.arefs[0].xy, coords(&[(10, 0), (10, 0), (10, 100)]));
+        assert_eq!((top.arefs[0].col, top.arefs[0].row), (1, 2));
+        assert_eq!(top.srefs[0].xy, coords(&[(15, 0)]));
+        assert_eq!(top.srefs[0].s_name, "leaf");
+
+        assert_eq!(top.boundarys.len(), 4);
+        for (i, x) in [0, 10, 20, 100].into_iter().enumerate() {
+            assert_eq!(top.boundarys[i].xy, coords(&[(x, 0), (x + 4, 0), (x + 4, 4), (x, 4), (x, 0)]));
+            assert_eq!(top.boundarys[i].properties, vec![(1, "net=vdd".to_string())]);
+        }
+
+        assert_eq!(top.texts[0].string, "vdd");
+        assert_eq!(top.texts[0].xy, coords(&[(1, 2)]));
+        assert_eq!(top.paths[0].xy, coords(&[(-1, 0), (10, 0), (10, 13)]));
+        assert_eq!(top.paths[0].width, Some(4));
+
+        let leaf = library.structures["leaf"].read().unwrap();
+        assert_eq!(leaf.boundarys[0].xy, coords(&[(0, 0), (10, 0), (10, 5), (0, 5), (0, 0)]));
+        assert_eq!(leaf.boundarys[1].xy, coords(&[(20, 0), (20, 4), (26, 4), (30, 0), (20, 0)]));
+        assert_eq!(leaf.boundarys[2].xy, coords(&[(42, 4), (50, 4), (50, 0), (40, 0), (42, 4)]));
+        drop((top, leaf));
+
+        let padding = file.len() - 8;
+        file[padding] = 1;
+        assert!(GdsLibrary::from_oasis_bytes(&file).is_err());
+    }
+
+    #[test]
+    fn test_read_hostile_counts() {
+        let file = |body: &[u8]| {
+            let mut file = MAGIC.to_vec();
+            put_uint(&mut file, 1);
+            put_string(&mut file, b"1.0");
+            put_real(&mut file, 1000.0);
+            put_uint(&mut file, 1);
+            put_uint(&mut file, 3);
+            put_string(&mut file, b"leaf");
+            put_uint(&mut file, 14);
+            put_string(&mut file, b"top");
+            file.extend_from_slice(body);
+            put_uint(&mut file, 2);
+            for _ in 0..12 {
+                put_uint(&mut file, 0);
+            }
+            put_string(&mut file, &[]);
+            put_uint(&mut file, 0);
+            file
+        };
+        let record = |head: &[u8], values: &[u64]| {
+            let mut record = head.to_vec();
+            values.iter().for_each(|value| put_uint(&mut record, *value));
+            record
+        };
+        let error = |body: Vec<u8>| GdsLibrary::from_oasis_bytes(&file(&body)).unwrap_err().to_string();
+
+        // A CBLOCK claiming an endless content is not allocated for
+        let mut cblock = record(&[], &[34, 0, u64::MAX]);
+        let compressed = flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default()).finish().unwrap();
+        put_string(&mut cblock, &compressed);
+        assert!(GdsLibrary::from_oasis_bytes(&file(&cblock)).is_ok());
+
+        // A polygon with more points than bytes, a rectangle grid overflowing 64 bits
+        assert!(error(record(&[21, 0x23], &[1, 0, 4, u64::MAX])).contains("Unexpected end"));
+        assert!(error(record(&[20, 0x67], &[1, 0, 1, 1, 8, u64::MAX - 2, u64::MAX - 2, 2, 2])).contains("Integer too large"));
+
+        // A 63 bytes file describing 2^40 rectangles
+        let body = record(&[20, 0x67], &[1, 0, 1, 1, 1, (1 << 20) - 2, (1 << 20) - 2, 0, 0]);
+        assert_eq!(file(&body).len(), 63);
+        assert!(error(body).contains(&format!("More than {} elements", OASIS_ELEMENT_LIMIT)));
+
+        // A placement grid over the AREF counts is tiled by AREFs
+        let body = record(&[17, 0xf8], &[0, 0, 0, 1, 40000 - 2, 0, 10, 10]);
+        let library = GdsLibrary::from_oasis_bytes(&file(&body)).unwrap();
+        let top = library.structures["top"].read().unwrap();
+        assert_eq!(top.arefs.len(), 2);
+        assert_eq!((top.arefs[0].col, top.arefs[1].col), (i16::MAX, 7233));
+        assert_eq!(top.arefs[1].xy, coords(&[(327670, 0), (400000, 0), (327670, 20)]));
+        let limited = OasisReader::new(file(&body).as_slice()).with_element_limit(1).read();
+        assert!(matches!(limited, Err(OasisReadError::Wrap(_, e)) if matches!(*e, OasisReadError::TooManyElements(1))));
+    }
+
+    #[test]
+    fn test_write_unreadable_values() {
+        for (layer, width) in [(3, 5), (-1, 6)] {
+            let library = library();
+            let mut top = library.structures["top"].write().unwrap();
+            (top.paths[0].layer, top.paths[0].width) = (layer, Some(width));
+            drop(top);
+            assert!(library.to_oasis_bytes().is_err());
+        }
+    }
+}
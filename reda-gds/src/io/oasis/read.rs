@@ -0,0 +1,962 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use flate2::read::DeflateDecoder;
+
+use crate::{
+    GdsArefBuilder, GdsBoundaryBuilder, GdsDateTime, GdsDbCoord, GdsElement, GdsLibrary, GdsLibraryBuilder, GdsPath, GdsPathBuilder, GdsPathType, GdsSrefBuilder, GdsStructure, GdsTextBuilder, GdsTransform, GdsTransformFlag, round_to_db
+};
+use super::codec::{Delta, OasisBytes, Repetition};
+use super::{GDS_PROPERTY, MAGIC, OasisReadError, OasisReadResult, VERSION};
+
+/// Segments of each half of a CIRCLE outline
+const CIRCLE_SEGMENTS: usize = 16;
+
+/// Default limit of `OasisReader::with_element_limit`
+pub const OASIS_ELEMENT_LIMIT: usize = 1 << 24;
+
+pub struct OasisReader<R> {
+    reader: R,
+    element_limit: usize,
+}
+
+impl OasisReader<File> {
+    pub fn open<P: AsRef<Path>>(path: P) -> OasisReadResult<Self> {
+        Ok(Self::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> OasisReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader, element_limit: OASIS_ELEMENT_LIMIT }
+    }
+
+    /// Fail once the file expands to more than `limit` elements. Repetitions make a small
+    /// file describe any number of them, placement grids are kept as AREFs and count one
+    /// per AREF.
+    pub fn with_element_limit(mut self, limit: usize) -> Self {
+        self.element_limit = limit;
+        self
+    }
+
+    /// Read the whole library.
+    ///
+    /// Names may be used before the record defining them, so the file is parsed twice: once
+    /// for the name tables, once for the cells.
+    pub fn read(&mut self) -> OasisReadResult<GdsLibrary> {
+        let mut data = Vec::new();
+        self.reader.read_to_end(&mut data)?;
+
+        let mut tables = Parser::new(Names::default(), true, self.element_limit);
+        tables.file(&data)?;
+        let mut parser = Parser::new(tables.names, false, self.element_limit);
+        parser.file(&data)?;
+        parser.into_library()
+    }
+}
+
+/// Cell, text, property name and property string tables
+#[derive(Default)]
+struct Names {
+    cells: NameTable,
+    texts: NameTable,
+    prop_names: NameTable,
+    prop_strings: NameTable,
+}
+
+#[derive(Default)]
+struct NameTable {
+    names: HashMap<u64, String>,
+    /// Reference number of the next implicitly numbered name
+    next: u64,
+}
+
+impl NameTable {
+    fn insert(&mut self, name: String, number: Option<u64>) {
+        let number = number.unwrap_or_else(|| {
+            self.next += 1;
+            self.next - 1
+        });
+        self.names.insert(number, name);
+    }
+
+    fn get(&self, number: u64, kind: &'static str) -> OasisReadResult<String> {
+        self.names.get(&number).cloned().ok_or(OasisReadError::UnknownReference(kind, number))
+    }
+}
+
+/// A name given in the record or by reference number
+#[derive(Debug, Clone)]
+enum NameRef {
+    Name(String),
+    Number(u64),
+}
+
+#[derive(Debug, Clone)]
+enum PropValue {
+    Real(f64),
+    UInt(u64),
+    SInt(i64),
+    String(Vec<u8>),
+    StringRef(u64),
+}
+
+/// Modal variables, reset at every CELL record
+#[derive(Default)]
+struct Modal {
+    repetition: Option<Repetition>,
+    placement_x: i64,
+    placement_y: i64,
+    placement_cell: Option<NameRef>,
+    layer: Option<u64>,
+    datatype: Option<u64>,
+    textlayer: Option<u64>,
+    texttype: Option<u64>,
+    text_x: i64,
+    text_y: i64,
+    text_string: Option<NameRef>,
+    geometry_x: i64,
+    geometry_y: i64,
+    relative: bool,
+    geometry_w: Option<u64>,
+    geometry_h: Option<u64>,
+    polygon_points: Option<Vec<Delta>>,
+    path_halfwidth: Option<u64>,
+    path_points: Option<Vec<Delta>>,
+    path_start_extension: Option<i64>,
+    path_end_extension: Option<i64>,
+    ctrapezoid_type: Option<u64>,
+    circle_radius: Option<u64>,
+    property_name: Option<NameRef>,
+    property_values: Option<Vec<PropValue>>,
+}
+
+fn modal<T: Clone>(value: &Option<T>, name: &'static str) -> OasisReadResult<T> {
+    value.clone().ok_or(OasisReadError::UndefinedModal(name))
+}
+
+fn bit(info: u8, index: u8) -> bool {
+    info & (1 << index) != 0
+}
+
+/// Update a modal coordinate pair with the values present in the record
+fn read_xy(bytes: &mut OasisBytes, has_x: bool, has_y: bool, relative: bool, x: &mut i64, y: &mut i64) -> OasisReadResult<()> {
+    for (present, value) in [(has_x, x), (has_y, y)] {
+        if present {
+            let read = bytes.sint()?;
+            *value = if relative { *value + read } else { read };
+        }
+    }
+    Ok(())
+}
+
+fn to_i16(value: u64, what: &'static str) -> OasisReadResult<i16> {
+    i16::try_from(value).map_err(|_| OasisReadError::OutOfRange(what, value as i64))
+}
+
+fn to_i32(value: i64, what: &'static str) -> OasisReadResult<i32> {
+    i32::try_from(value).map_err(|_| OasisReadError::OutOfRange(what, value))
+}
+
+fn to_coord((x, y): Delta) -> OasisReadResult<GdsDbCoord> {
+    Ok(GdsDbCoord::new(to_i32(x, "coordinate")?, to_i32(y, "coordinate")?))
+}
+
+struct Parser {
+    /// Only fill the name tables
+    tables_only: bool,
+    names: Names,
+    unit: f64,
+    /// Table offsets are stored in the END record
+    offsets_at_end: bool,
+    modal: Modal,
+    cells: Vec<GdsStructure>,
+    current: Option<GdsStructure>,
+    /// Elements of the last element record, properties attach to them
+    pending: Vec<GdsElement>,
+    /// Elements left before `OasisReader::with_element_limit` is reached
+    elements_left: usize,
+    element_limit: usize,
+}
+
+impl Parser {
+    fn new(names: Names, tables_only: bool, element_limit: usize) -> Self {
+        Self {
+            tables_only,
+            names,
+            unit: 1000.0,
+            offsets_at_end: false,
+            modal: Modal::default(),
+            cells: Vec::new(),
+            current: None,
+            pending: Vec::new(),
+            elements_left: element_limit,
+            element_limit,
+        }
+    }
+
+    fn into_library(mut self) -> OasisReadResult<GdsLibrary> {
+        self.finish_cell();
+        let structures = self.cells.into_iter()
+            .map(|s| (s.name.clone(), Arc::new(RwLock::new(s))))
+            .collect();
+        let library = GdsLibraryBuilder::default()
+            .version(600)
+            .create_date(GdsDateTime::now())
+            .modify_date(GdsDateTime::now())
+            .name("LIB".to_string())
+            .usrunits_per_dbunit(1.0 / self.unit)
+            .meters_per_dbunit(1.0 / (self.unit * 1e6))
+            .structures(structures)
+            .build()
+            .unwrap();
+        Ok(library)
+    }
+
+    fn file(&mut self, data: &[u8]) -> OasisReadResult<()> {
+        let mut bytes = OasisBytes::new(data);
+        if bytes.bytes(MAGIC.len()).ok() != Some(MAGIC) {
+            return Err(OasisReadError::InvalidMagic);
+        }
+        match bytes.uint()? {
+            1 => self.start(&mut bytes).map_err(|e| e.wrap("read START"))?,
+            id => return Err(OasisReadError::MissingStart(id)),
+        }
+
+        loop {
+            let position = bytes.position();
+            let id = bytes.uint()?;
+            if id == 2 {
+                return self.end(&mut bytes, data).map_err(|e| e.wrap("read END"));
+            }
+            self.record(id, &mut bytes)
+                .map_err(|e| e.wrap(format!("read record {} at {} bytes", id, position)))?;
+        }
+    }
+
+    fn start(&mut self, bytes: &mut OasisBytes) -> OasisReadResult<()> {
+        let version = bytes.string()?;
+        if version != VERSION {
+            return Err(OasisReadError::UnsupportVersion(version));
+        }
+        self.unit = bytes.real()?;
+        self.offsets_at_end = bytes.uint()? == 1;
+        if !self.offsets_at_end {
+            for _ in 0..12 {
+                bytes.uint()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn end(&mut self, bytes: &mut OasisBytes, data: &[u8]) -> OasisReadResult<()> {
+        self.flush_pending()?;
+        if self.offsets_at_end {
+            for _ in 0..12 {
+                bytes.uint()?;
+            }
+        }
+        bytes.bstring()?;
+        let scheme = bytes.uint()?;
+        let signed = &data[..bytes.position()];
+        let expected = match scheme {
+            0 => return Ok(()),
+            1 => {
+                let mut crc = flate2::Crc::new();
+                crc.update(signed);
+                crc.sum()
+            }
+            2 => signed.iter().fold(0u32, |sum, b| sum.wrapping_add(*b as u32)),
+            _ => return Err(OasisReadError::InvalidType("validation scheme", scheme)),
+        };
+        let signature = u32::from_le_bytes(bytes.bytes(4)?.try_into().unwrap());
+        match signature == expected {
+            true => Ok(()),
+            false => Err(OasisReadError::Validation(expected, signature)),
+        }
+    }
+
+    fn record(&mut self, id: u64, bytes: &mut OasisBytes) -> OasisReadResult<()> {
+        if !matches!(id, 0 | 15 | 16 | 28 | 29 | 34) {
+            self.flush_pending()?;
+        }
+        match id {
+            0 => {}
+            3..=10 => {
+                let name = String::from_utf8_lossy(&bytes.bstring()?).into_owned();
+                let number = match id % 2 { 0 => Some(bytes.uint()?), _ => None };
+                let table = match id {
+                    3 | 4 => &mut self.names.cells,
+                    5 | 6 => &mut self.names.texts,
+                    7 | 8 => &mut self.names.prop_names,
+                    _ => &mut self.names.prop_strings,
+                };
+                if self.tables_only {
+                    table.insert(name, number);
+                }
+            }
+            11 | 12 => {
+                bytes.bstring()?;
+                bytes.interval()?;
+                bytes.interval()?;
+            }
+            13 => {
+                let number = bytes.uint()?;
+                self.begin_cell(NameRef::Number(number))?;
+            }
+            14 => {
+                let name = bytes.string()?;
+                self.begin_cell(NameRef::Name(name))?;
+            }
+            15 => self.modal.relative = false,
+            16 => self.modal.relative = true,
+            17 | 18 => self.placement(id, bytes)?,
+            19 => self.text(bytes)?,
+            20 => self.rectangle(bytes)?,
+            21 => self.polygon(bytes)?,
+            22 => self.path(bytes)?,
+            23..=25 => self.trapezoid(id, bytes)?,
+            26 => self.ctrapezoid(bytes)?,
+            27 => self.circle(bytes)?,
+            28 => self.property(bytes)?,
+            29 => self.repeat_property()?,
+            30 | 31 => {
+                bytes.uint()?;
+                bytes.bstring()?;
+                if id == 31 {
+                    bytes.uint()?;
+                }
+            }
+            32 => {
+                bytes.uint()?;
+                bytes.bstring()?;
+            }
+            33 => self.xgeometry(bytes)?,
+            34 => self.cblock(bytes)?,
+            _ => return Err(OasisReadError::UnsupportRecordId(id)),
+        }
+        Ok(())
+    }
+
+    fn cblock(&mut self, bytes: &mut OasisBytes) -> OasisReadResult<()> {
+        let tp = bytes.uint()?;
+        if tp != 0 {
+            return Err(OasisReadError::UnsupportCompression(tp));
+        }
+        let uncompressed = bytes.uint()?;
+        let compressed = bytes.uint()? as usize;
+        // The declared size only bounds the output, it is not trusted for allocation
+        let mut data = Vec::new();
+        DeflateDecoder::new(bytes.bytes(compressed)?).take(uncompressed).read_to_end(&mut data)?;
+
+        let mut inner = OasisBytes::new(&data);
+        while !inner.is_empty() {
+            let position = inner.position();
+            let id = inner.uint()?;
+            self.record(id, &mut inner)
+                .map_err(|e| e.wrap(format!("read record {} at {} bytes of CBLOCK", id, position)))?;
+        }
+        Ok(())
+    }
+}
+
+impl Parser {
+    fn begin_cell(&mut self, name: NameRef) -> OasisReadResult<()> {
+        self.finish_cell();
+        self.modal = Modal::default();
+        if !self.tables_only {
+            let name = self.cell_name(&name)?;
+            self.current = Some(GdsStructure::new(name));
+        }
+        Ok(())
+    }
+
+    fn finish_cell(&mut self) {
+        if let Some(cell) = self.current.take() {
+            self.cells.push(cell);
+        }
+    }
+
+    fn flush_pending(&mut self) -> OasisReadResult<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let cell = self.current.as_mut().ok_or(OasisReadError::ElementOutsideCell)?;
+        for element in self.pending.drain(..) {
+            cell.push_element(element);
+        }
+        Ok(())
+    }
+
+    fn cell_name(&self, name: &NameRef) -> OasisReadResult<String> {
+        match name {
+            NameRef::Name(name) => Ok(name.clone()),
+            NameRef::Number(number) => self.names.cells.get(*number, "cell name"),
+        }
+    }
+
+    /// Repetition of the record if `present`, from the modal variable for type 0
+    fn repetition(&mut self, bytes: &mut OasisBytes, present: bool) -> OasisReadResult<Option<Repetition>> {
+        if !present {
+            return Ok(None);
+        }
+        let repetition = match bytes.repetition()? {
+            Some(repetition) => repetition,
+            None => modal(&self.modal.repetition, "repetition")?,
+        };
+        self.modal.repetition = Some(repetition.clone());
+        Ok(Some(repetition))
+    }
+
+    fn layer(&mut self, bytes: &mut OasisBytes, info: u8) -> OasisReadResult<(u64, u64)> {
+        if bit(info, 0) {
+            self.modal.layer = Some(bytes.uint()?);
+        }
+        if bit(info, 1) {
+            self.modal.datatype = Some(bytes.uint()?);
+        }
+        Ok((modal(&self.modal.layer, "layer")?, modal(&self.modal.datatype, "datatype")?))
+    }
+
+    fn geometry_xy(&mut self, bytes: &mut OasisBytes, info: u8) -> OasisReadResult<(i64, i64)> {
+        let state = &mut self.modal;
+        read_xy(bytes, bit(info, 4), bit(info, 3), state.relative, &mut state.geometry_x, &mut state.geometry_y)?;
+        Ok((state.geometry_x, state.geometry_y))
+    }
+
+    /// One boundary for every element of the repetition
+    fn push_polygon(&mut self, (layer, datatype): (u64, u64), origin: Delta, points: &[Delta], repetition: Option<Repetition>) -> OasisReadResult<()> {
+        let (layer, data_type) = (to_i16(layer, "layer")?, to_i16(datatype, "datatype")?);
+        let mut outline: Vec<Delta> = Vec::with_capacity(points.len());
+        for p in points {
+            if outline.last() != Some(p) {
+                outline.push(*p);
+            }
+        }
+        if outline.len() > 1 && outline.first() == outline.last() {
+            outline.pop();
+        }
+
+        for (dx, dy) in self.offsets(&repetition)? {
+            let mut xy = outline.iter()
+                .map(|(x, y)| to_coord((origin.0 + dx + x, origin.1 + dy + y)))
+                .collect::<OasisReadResult<Vec<_>>>()?;
+            if let Some(first) = xy.first().copied() {
+                xy.push(first);
+            }
+            let boundary = GdsBoundaryBuilder::default()
+                .layer(layer)
+                .data_type(data_type)
+                .xy(xy)
+                .build()
+                .unwrap();
+            self.pending.push(boundary.into());
+        }
+        Ok(())
+    }
+}
+
+impl Parser {
+    /// Take `count` elements off the limit
+    fn add_elements(&mut self, count: u64) -> OasisReadResult<()> {
+        match usize::try_from(count).ok().and_then(|count| self.elements_left.checked_sub(count)) {
+            Some(left) => self.elements_left = left,
+            None => return Err(OasisReadError::TooManyElements(self.element_limit)),
+        }
+        Ok(())
+    }
+
+    /// Offsets of the elements of an optional repetition
+    fn offsets(&mut self, repetition: &Option<Repetition>) -> OasisReadResult<Vec<Delta>> {
+        match repetition {
+            Some(repetition) => {
+                self.add_elements(repetition.len())?;
+                Ok(repetition.offsets())
+            }
+            None => {
+                self.add_elements(1)?;
+                Ok(vec![(0, 0)])
+            }
+        }
+    }
+}
+
+/// Point `cols` columns and `rows` rows away from `origin` on a lattice
+fn lattice_point(origin: Delta, (cols, rows): (u64, u64), col_step: Delta, row_step: Delta) -> OasisReadResult<GdsDbCoord> {
+    let axis = |o: i64, c: i64, r: i64| {
+        let value = o as i128 + cols as i128 * c as i128 + rows as i128 * r as i128;
+        i32::try_from(value).map_err(|_| OasisReadError::OutOfRange("coordinate", value.clamp(i64::MIN as i128, i64::MAX as i128) as i64))
+    };
+    Ok(GdsDbCoord::new(axis(origin.0, col_step.0, row_step.0)?, axis(origin.1, col_step.1, row_step.1)?))
+}
+
+impl Parser {
+    /// PLACEMENT: 'CNXYRAAF' or 'CNXYRMAF'
+    fn placement(&mut self, id: u64, bytes: &mut OasisBytes) -> OasisReadResult<()> {
+        let info = bytes.byte()?;
+        if bit(info, 7) {
+            self.modal.placement_cell = Some(match bit(info, 6) {
+                true => NameRef::Number(bytes.uint()?),
+                false => NameRef::Name(bytes.string()?),
+            });
+        }
+        let (magnification, angle) = match id {
+            17 => (1.0, ((info >> 1) & 3) as f64 * 90.0),
+            _ => {
+                let magnification = if bit(info, 2) { bytes.real()? } else { 1.0 };
+                let angle = if bit(info, 1) { bytes.real()? } else { 0.0 };
+                (magnification, angle)
+            }
+        };
+        let state = &mut self.modal;
+        read_xy(bytes, bit(info, 5), bit(info, 4), state.relative, &mut state.placement_x, &mut state.placement_y)?;
+        let repetition = self.repetition(bytes, bit(info, 3))?;
+        if self.tables_only {
+            return Ok(());
+        }
+
+        let s_name = self.cell_name(&modal(&self.modal.placement_cell, "placement-cell")?)?;
+        let transform = GdsTransform {
+            flag: GdsTransformFlag { reflect: bit(info, 0), ..Default::default() },
+            magnification: (magnification != 1.0).then_some(magnification),
+            angle: (angle != 0.0).then_some(angle),
+        };
+        let transform = (!transform.is_identity()).then_some(transform);
+        let origin = (self.modal.placement_x, self.modal.placement_y);
+
+        match repetition {
+            Some(Repetition::Grid { cols, rows, col_step, row_step }) => {
+                // Grids over the AREF counts are tiled by several AREFs, never expanded
+                let tile = i16::MAX as u64;
+                self.add_elements(cols.div_ceil(tile) * rows.div_ceil(tile))?;
+                for row in (0..rows.div_ceil(tile)).map(|i| i * tile) {
+                    for col in (0..cols.div_ceil(tile)).map(|i| i * tile) {
+                        let (tile_cols, tile_rows) = ((cols - col).min(tile), (rows - row).min(tile));
+                        let xy = vec![
+                            lattice_point(origin, (col, row), col_step, row_step)?,
+                            lattice_point(origin, (col + tile_cols, row), col_step, row_step)?,
+                            lattice_point(origin, (col, row + tile_rows), col_step, row_step)?,
+                        ];
+                        let mut builder = GdsArefBuilder::default();
+                        builder.s_name(s_name.clone()).col(tile_cols as i16).row(tile_rows as i16).xy(xy);
+                        if let Some(transform) = transform {
+                            builder.transform(transform);
+                        }
+                        self.pending.push(builder.build().unwrap().into());
+                    }
+                }
+            }
+            repetition => {
+                for (dx, dy) in self.offsets(&repetition)? {
+                    let mut builder = GdsSrefBuilder::default();
+                    builder.s_name(s_name.clone()).xy(vec![to_coord((origin.0 + dx, origin.1 + dy))?]);
+                    if let Some(transform) = transform {
+                        builder.transform(transform);
+                    }
+                    self.pending.push(builder.build().unwrap().into());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// TEXT: '0CNXYRTL'
+    fn text(&mut self, bytes: &mut OasisBytes) -> OasisReadResult<()> {
+        let info = bytes.byte()?;
+        if bit(info, 6) {
+            self.modal.text_string = Some(match bit(info, 5) {
+                true => NameRef::Number(bytes.uint()?),
+                false => NameRef::Name(String::from_utf8_lossy(&bytes.bstring()?).into_owned()),
+            });
+        }
+        if bit(info, 0) {
+            self.modal.textlayer = Some(bytes.uint()?);
+        }
+        if bit(info, 1) {
+            self.modal.texttype = Some(bytes.uint()?);
+        }
+        let state = &mut self.modal;
+        read_xy(bytes, bit(info, 4), bit(info, 3), state.relative, &mut state.text_x, &mut state.text_y)?;
+        let repetition = self.repetition(bytes, bit(info, 2))?;
+        if self.tables_only {
+            return Ok(());
+        }
+
+        let string = match modal(&self.modal.text_string, "text-string")? {
+            NameRef::Name(name) => name,
+            NameRef::Number(number) => self.names.texts.get(number, "text string")?,
+        };
+        let layer = to_i16(modal(&self.modal.textlayer, "textlayer")?, "layer")?;
+        let text_type = to_i16(modal(&self.modal.texttype, "texttype")?, "texttype")?;
+        for (dx, dy) in self.offsets(&repetition)? {
+            let text = GdsTextBuilder::default()
+                .layer(layer)
+                .text_type(text_type)
+                .string(string.clone())
+                .xy(vec![to_coord((self.modal.text_x + dx, self.modal.text_y + dy))?])
+                .build()
+                .unwrap();
+            self.pending.push(text.into());
+        }
+        Ok(())
+    }
+
+    /// RECTANGLE: 'SWHXYRDL'
+    fn rectangle(&mut self, bytes: &mut OasisBytes) -> OasisReadResult<()> {
+        let info = bytes.byte()?;
+        let layer = self.layer(bytes, info)?;
+        if bit(info, 6) {
+            self.modal.geometry_w = Some(bytes.uint()?);
+        }
+        let width = modal(&self.modal.geometry_w, "geometry-w")? as i64;
+        if bit(info, 7) {
+            self.modal.geometry_h = Some(width as u64);
+        } else if bit(info, 5) {
+            self.modal.geometry_h = Some(bytes.uint()?);
+        }
+        let height = modal(&self.modal.geometry_h, "geometry-h")? as i64;
+        let origin = self.geometry_xy(bytes, info)?;
+        let repetition = self.repetition(bytes, bit(info, 2))?;
+        if self.tables_only {
+            return Ok(());
+        }
+
+        let points = [(0, 0), (width, 0), (width, height), (0, height)];
+        self.push_polygon(layer, origin, &points, repetition)
+    }
+
+    /// POLYGON: '00PXYRDL'
+    fn polygon(&mut self, bytes: &mut OasisBytes) -> OasisReadResult<()> {
+        let info = bytes.byte()?;
+        let layer = self.layer(bytes, info)?;
+        if bit(info, 5) {
+            self.modal.polygon_points = Some(bytes.point_list(true)?);
+        }
+        let origin = self.geometry_xy(bytes, info)?;
+        let repetition = self.repetition(bytes, bit(info, 2))?;
+        if self.tables_only {
+            return Ok(());
+        }
+
+        let points = modal(&self.modal.polygon_points, "polygon-point-list")?;
+        self.push_polygon(layer, origin, &points, repetition)
+    }
+
+    /// PATH: 'EWPXYRDL'
+    fn path(&mut self, bytes: &mut OasisBytes) -> OasisReadResult<()> {
+        let info = bytes.byte()?;
+        let (layer, datatype) = self.layer(bytes, info)?;
+        if bit(info, 6) {
+            self.modal.path_halfwidth = Some(bytes.uint()?);
+        }
+        let half_width = modal(&self.modal.path_halfwidth, "path-halfwidth")? as i64;
+        if bit(info, 7) {
+            let scheme = bytes.uint()?;
+            for (shift, extension) in [(2, &mut self.modal.path_start_extension), (0, &mut self.modal.path_end_extension)] {
+                match (scheme >> shift) & 3 {
+                    0 => {}
+                    1 => *extension = Some(0),
+                    2 => *extension = Some(half_width),
+                    _ => *extension = Some(bytes.sint()?),
+                }
+            }
+        }
+        if bit(info, 5) {
+            self.modal.path_points = Some(bytes.point_list(false)?);
+        }
+        let origin = self.geometry_xy(bytes, info)?;
+        let repetition = self.repetition(bytes, bit(info, 2))?;
+        if self.tables_only {
+            return Ok(());
+        }
+
+        let start = modal(&self.modal.path_start_extension, "path-start-extension")?;
+        let end = modal(&self.modal.path_end_extension, "path-end-extension")?;
+        let mut points = modal(&self.modal.path_points, "path-point-list")?;
+        let path_type = match (start, end) {
+            (0, 0) => GdsPathType::SquareEnd,
+            (s, e) if s == half_width && e == half_width => GdsPathType::SquareEndExtend,
+            (s, e) => {
+                // No GDSII path type for these extensions, the ends are moved instead
+                extend(&mut points, s, false);
+                extend(&mut points, e, true);
+                GdsPathType::SquareEnd
+            }
+        };
+
+        let (layer, data_type) = (to_i16(layer, "layer")?, to_i16(datatype, "datatype")?);
+        let width = to_i32(2 * half_width, "path width")?;
+        for (dx, dy) in self.offsets(&repetition)? {
+            let xy = points.iter()
+                .map(|(x, y)| to_coord((origin.0 + dx + x, origin.1 + dy + y)))
+                .collect::<OasisReadResult<Vec<_>>>()?;
+            let path = GdsPathBuilder::default()
+                .layer(layer)
+                .data_type(data_type)
+                .path_type(path_type)
+                .width(width)
+                .xy(xy)
+                .build()
+                .unwrap();
+            self.pending.push(path.into());
+        }
+        Ok(())
+    }
+
+    /// TRAPEZOID: 'OWHXYRDL', record 24 has no delta-b and record 25 no delta-a
+    fn trapezoid(&mut self, id: u64, bytes: &mut OasisBytes) -> OasisReadResult<()> {
+        let info = bytes.byte()?;
+        let layer = self.layer(bytes, info)?;
+        if bit(info, 6) {
+            self.modal.geometry_w = Some(bytes.uint()?);
+        }
+        if bit(info, 5) {
+            self.modal.geometry_h = Some(bytes.uint()?);
+        }
+        let delta_a = if id != 25 { bytes.sint()? } else { 0 };
+        let delta_b = if id != 24 { bytes.sint()? } else { 0 };
+        let origin = self.geometry_xy(bytes, info)?;
+        let repetition = self.repetition(bytes, bit(info, 2))?;
+        if self.tables_only {
+            return Ok(());
+        }
+
+        let w = modal(&self.modal.geometry_w, "geometry-w")? as i64;
+        let h = modal(&self.modal.geometry_h, "geometry-h")? as i64;
+        let points = match bit(info, 7) {
+            true => [
+                (0, delta_a.max(0)),
+                (0, h + delta_b.min(0)),
+                (w, h - delta_b.max(0)),
+                (w, -delta_a.min(0)),
+            ],
+            false => [
+                (delta_a.max(0), h),
+                (w + delta_b.min(0), h),
+                (w - delta_b.max(0), 0),
+                (-delta_a.min(0), 0),
+            ],
+        };
+        self.push_polygon(layer, origin, &points, repetition)
+    }
+
+    /// CTRAPEZOID: 'TWHXYRDL'
+    fn ctrapezoid(&mut self, bytes: &mut OasisBytes) -> OasisReadResult<()> {
+        let info = bytes.byte()?;
+        let layer = self.layer(bytes, info)?;
+        if bit(info, 7) {
+            self.modal.ctrapezoid_type = Some(bytes.uint()?);
+        }
+        if bit(info, 6) {
+            self.modal.geometry_w = Some(bytes.uint()?);
+        }
+        if bit(info, 5) {
+            self.modal.geometry_h = Some(bytes.uint()?);
+        }
+        let origin = self.geometry_xy(bytes, info)?;
+        let repetition = self.repetition(bytes, bit(info, 2))?;
+        if self.tables_only {
+            return Ok(());
+        }
+
+        let tp = modal(&self.modal.ctrapezoid_type, "ctrapezoid-type")?;
+        // Some types take one dimension from the other one
+        let (w, h) = match tp {
+            16..=19 | 25 => {
+                let w = modal(&self.modal.geometry_w, "geometry-w")? as i64;
+                (w, w)
+            }
+            20 | 21 => {
+                let h = modal(&self.modal.geometry_h, "geometry-h")? as i64;
+                (2 * h, h)
+            }
+            22 | 23 => {
+                let w = modal(&self.modal.geometry_w, "geometry-w")? as i64;
+                (w, 2 * w)
+            }
+            _ => (
+                modal(&self.modal.geometry_w, "geometry-w")? as i64,
+                modal(&self.modal.geometry_h, "geometry-h")? as i64,
+            ),
+        };
+        let points = ctrapezoid(tp, w, h)?;
+        self.push_polygon(layer, origin, &points, repetition)
+    }
+
+    /// CIRCLE: '00rXYRDL'
+    fn circle(&mut self, bytes: &mut OasisBytes) -> OasisReadResult<()> {
+        let info = bytes.byte()?;
+        let (layer, datatype) = self.layer(bytes, info)?;
+        if bit(info, 5) {
+            self.modal.circle_radius = Some(bytes.uint()?);
+        }
+        let origin = self.geometry_xy(bytes, info)?;
+        let repetition = self.repetition(bytes, bit(info, 2))?;
+        if self.tables_only {
+            return Ok(());
+        }
+
+        let radius = modal(&self.modal.circle_radius, "circle-radius")? as i64;
+        let circle = GdsPath {
+            path_type: Some(GdsPathType::RoundEnd),
+            width: Some(to_i32(2 * radius, "circle diameter")?),
+            xy: vec![GdsDbCoord::default()],
+            ..Default::default()
+        };
+        let points: Vec<Delta> = match circle.to_polygon_with(CIRCLE_SEGMENTS) {
+            Some(polygon) => polygon.xy.iter().map(|p| (p.x as i64, p.y as i64)).collect(),
+            None => return Ok(()),
+        };
+        self.push_polygon((layer, datatype), origin, &points, repetition)
+    }
+
+    /// XGEOMETRY: '000XYRDL', skipped but its modal variables are kept
+    fn xgeometry(&mut self, bytes: &mut OasisBytes) -> OasisReadResult<()> {
+        let info = bytes.byte()?;
+        bytes.uint()?;
+        self.layer(bytes, info)?;
+        bytes.bstring()?;
+        self.geometry_xy(bytes, info)?;
+        self.repetition(bytes, bit(info, 2))?;
+        Ok(())
+    }
+}
+
+impl Parser {
+    /// PROPERTY: 'UUUUVCNS'
+    fn property(&mut self, bytes: &mut OasisBytes) -> OasisReadResult<()> {
+        let info = bytes.byte()?;
+        if bit(info, 2) {
+            self.modal.property_name = Some(match bit(info, 1) {
+                true => NameRef::Number(bytes.uint()?),
+                false => NameRef::Name(bytes.string()?),
+            });
+        }
+        if !bit(info, 3) {
+            let count = match info >> 4 {
+                15 => bytes.uint()?,
+                count => count as u64,
+            };
+            let values = (0..count)
+                .map(|_| property_value(bytes))
+                .collect::<OasisReadResult<Vec<_>>>()?;
+            self.modal.property_values = Some(values);
+        }
+        self.repeat_property()
+    }
+
+    /// Attach the last property to the elements of the last element record
+    fn repeat_property(&mut self) -> OasisReadResult<()> {
+        if self.tables_only || self.pending.is_empty() {
+            return Ok(());
+        }
+        let name = match modal(&self.modal.property_name, "last-property-name")? {
+            NameRef::Name(name) => name,
+            NameRef::Number(number) => self.names.prop_names.get(number, "property name")?,
+        };
+        let values = modal(&self.modal.property_values, "last-value-list")?;
+        if name != GDS_PROPERTY || values.len() != 2 {
+            return Ok(());
+        }
+
+        let attribute = match values[0] {
+            PropValue::UInt(value) => value as i64,
+            PropValue::SInt(value) => value,
+            PropValue::Real(value) => value as i64,
+            _ => return Err(OasisReadError::InvalidType("S_GDS_PROPERTY attribute", 10)),
+        };
+        let attribute = i16::try_from(attribute).map_err(|_| OasisReadError::OutOfRange("property attribute", attribute))?;
+        let value = match &values[1] {
+            PropValue::String(value) => String::from_utf8_lossy(value).into_owned(),
+            PropValue::StringRef(number) => self.names.prop_strings.get(*number, "property string")?,
+            PropValue::UInt(value) => value.to_string(),
+            PropValue::SInt(value) => value.to_string(),
+            PropValue::Real(value) => value.to_string(),
+        };
+        for element in self.pending.iter_mut() {
+            properties_mut(element).push((attribute, value.clone()));
+        }
+        Ok(())
+    }
+}
+
+fn property_value(bytes: &mut OasisBytes) -> OasisReadResult<PropValue> {
+    let tp = bytes.uint()?;
+    let value = match tp {
+        0..=7 => PropValue::Real(bytes.real_of_type(tp)?),
+        8 => PropValue::UInt(bytes.uint()?),
+        9 => PropValue::SInt(bytes.sint()?),
+        10..=12 => PropValue::String(bytes.bstring()?),
+        13..=15 => PropValue::StringRef(bytes.uint()?),
+        _ => return Err(OasisReadError::InvalidType("property value", tp)),
+    };
+    Ok(value)
+}
+
+fn properties_mut(element: &mut GdsElement) -> &mut Vec<(i16, String)> {
+    match element {
+        GdsElement::Boundary(e) => &mut e.properties,
+        GdsElement::Path(e) => &mut e.properties,
+        GdsElement::Sref(e) => &mut e.properties,
+        GdsElement::Aref(e) => &mut e.properties,
+        GdsElement::Text(e) => &mut e.properties,
+        GdsElement::Node(e) => &mut e.properties,
+        GdsElement::Box(e) => &mut e.properties,
+    }
+}
+
+/// Move the first or last point of a path by `extension` along its end segment
+fn extend(points: &mut [Delta], extension: i64, at_end: bool) {
+    let n = points.len();
+    if n < 2 || extension == 0 {
+        return;
+    }
+    let (end, inner) = match at_end { true => (n - 1, n - 2), false => (0, 1) };
+    let (p, q) = (points[end], points[inner]);
+    let (dx, dy) = ((p.0 - q.0) as f64, (p.1 - q.1) as f64);
+    let length = dx.hypot(dy);
+    if length == 0.0 {
+        return;
+    }
+    let scale = extension as f64 / length;
+    points[end] = (
+        p.0 + round_to_db(dx * scale) as i64,
+        p.1 + round_to_db(dy * scale) as i64,
+    );
+}
+
+/// Vertices of the compact trapezoid types of the OASIS specification
+fn ctrapezoid(tp: u64, w: i64, h: i64) -> OasisReadResult<Vec<Delta>> {
+    let points = match tp {
+        0 => vec![(0, 0), (0, h), (w - h, h), (w, 0)],
+        1 => vec![(0, 0), (0, h), (w, h), (w - h, 0)],
+        2 => vec![(0, 0), (h, h), (w, h), (w, 0)],
+        3 => vec![(h, 0), (0, h), (w, h), (w, 0)],
+        4 => vec![(0, 0), (h, h), (w - h, h), (w, 0)],
+        5 => vec![(h, 0), (0, h), (w, h), (w - h, 0)],
+        6 => vec![(0, 0), (h, h), (w, h), (w - h, 0)],
+        7 => vec![(h, 0), (0, h), (w - h, h), (w, 0)],
+        8 => vec![(0, 0), (0, h), (w, h - w), (w, 0)],
+        9 => vec![(0, 0), (0, h - w), (w, h), (w, 0)],
+        10 => vec![(0, 0), (0, h), (w, h), (w, w)],
+        11 => vec![(0, w), (0, h), (w, h), (w, 0)],
+        12 => vec![(0, 0), (0, h), (w, h - w), (w, w)],
+        13 => vec![(0, w), (0, h - w), (w, h), (w, 0)],
+        14 => vec![(0, 0), (0, h - w), (w, h), (w, w)],
+        15 => vec![(0, w), (0, h), (w, h - w), (w, 0)],
+        16 => vec![(0, 0), (0, w), (w, 0)],
+        17 => vec![(0, 0), (0, w), (w, w)],
+        18 => vec![(0, 0), (w, w), (w, 0)],
+        19 => vec![(0, w), (w, w), (w, 0)],
+        20 => vec![(0, 0), (h, h), (w, 0)],
+        21 => vec![(0, h), (w, h), (h, 0)],
+        22 => vec![(0, 0), (0, h), (w, w)],
+        23 => vec![(w, 0), (0, w), (w, h)],
+        24 | 25 => vec![(0, 0), (0, h), (w, h), (w, 0)],
+        _ => return Err(OasisReadError::InvalidType("ctrapezoid", tp)),
+    };
+    Ok(points)
+}
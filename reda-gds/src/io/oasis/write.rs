@@ -0,0 +1,456 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use flate2::{Compression, write::DeflateEncoder};
+
+use crate::{GdsAref, GdsDbCoord, GdsLibrary, GdsPathType, GdsStructure, GdsTransform};
+use super::codec::{Delta, Repetition, put_point_list, put_real, put_repetition, put_sint, put_string, put_uint};
+use super::{GDS_PROPERTY, MAGIC, OasisWriteError, OasisWriteResult, VERSION};
+
+/// Size of the END record, padding included
+const END_RECORD_SIZE: usize = 256;
+
+pub struct OasisWriter<W> {
+    writer: W,
+    /// CRC32 of the bytes written so far
+    crc: flate2::Crc,
+    cblocks: bool,
+}
+
+impl OasisWriter<BufWriter<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> OasisWriteResult<Self> {
+        let file = File::create(path)?;
+        Ok(Self::new(BufWriter::new(file)))
+    }
+}
+
+impl<W: Write> OasisWriter<W> {
+    /// Create a writer over any byte sink
+    pub fn new(writer: W) -> Self {
+        Self { writer, crc: flate2::Crc::new(), cblocks: false }
+    }
+
+    /// Compress the content of every cell in a CBLOCK
+    pub fn with_cblocks(mut self, cblocks: bool) -> Self {
+        self.cblocks = cblocks;
+        self
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Write the library, cells in name order, with a CRC32 validation signature
+    pub fn write(&mut self, library: &GdsLibrary) -> OasisWriteResult<()> {
+        let mut cells: Vec<_> = library.structures.iter().collect();
+        cells.sort_by(|a, b| a.0.cmp(b.0));
+        let cell_numbers: HashMap<&str, u64> = cells.iter()
+            .enumerate()
+            .map(|(i, (name, _))| (name.as_str(), i as u64))
+            .collect();
+
+        let mut texts = BTreeSet::new();
+        let mut has_properties = false;
+        for (_, cell) in &cells {
+            let cell = cell.read().unwrap();
+            texts.extend(cell.texts.iter().map(|t| t.string.clone()));
+            has_properties |= has_gds_properties(&cell);
+        }
+        let text_numbers: HashMap<String, u64> = texts.into_iter()
+            .enumerate()
+            .map(|(i, text)| (text, i as u64))
+            .collect();
+
+        self.emit(MAGIC)?;
+        self.write_start(library)?;
+        self.write_names(&cells.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>(), &text_numbers, has_properties)?;
+
+        let tables = Tables { cells: &cell_numbers, texts: &text_numbers };
+        for (name, cell) in &cells {
+            let mut record = Vec::new();
+            put_uint(&mut record, 13);
+            put_uint(&mut record, cell_numbers[name.as_str()]);
+            self.emit(&record)?;
+
+            let mut encoder = CellEncoder { buf: Vec::new(), modal: WriteModal::default(), tables: &tables };
+            encoder.structure(&cell.read().unwrap())
+                .map_err(|e| e.wrap(format!("write structure '{}'", name)))?;
+            match self.cblocks {
+                true => self.write_cblock(&encoder.buf)?,
+                false => self.emit(&encoder.buf)?,
+            }
+        }
+
+        self.write_end()?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn emit(&mut self, bytes: &[u8]) -> OasisWriteResult<()> {
+        self.crc.update(bytes);
+        self.writer.write_all(bytes)?;
+        Ok(())
+    }
+
+    /// START with the grid steps per micron and empty table offsets
+    fn write_start(&mut self, library: &GdsLibrary) -> OasisWriteResult<()> {
+        let mut record = Vec::new();
+        put_uint(&mut record, 1);
+        put_string(&mut record, VERSION.as_bytes());
+        let unit = 1e-6 / library.meters_per_dbunit;
+        put_real(&mut record, if (unit - unit.round()).abs() < 1e-9 { unit.round() } else { unit });
+        put_uint(&mut record, 0);
+        for _ in 0..12 {
+            put_uint(&mut record, 0);
+        }
+        self.emit(&record)
+    }
+
+    /// Implicitly numbered CELLNAME, TEXTSTRING and PROPNAME records
+    fn write_names(&mut self, cells: &[&str], texts: &HashMap<String, u64>, has_properties: bool) -> OasisWriteResult<()> {
+        let mut record = Vec::new();
+        for name in cells {
+            put_uint(&mut record, 3);
+            put_string(&mut record, name.as_bytes());
+        }
+
+        let mut texts: Vec<(&String, &u64)> = texts.iter().collect();
+        texts.sort_by_key(|(_, number)| **number);
+        for (text, _) in texts {
+            put_uint(&mut record, 5);
+            put_string(&mut record, text.as_bytes());
+        }
+
+        if has_properties {
+            put_uint(&mut record, 7);
+            put_string(&mut record, GDS_PROPERTY.as_bytes());
+        }
+        self.emit(&record)
+    }
+
+    fn write_cblock(&mut self, content: &[u8]) -> OasisWriteResult<()> {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(content)?;
+        let compressed = encoder.finish()?;
+
+        let mut record = Vec::new();
+        put_uint(&mut record, 34);
+        put_uint(&mut record, 0);
+        put_uint(&mut record, content.len() as u64);
+        put_uint(&mut record, compressed.len() as u64);
+        record.extend_from_slice(&compressed);
+        self.emit(&record)
+    }
+
+    /// END padded to its fixed size, signed with the CRC32 of the whole file
+    fn write_end(&mut self) -> OasisWriteResult<()> {
+        let mut padding = END_RECORD_SIZE - 1 - 1 - 4;
+        let record = loop {
+            let mut record = Vec::new();
+            put_uint(&mut record, 2);
+            put_string(&mut record, &vec![0; padding]);
+            put_uint(&mut record, 1);
+            match record.len() + 4 - END_RECORD_SIZE {
+                0 => break record,
+                excess => padding -= excess,
+            }
+        };
+        self.emit(&record)?;
+        let signature = self.crc.sum().to_le_bytes();
+        self.writer.write_all(&signature)?;
+        Ok(())
+    }
+}
+
+/// Reference numbers of the names
+struct Tables<'a> {
+    cells: &'a HashMap<&'a str, u64>,
+    texts: &'a HashMap<String, u64>,
+}
+
+/// Modal variables the writer reuses, reset at every cell
+#[derive(Default)]
+struct WriteModal {
+    layer: Option<u64>,
+    datatype: Option<u64>,
+    textlayer: Option<u64>,
+    texttype: Option<u64>,
+    placement_cell: Option<u64>,
+}
+
+struct CellEncoder<'a> {
+    buf: Vec<u8>,
+    modal: WriteModal,
+    tables: &'a Tables<'a>,
+}
+
+impl CellEncoder<'_> {
+    fn structure(&mut self, structure: &GdsStructure) -> OasisWriteResult<()> {
+        for e in &structure.boundarys {
+            self.polygon(e.layer, e.data_type, &e.xy)?;
+            self.properties(&e.properties);
+        }
+        for e in &structure.paths {
+            self.path(e.layer, e.data_type, e.width.unwrap_or(0), e.path_type.unwrap_or_default(), &e.xy)?;
+            self.properties(&e.properties);
+        }
+        for e in &structure.srefs {
+            let origin = e.xy.first().copied().unwrap_or_default();
+            self.placement(&e.s_name, e.transform.as_ref(), origin, None);
+            self.properties(&e.properties);
+        }
+        for e in &structure.arefs {
+            let origin = e.xy.first().copied().unwrap_or_default();
+            self.placement(&e.s_name, e.transform.as_ref(), origin, aref_repetition(e));
+            self.properties(&e.properties);
+        }
+        for e in &structure.texts {
+            let origin = e.xy.first().copied().unwrap_or_default();
+            self.text(&e.string, e.layer, e.text_type, origin)?;
+            self.properties(&e.properties);
+        }
+        for e in &structure.boxes {
+            self.polygon(e.layer, e.box_type, &e.xy)?;
+            self.properties(&e.properties);
+        }
+        Ok(())
+    }
+
+    /// Layer and datatype bits of the info byte, values written when they changed
+    fn layer_bits(&mut self, layer: i16, datatype: i16) -> OasisWriteResult<(u8, Vec<u8>)> {
+        let mut info = 0;
+        let mut fields = Vec::new();
+        let (layer, datatype) = (to_u64(layer, "layer")?, to_u64(datatype, "datatype")?);
+        if self.modal.layer != Some(layer) {
+            info |= 0x01;
+            put_uint(&mut fields, layer);
+            self.modal.layer = Some(layer);
+        }
+        if self.modal.datatype != Some(datatype) {
+            info |= 0x02;
+            put_uint(&mut fields, datatype);
+            self.modal.datatype = Some(datatype);
+        }
+        Ok((info, fields))
+    }
+
+    /// RECTANGLE for axis aligned rectangles, POLYGON otherwise
+    fn polygon(&mut self, layer: i16, datatype: i16, xy: &[GdsDbCoord]) -> OasisWriteResult<()> {
+        let mut points: Vec<Delta> = xy.iter().map(|p| (p.x as i64, p.y as i64)).collect();
+        if points.len() > 1 && points.first() == points.last() {
+            points.pop();
+        }
+        if points.len() < 3 {
+            return Ok(());
+        }
+        let (layer_info, layer_fields) = self.layer_bits(layer, datatype)?;
+
+        if let Some((x, y, w, h)) = rectangle(&points) {
+            let info = layer_info | if w == h { 0xd8 } else { 0x78 };
+            self.buf.push(20);
+            self.buf.push(info);
+            self.buf.extend_from_slice(&layer_fields);
+            put_uint(&mut self.buf, w);
+            if w != h {
+                put_uint(&mut self.buf, h);
+            }
+            put_sint(&mut self.buf, x);
+            put_sint(&mut self.buf, y);
+            return Ok(());
+        }
+
+        let origin = points[0];
+        let relative: Vec<Delta> = points.iter().map(|p| (p.0 - origin.0, p.1 - origin.1)).collect();
+        self.buf.push(21);
+        self.buf.push(layer_info | 0x38);
+        self.buf.extend_from_slice(&layer_fields);
+        put_point_list(&mut self.buf, &relative);
+        put_sint(&mut self.buf, origin.0);
+        put_sint(&mut self.buf, origin.1);
+        Ok(())
+    }
+
+    /// Round ends have no OASIS equivalent and are extended by half the width. OASIS
+    /// stores half widths, odd widths are refused.
+    fn path(&mut self, layer: i16, datatype: i16, width: i32, path_type: GdsPathType, xy: &[GdsDbCoord]) -> OasisWriteResult<()> {
+        let Some(first) = xy.first() else {
+            return Ok(());
+        };
+        if width % 2 != 0 {
+            return Err(OasisWriteError::OddPathWidth(width));
+        }
+        let (layer_info, layer_fields) = self.layer_bits(layer, datatype)?;
+        let origin = (first.x as i64, first.y as i64);
+        let relative: Vec<Delta> = xy.iter().map(|p| (p.x as i64 - origin.0, p.y as i64 - origin.1)).collect();
+        let extension = match path_type {
+            GdsPathType::SquareEnd => 0b0101,
+            GdsPathType::RoundEnd | GdsPathType::SquareEndExtend => 0b1010,
+        };
+
+        self.buf.push(22);
+        self.buf.push(layer_info | 0xf8);
+        self.buf.extend_from_slice(&layer_fields);
+        put_uint(&mut self.buf, width.unsigned_abs() as u64 / 2);
+        put_uint(&mut self.buf, extension);
+        put_point_list(&mut self.buf, &relative);
+        put_sint(&mut self.buf, origin.0);
+        put_sint(&mut self.buf, origin.1);
+        Ok(())
+    }
+
+    /// PLACEMENT 17 for quarter turns without magnification, PLACEMENT 18 otherwise
+    fn placement(&mut self, s_name: &str, transform: Option<&GdsTransform>, origin: GdsDbCoord, repetition: Option<Repetition>) {
+        let (reflect, magnification, angle) = match transform {
+            Some(t) => (t.flag.reflect, t.magnification(), t.angle().rem_euclid(360.0)),
+            None => (false, 1.0, 0.0),
+        };
+        let quarter = angle / 90.0;
+        let manhattan = magnification == 1.0 && quarter.fract() == 0.0;
+
+        let mut info = 0x30 | reflect as u8;
+        let mut fields = Vec::new();
+        match self.tables.cells.get(s_name) {
+            Some(number) if self.modal.placement_cell == Some(*number) => {}
+            Some(number) => {
+                info |= 0xc0;
+                put_uint(&mut fields, *number);
+                self.modal.placement_cell = Some(*number);
+            }
+            None => {
+                info |= 0x80;
+                put_string(&mut fields, s_name.as_bytes());
+                self.modal.placement_cell = None;
+            }
+        }
+        if manhattan {
+            info |= (quarter as u8 & 3) << 1;
+        } else {
+            if magnification != 1.0 {
+                info |= 0x04;
+                put_real(&mut fields, magnification);
+            }
+            if angle != 0.0 {
+                info |= 0x02;
+                put_real(&mut fields, angle);
+            }
+        }
+        put_sint(&mut fields, origin.x as i64);
+        put_sint(&mut fields, origin.y as i64);
+        if let Some(repetition) = &repetition {
+            info |= 0x08;
+            put_repetition(&mut fields, repetition);
+        }
+
+        self.buf.push(if manhattan { 17 } else { 18 });
+        self.buf.push(info);
+        self.buf.extend_from_slice(&fields);
+    }
+
+    fn text(&mut self, string: &str, layer: i16, texttype: i16, origin: GdsDbCoord) -> OasisWriteResult<()> {
+        let mut info = 0x78;
+        let mut fields = Vec::new();
+        put_uint(&mut fields, self.tables.texts[string]);
+        let (layer, texttype) = (to_u64(layer, "layer")?, to_u64(texttype, "texttype")?);
+        if self.modal.textlayer != Some(layer) {
+            info |= 0x01;
+            put_uint(&mut fields, layer);
+            self.modal.textlayer = Some(layer);
+        }
+        if self.modal.texttype != Some(texttype) {
+            info |= 0x02;
+            put_uint(&mut fields, texttype);
+            self.modal.texttype = Some(texttype);
+        }
+        put_sint(&mut fields, origin.x as i64);
+        put_sint(&mut fields, origin.y as i64);
+
+        self.buf.push(19);
+        self.buf.push(info);
+        self.buf.extend_from_slice(&fields);
+        Ok(())
+    }
+
+    /// One S_GDS_PROPERTY with the attribute and the value for every pair
+    fn properties(&mut self, properties: &[(i16, String)]) {
+        for (attribute, value) in properties {
+            self.buf.push(28);
+            self.buf.push(0x27);
+            put_uint(&mut self.buf, 0);
+            match *attribute >= 0 {
+                true => {
+                    put_uint(&mut self.buf, 8);
+                    put_uint(&mut self.buf, *attribute as u64);
+                }
+                false => {
+                    put_uint(&mut self.buf, 9);
+                    put_sint(&mut self.buf, *attribute as i64);
+                }
+            }
+            put_uint(&mut self.buf, 11);
+            put_string(&mut self.buf, value.as_bytes());
+        }
+    }
+}
+
+/// Layers and types are read back as `i16`, negative ones would not be
+fn to_u64(value: i16, what: &'static str) -> OasisWriteResult<u64> {
+    u64::try_from(value).map_err(|_| OasisWriteError::OutOfRange(what, value as i64))
+}
+
+fn has_gds_properties(structure: &GdsStructure) -> bool {
+    structure.boundarys.iter().any(|e| !e.properties.is_empty())
+        || structure.paths.iter().any(|e| !e.properties.is_empty())
+        || structure.srefs.iter().any(|e| !e.properties.is_empty())
+        || structure.arefs.iter().any(|e| !e.properties.is_empty())
+        || structure.texts.iter().any(|e| !e.properties.is_empty())
+        || structure.boxes.iter().any(|e| !e.properties.is_empty())
+}
+
+/// Origin and size of an axis aligned rectangle
+fn rectangle(points: &[Delta]) -> Option<(i64, i64, u64, u64)> {
+    if points.len() != 4 {
+        return None;
+    }
+    let horizontal_first = (0..4).all(|i| {
+        let (p, q) = (points[i], points[(i + 1) % 4]);
+        if i % 2 == 0 { p.1 == q.1 } else { p.0 == q.0 }
+    });
+    let vertical_first = (0..4).all(|i| {
+        let (p, q) = (points[i], points[(i + 1) % 4]);
+        if i % 2 == 0 { p.0 == q.0 } else { p.1 == q.1 }
+    });
+    if !horizontal_first && !vertical_first {
+        return None;
+    }
+    let x = points.iter().map(|p| p.0).min()?;
+    let y = points.iter().map(|p| p.1).min()?;
+    let w = (points.iter().map(|p| p.0).max()? - x) as u64;
+    let h = (points.iter().map(|p| p.1).max()? - y) as u64;
+    (w > 0 && h > 0).then_some((x, y, w, h))
+}
+
+/// Lattice of the array, or every instance when the lattice is not on the grid
+fn aref_repetition(aref: &GdsAref) -> Option<Repetition> {
+    let (cols, rows) = (aref.col.max(1) as i64, aref.row.max(1) as i64);
+    if cols * rows < 2 {
+        return None;
+    }
+    let origin = aref.xy.first().copied().unwrap_or_default();
+    let step = |corner: Option<&GdsDbCoord>, count: i64| {
+        let corner = corner.copied().unwrap_or(origin);
+        let (dx, dy) = (corner.x as i64 - origin.x as i64, corner.y as i64 - origin.y as i64);
+        (dx % count == 0 && dy % count == 0).then_some((dx / count, dy / count))
+    };
+    match (step(aref.xy.get(1), cols), step(aref.xy.get(2), rows)) {
+        (Some(col_step), Some(row_step)) => Some(Repetition::Grid { cols: cols as u64, rows: rows as u64, col_step, row_step }),
+        _ => {
+            let offsets = aref.instance_origins()
+                .iter()
+                .map(|p| (p.x as i64 - origin.x as i64, p.y as i64 - origin.y as i64))
+                .collect();
+            Some(Repetition::Positions(offsets))
+        }
+    }
+}
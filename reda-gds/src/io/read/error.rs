@@ -52,14 +52,78 @@ pub enum GdsReadError {
     #[error("Build library failed for '{0}'")]
     BuildLibrary(#[from] GdsLibraryBuilderError),
 
+    #[error("Library must be read from the beginning of the stream")]
+    NotAtStart,
+
     #[error("When {0} >> {1}")]
-    Wrap(String, Box<GdsReadError>)
+    Wrap(String, Box<GdsReadError>),
+
+    #[error("At {0} >> {1}")]
+    Located(GdsReadLocation, Box<GdsReadError>),
 }
 
 impl GdsReadError {
     pub fn wrap<S: Into<String>>(self, context: S) -> Self {
         Self::Wrap(context.into(), Box::new(self))
     }
+
+    /// Where the read failed, set on the errors returned by `GdsReader`
+    pub fn location(&self) -> Option<&GdsReadLocation> {
+        match self {
+            Self::Located(location, _) => Some(location),
+            Self::Wrap(_, error) => error.location(),
+            _ => None,
+        }
+    }
+}
+
+/// Record, structure and element a read failed in
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GdsReadLocation {
+    /// Offset of the header of the record being read from the start of the stream
+    pub offset: u64,
+    /// `None` if the record type is unknown or its header could not be read
+    pub record_type: Option<GdsRecordType>,
+    pub structure: Option<String>,
+    /// Index of the element among the elements of its structure, in stream order
+    pub element: Option<usize>,
+}
+
+impl std::fmt::Display for GdsReadLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "offset {}", self.offset)?;
+        if let Some(record_type) = self.record_type {
+            write!(f, " ({} record)", record_type)?;
+        }
+        if let Some(structure) = &self.structure {
+            write!(f, " in structure '{}'", structure)?;
+        }
+        if let Some(element) = self.element {
+            write!(f, " element #{}", element)?;
+        }
+        Ok(())
+    }
 }
 
 pub type GdsReadResult<T> = Result<T, GdsReadError>;
+
+
+/// A record skipped by a tolerant reader
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GdsReadWarning {
+    /// Offset of the record header from the start of the stream
+    pub offset: u64,
+    pub record_type: u16,
+    /// Size of the record, header included
+    pub size: usize,
+}
+
+impl std::fmt::Display for GdsReadWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match GdsRecordType::from_u16(self.record_type) {
+            Some(record_type) => write!(f, "skipped {} record", record_type)?,
+            None => write!(f, "skipped unknown record {:#06x}", self.record_type)?,
+        }
+        write!(f, " of {} bytes at offset {}", self.size, self.offset)
+    }
+}
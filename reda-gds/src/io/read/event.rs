@@ -0,0 +1,113 @@
+use indexmap::IndexMap;
+use std::io::Read;
+
+use crate::{GdsElement, GdsLibrary, GdsLibraryBuilder, GdsStructure};
+use crate::io::record::GdsRecordType;
+use super::{GdsReadResult, GdsReader};
+
+/// Events yielded by `GdsReader::next_event`, in stream order:
+///
+/// `Library (BeginStructure Element* EndStructure)* EndLibrary`
+#[derive(Debug, Clone)]
+pub enum GdsEvent {
+    /// HEADER, BGNLIB, LIBNAME, library options and UNITS. `structures` is always empty
+    Library(GdsLibrary),
+    /// BGNSTR and STRNAME. The structure holds no element yet
+    BeginStructure(GdsStructure),
+    /// One element of the current structure
+    Element(GdsElement),
+    /// ENDSTR
+    EndStructure,
+    /// ENDLIB
+    EndLibrary,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum GdsReadState {
+    Header,
+    Library,
+    Structure,
+    Finished,
+}
+
+impl<R: Read> GdsReader<R> {
+    /// Pull the next event from the stream, `None` once ENDLIB has been read.
+    /// Only the current element is kept in memory.
+    pub fn next_event(&mut self) -> GdsReadResult<Option<GdsEvent>> {
+        self.next_event_impl().map_err(|e| self.locate(e))
+    }
+
+    fn next_event_impl(&mut self) -> GdsReadResult<Option<GdsEvent>> {
+        match self.state {
+            GdsReadState::Header => {
+                let library = self.read_library_header().map_err(|e| e.wrap("read library header"))?;
+                self.state = GdsReadState::Library;
+                Ok(Some(GdsEvent::Library(library)))
+            }
+            GdsReadState::Library => {
+                self.skip_unexpected(&[GdsRecordType::BgnStr, GdsRecordType::EndLib])?;
+                self.skipped.clear();
+                if self.check_record_type(GdsRecordType::BgnStr)? {
+                    let structure = self.read_structure_header().map_err(|e| e.wrap("read structure header"))?;
+                    self.state = GdsReadState::Structure;
+                    Ok(Some(GdsEvent::BeginStructure(structure)))
+                } else {
+                    self.read_library_end().map_err(|e| e.wrap("read library end"))?;
+                    self.state = GdsReadState::Finished;
+                    Ok(Some(GdsEvent::EndLibrary))
+                }
+            }
+            GdsReadState::Structure => {
+                match self.read_element().map_err(|e| e.wrap("read structure elements"))? {
+                    Some(element) => Ok(Some(GdsEvent::Element(element))),
+                    None => {
+                        self.read_structure_end().map_err(|e| e.wrap("read structure end"))?;
+                        self.state = GdsReadState::Library;
+                        Ok(Some(GdsEvent::EndStructure))
+                    }
+                }
+            }
+            GdsReadState::Finished => Ok(None),
+        }
+    }
+
+    /// Iterate over the remaining events, stops after the first error
+    pub fn events(&mut self) -> GdsEvents<'_, R> {
+        GdsEvents { reader: self, failed: false }
+    }
+
+    fn read_library_header(&mut self) -> GdsReadResult<GdsLibrary> {
+        let mut builder = GdsLibraryBuilder::default();
+        self.read_header(&mut builder).map_err(|e| e.wrap("read header"))?;
+        self.read_library_begin(&mut builder).map_err(|e| e.wrap("read library begin"))?;
+        self.skip_unexpected(&[GdsRecordType::LibName, GdsRecordType::Uints, GdsRecordType::BgnStr, GdsRecordType::EndLib])?;
+        self.read_library_name(&mut builder).map_err(|e| e.wrap("read library name"))?;
+        self.read_library_options(&mut builder).map_err(|e| e.wrap("read library options"))?;
+        self.read_units(&mut builder).map_err(|e| e.wrap("read units"))?;
+        builder.structures(IndexMap::new());
+        builder.extra_records(std::mem::take(&mut self.skipped));
+        Ok(builder.build()?)
+    }
+}
+
+pub struct GdsEvents<'a, R> {
+    reader: &'a mut GdsReader<R>,
+    failed: bool,
+}
+
+impl<R: Read> Iterator for GdsEvents<'_, R> {
+    type Item = GdsReadResult<GdsEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        match self.reader.next_event() {
+            Ok(event) => event.map(Ok),
+            Err(e) => {
+                self.failed = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
@@ -0,0 +1,264 @@
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use indexmap::IndexMap;
+
+use crate::{GdsLibrary, GdsStructure};
+use crate::io::record::GdsRecordType;
+use super::{GdsEvent, GdsReadError, GdsReadResult, GdsReader};
+
+/// Byte span of one structure in the stream, from its BGNSTR to the end of its ENDSTR
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GdsStructureSpan {
+    pub offset: u64,
+    pub length: u64,
+}
+
+/// A library that only decodes structures on demand.
+///
+/// Opening it reads the library header and indexes the span of every structure
+/// without decoding any element. Structures are decoded by `structure` or
+/// `load_hierarchy` and cached.
+pub struct LazyGdsLibrary<R> {
+    reader: R,
+    header: GdsLibrary,
+    spans: IndexMap<String, GdsStructureSpan>,
+    structures: IndexMap<String, Arc<RwLock<GdsStructure>>>,
+}
+
+impl LazyGdsLibrary<File> {
+    pub fn open<P: AsRef<Path>>(path: P) -> GdsReadResult<Self> {
+        Self::new(File::open(path)?)
+    }
+}
+
+impl<R: Read + Seek> LazyGdsLibrary<R> {
+    pub fn new(mut reader: R) -> GdsReadResult<Self> {
+        let start = reader.stream_position()?;
+
+        let (header, header_length) = {
+            let mut gds_reader = GdsReader::new(&mut reader);
+            let Some(GdsEvent::Library(header)) = gds_reader.next_event()? else {
+                unreachable!("the library header is always the first event")
+            };
+            (header, gds_reader.position())
+        };
+
+        reader.seek(SeekFrom::Start(start + header_length))?;
+        let spans = Self::index_structures(&mut reader, start + header_length)
+            .map_err(|e| e.wrap("index structures"))?;
+
+        Ok(Self { reader, header, spans, structures: IndexMap::new() })
+    }
+
+    /// Library header, `structures` is always empty
+    pub fn header(&self) -> &GdsLibrary {
+        &self.header
+    }
+
+    /// Names of every structure, in file order
+    pub fn structure_names(&self) -> impl Iterator<Item = &str> {
+        self.spans.keys().map(|name| name.as_str())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.spans.contains_key(name)
+    }
+
+    pub fn span(&self, name: &str) -> Option<GdsStructureSpan> {
+        self.spans.get(name).copied()
+    }
+
+    pub fn is_loaded(&self, name: &str) -> bool {
+        self.structures.contains_key(name)
+    }
+
+    /// Structures decoded so far, in decoding order
+    pub fn loaded(&self) -> &IndexMap<String, Arc<RwLock<GdsStructure>>> {
+        &self.structures
+    }
+
+    /// Decode one structure, `None` if the library has no structure of this name
+    pub fn structure(&mut self, name: &str) -> GdsReadResult<Option<Arc<RwLock<GdsStructure>>>> {
+        if let Some(structure) = self.structures.get(name) {
+            return Ok(Some(structure.clone()));
+        }
+
+        let Some(span) = self.span(name) else {
+            return Ok(None);
+        };
+
+        self.reader.seek(SeekFrom::Start(span.offset))?;
+        let structure = GdsReader::new((&mut self.reader).take(span.length))
+            .starting_at(span.offset)
+            .read_structure()
+            .map_err(|e| e.wrap(format!("read structure '{name}' at {} bytes", span.offset)))?;
+
+        let structure = Arc::new(RwLock::new(structure));
+        self.structures.insert(name.to_string(), structure.clone());
+        Ok(Some(structure))
+    }
+
+    /// Decode a structure and every structure it references, directly or not.
+    /// References to structures missing from the library are ignored.
+    pub fn load_hierarchy(&mut self, name: &str) -> GdsReadResult<Option<Arc<RwLock<GdsStructure>>>> {
+        let Some(top) = self.structure(name)? else {
+            return Ok(None);
+        };
+
+        let mut pending = vec![top.clone()];
+        while let Some(structure) = pending.pop() {
+            let names: Vec<String> = {
+                let structure = structure.read().unwrap();
+                structure.srefs.iter().map(|r| r.s_name.clone())
+                    .chain(structure.arefs.iter().map(|r| r.s_name.clone()))
+                    .collect()
+            };
+
+            for name in names {
+                if self.is_loaded(&name) {
+                    continue;
+                }
+                if let Some(child) = self.structure(&name)? {
+                    pending.push(child);
+                }
+            }
+        }
+
+        Ok(Some(top))
+    }
+
+    /// Library made of the header and the structures decoded so far, in file order
+    pub fn into_library(self) -> GdsLibrary {
+        let mut library = self.header;
+        library.structures = self.structures;
+        library.structures.sort_by_cached_key(|name, _| self.spans[name].offset);
+        library
+    }
+}
+
+impl<R: Read + Seek> LazyGdsLibrary<R> {
+    /// Walk the record headers after the library header, only STRNAME payloads are read
+    fn index_structures(reader: &mut R, offset: u64) -> GdsReadResult<IndexMap<String, GdsStructureSpan>> {
+        let mut reader = BufReader::new(reader);
+        let mut spans = IndexMap::new();
+        let mut offset = offset;
+        let mut begin: Option<(u64, Option<String>)> = None;
+
+        loop {
+            let mut header = [0u8; 4];
+            reader.read_exact(&mut header)?;
+            let size = u16::from_be_bytes([header[0], header[1]]) as u64;
+            let record_type = u16::from_be_bytes([header[2], header[3]]);
+            if size < 4 {
+                return Err(GdsReadError::InvalidRecordSize(size as usize));
+            }
+
+            match GdsRecordType::from_u16(record_type) {
+                Some(GdsRecordType::BgnStr) => {
+                    begin = Some((offset, None));
+                    reader.seek_relative(size as i64 - 4)?;
+                }
+                Some(GdsRecordType::StrName) => {
+                    let mut buf = vec![0u8; size as usize - 4];
+                    reader.read_exact(&mut buf)?;
+                    while buf.last() == Some(&0) {
+                        buf.pop();
+                    }
+                    if let Some((_, name)) = begin.as_mut() {
+                        *name = Some(String::from_utf8(buf)?);
+                    }
+                }
+                Some(GdsRecordType::EndStr) => {
+                    if let Some((begin_offset, Some(name))) = begin.take() {
+                        let span = GdsStructureSpan { offset: begin_offset, length: offset + size - begin_offset };
+                        spans.insert(name, span);
+                    }
+                    reader.seek_relative(size as i64 - 4)?;
+                }
+                Some(GdsRecordType::EndLib) => break,
+                _ => reader.seek_relative(size as i64 - 4)?,
+            }
+
+            offset += size;
+        }
+
+        Ok(spans)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use crate::*;
+
+    fn library() -> GdsLibrary {
+        let mut top = GdsStructure::new("top");
+        top.srefs.push(GdsSrefBuilder::default()
+            .s_name("mid".to_string())
+            .xy(vec![GdsDbCoord::new(0, 0)])
+            .build().unwrap());
+        let mut mid = GdsStructure::new("mid");
+        mid.arefs.push(GdsArefBuilder::default()
+            .s_name("leaf".to_string())
+            .col(2)
+            .row(2)
+            .xy(vec![GdsDbCoord::new(0, 0), GdsDbCoord::new(2, 0), GdsDbCoord::new(0, 2)])
+            .build().unwrap());
+        let mut leaf = GdsStructure::new("leaf");
+        leaf.boundarys.push(GdsBoundaryBuilder::default()
+            .layer(1)
+            .xy(vec![GdsDbCoord::new(0, 0), GdsDbCoord::new(1, 0), GdsDbCoord::new(1, 1), GdsDbCoord::new(0, 0)])
+            .build().unwrap());
+        let other = GdsStructure::new("other");
+
+        GdsLibraryBuilder::default()
+            .version(600)
+            .create_date(GdsDateTime::now())
+            .modify_date(GdsDateTime::now())
+            .name("lib".to_string())
+            .usrunits_per_dbunit(1e-3)
+            .meters_per_dbunit(1e-9)
+            .structures([top, mid, leaf, other].into_iter()
+                .map(|s| (s.name.clone(), std::sync::Arc::new(std::sync::RwLock::new(s))))
+                .collect())
+            .build().unwrap()
+    }
+
+    #[test]
+    fn test_index_structures() {
+        let bytes = library().to_bytes().unwrap();
+        let lazy = LazyGdsLibrary::new(Cursor::new(&bytes)).unwrap();
+
+        assert_eq!(lazy.header().name, "lib");
+        let names: Vec<_> = lazy.structure_names().collect();
+        assert_eq!(names, ["top", "mid", "leaf", "other"]);
+
+        // A span starts with BGNSTR and ends with ENDSTR
+        let span = lazy.span("leaf").unwrap();
+        let begin = &bytes[span.offset as usize..];
+        assert_eq!(&begin[2..4], &[0x05, 0x02]);
+        let end = &bytes[(span.offset + span.length) as usize - 4..];
+        assert_eq!(&end[..4], &[0x00, 0x04, 0x07, 0x00]);
+    }
+
+    #[test]
+    fn test_load_hierarchy() {
+        let bytes = library().to_bytes().unwrap();
+        let mut lazy = LazyGdsLibrary::new(Cursor::new(bytes)).unwrap();
+
+        assert!(lazy.structure("missing").unwrap().is_none());
+        let top = lazy.load_hierarchy("top").unwrap().unwrap();
+        assert_eq!(top.read().unwrap().srefs[0].s_name, "mid");
+        assert!(lazy.is_loaded("mid"));
+        assert!(lazy.is_loaded("leaf"));
+        assert!(!lazy.is_loaded("other"));
+
+        let leaf = lazy.loaded()["leaf"].clone();
+        assert_eq!(leaf.read().unwrap().boundarys.len(), 1);
+
+        let library = lazy.into_library();
+        assert_eq!(library.structures.len(), 3);
+    }
+}
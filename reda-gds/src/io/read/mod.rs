@@ -1,57 +1,156 @@
 mod error;
+mod event;
+mod lazy;
 
 pub use error::*;
-use std::collections::HashMap;
+pub use event::*;
+pub use lazy::*;
+use event::GdsReadState;
 use std::fs::File;
-use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::io::{BufReader, Read};
 use std::path::Path;
 use std::sync::{Arc, RwLock};
 
 use crate::{
-    GdsAref, GdsArefBuilder, GdsArefBuilderError, GdsBoundary, GdsBoundaryBuilder, GdsBoundaryBuilderError, GdsBox, GdsBoxBuilder, GdsBoxBuilderError, GdsDateTime, GdsDbCoord, GdsFormat, GdsLibrary, GdsLibraryBuilder, GdsNode, GdsNodeBuilder, GdsNodeBuilderError, GdsPath, GdsPathBuilder, GdsPathBuilderError, GdsPathType, GdsPresentation, GdsSref, GdsSrefBuilder, GdsSrefBuilderError, GdsStructure, GdsText, GdsTextBuilder, GdsTextBuilderError, GdsTransform
+    GdsAref, GdsArefBuilder, GdsArefBuilderError, GdsBoundary, GdsBoundaryBuilder, GdsBoundaryBuilderError, GdsBox, GdsBoxBuilder, GdsBoxBuilderError, GdsDateTime, GdsDbCoord, GdsElement, GdsFormat, GdsLibrary, GdsLibraryBuilder, GdsNode, GdsNodeBuilder, GdsNodeBuilderError, GdsPath, GdsPathBuilder, GdsPathBuilderError, GdsPathType, GdsPresentation, GdsRawRecord, GdsSref, GdsSrefBuilder, GdsSrefBuilderError, GdsStructure, GdsText, GdsTextBuilder, GdsTextBuilderError, GdsTransform
 };
 use super::record::GdsRecordType;
 
+const LIBRARY_OPTIONS: [GdsRecordType; 5] = [
+    GdsRecordType::RefLibs, GdsRecordType::Fonts, GdsRecordType::AttrTable, GdsRecordType::Generations, GdsRecordType::Format,
+];
+
+/// Records a tolerant reader does not skip after the library options
+const LIBRARY_OPTIONS_END: [GdsRecordType; 3] = [GdsRecordType::Uints, GdsRecordType::BgnStr, GdsRecordType::EndLib];
+
+/// Records a tolerant reader does not skip between the elements of a structure
+const STRUCTURE_CONTENT: [GdsRecordType; 10] = [
+    GdsRecordType::Boundary, GdsRecordType::Path, GdsRecordType::SRef, GdsRecordType::ARef, GdsRecordType::Text, 
+    GdsRecordType::Node, GdsRecordType::Box, GdsRecordType::EndStr, GdsRecordType::BgnStr, GdsRecordType::EndLib,
+];
+
+/// Records a tolerant reader does not skip before the end of an element
+const ELEMENT_CONTENT_END: [GdsRecordType; 11] = [
+    GdsRecordType::EndEle, GdsRecordType::Boundary, GdsRecordType::Path, GdsRecordType::SRef, GdsRecordType::ARef, 
+    GdsRecordType::Text, GdsRecordType::Node, GdsRecordType::Box, GdsRecordType::EndStr, GdsRecordType::BgnStr, 
+    GdsRecordType::EndLib,
+];
+
 pub struct GdsReader<R> {
     reader: BufReader<R>,
+    /// Bytes taken from `reader` by `peek_bytes` but not consumed yet
+    peeked: Vec<u8>,
+    /// Count of bytes consumed from the stream
+    position: u64,
+    state: GdsReadState,
+    tolerant: bool,
+    warnings: Vec<GdsReadWarning>,
+    /// Records skipped since the last model that keeps them
+    skipped: Vec<GdsRawRecord>,
+    /// Records skipped between the elements of the current structure
+    structure_records: Vec<GdsRawRecord>,
+    /// Count of records started so far, and its value at the header of the element being read
+    records: u64,
+    element_start: Option<u64>,
+    /// Offset and type of the record being read
+    record_offset: u64,
+    record_type: Option<GdsRecordType>,
+    /// Structure being read, with the index of the element being read and the count read so far
+    structure: Option<String>,
+    element: Option<usize>,
+    element_count: usize,
 }
 
 impl GdsReader<File> {
     pub fn open<P: AsRef<Path>>(path: P) -> GdsReadResult<Self> {
-        let reader = BufReader::new(File::open(path)?);
-        Ok(Self { reader })
+        Ok(Self::new(File::open(path)?))
     }
 }
 
-// impl<R: Read + Seek> GdsReader<R> {
-//     pub fn with_reader(reader: R) -> GdsReadResult<Self> {
-//         Ok(Self { reader: BufReader::new(reader) })
-//     }
-// }
+impl<R: Read> GdsReader<R> {
+    /// Create a reader over any byte source, seekable or not (e.g. stdin)
+    pub fn new(reader: R) -> Self {
+        Self { 
+            reader: BufReader::new(reader),
+            peeked: Vec::with_capacity(4),
+            position: 0,
+            state: GdsReadState::Header,
+            tolerant: false,
+            warnings: Vec::new(),
+            skipped: Vec::new(),
+            structure_records: Vec::new(),
+            records: 0,
+            element_start: None,
+            record_offset: 0,
+            record_type: None,
+            structure: None,
+            element: None,
+            element_count: 0,
+        }
+    }
+
+    /// Count offsets from `position` instead of 0, for a stream that starts inside a file
+    pub fn starting_at(mut self, position: u64) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// Skip unknown records, and known records where they are not expected, instead of failing.
+    ///
+    /// Every skipped record is listed in `warnings`. Their payloads are kept in the
+    /// `extra_records` of the library header, structure or element around them, so they are
+    /// written back unchanged. Records between structures are dropped, and with `next_event`
+    /// so are records between elements.
+    pub fn with_tolerance(mut self, tolerant: bool) -> Self {
+        self.tolerant = tolerant;
+        self
+    }
+
+    /// Records skipped so far, in stream order
+    pub fn warnings(&self) -> &[GdsReadWarning] {
+        &self.warnings
+    }
 
-impl<R: Read + Seek> GdsReader<R> {
+    /// Count of bytes consumed from the underlying stream
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+}
+
+impl<R: Read> GdsReader<R> {
+    /// Read the whole library, the reader must not have yielded any event before
     pub fn read(&mut self) -> GdsReadResult<GdsLibrary> {
-        match self.read_impl() {
-            Ok(gds) => Ok(gds),
-            Err(e) => {
-                let context = match self.reader.stream_position() {
-                    Ok(pos) => format!("read until {pos} bytes"),
-                    Err(e) => format!("error to get bytes bias for '{e}'"),
-                };
-                Err(e.wrap(context)) 
+        if self.state != GdsReadState::Header {
+            return Err(GdsReadError::NotAtStart);
+        }
+        let Some(GdsEvent::Library(mut library)) = self.next_event()? else {
+            return Err(GdsReadError::NotAtStart);
+        };
+
+        let mut current = None;
+        while let Some(event) = self.next_event()? {
+            match event {
+                GdsEvent::BeginStructure(structure) => current = Some(structure),
+                GdsEvent::Element(element) => {
+                    if let Some(structure) = current.as_mut() {
+                        structure.push_element(element);
+                    }
+                }
+                GdsEvent::EndStructure => {
+                    if let Some(mut structure) = current.take() {
+                        structure.extra_records.append(&mut self.structure_records);
+                        library.structures.insert(structure.name.clone(), Arc::new(RwLock::new(structure)));
+                    }
+                }
+                GdsEvent::Library(_) | GdsEvent::EndLibrary => {}
             }
         }
-    }
 
-    fn read_impl(&mut self) -> GdsReadResult<GdsLibrary> {
-        let mut builder = GdsLibraryBuilder::default();
-        self.read_header(&mut builder).map_err(|e| e.wrap("read header"))?;
-        self.read_library(&mut builder).map_err(|e| e.wrap("read Library"))?;
-        Ok(builder.build().unwrap())
+        Ok(library)
     }
 }
 
-impl<R: Read + Seek> GdsReader<R> {
+impl<R: Read> GdsReader<R> {
     fn read_header(&mut self, builder: &mut GdsLibraryBuilder) -> GdsReadResult<()> {
         self.ensure_record(6, GdsRecordType::Header)?;
         builder.version(self.take_i16_record()?);
@@ -59,21 +158,7 @@ impl<R: Read + Seek> GdsReader<R> {
     }
 }
 
-impl<R: Read + Seek> GdsReader<R> {
-    fn read_library(&mut self, builder: &mut GdsLibraryBuilder) -> GdsReadResult<()> {
-        self.read_library_begin(builder).map_err(|e| e.wrap("read library begin"))?;
-
-        self.read_library_name(builder).map_err(|e| e.wrap("read library name"))?;
-        self.read_library_options(builder).map_err(|e| e.wrap(""))?;
-        self.read_units(builder).map_err(|e| e.wrap("read units"))?;
-
-        let structures = self.read_structures().map_err(|e| e.wrap("read structures"))?;
-        builder.structures(structures);
-        
-        self.read_library_end(builder).map_err(|e| e.wrap("read library end"))?;
-        Ok(())
-    }
-
+impl<R: Read> GdsReader<R> {
     fn read_library_begin(&mut self, builder: &mut GdsLibraryBuilder) -> GdsReadResult<()> {
         self.ensure_record(28, GdsRecordType::BgnLib)?;
         self.jump_bytes(4)?;
@@ -100,17 +185,21 @@ impl<R: Read + Seek> GdsReader<R> {
         Ok(())
     }
 
-    fn read_library_end(&mut self, _builder: &mut GdsLibraryBuilder) -> GdsReadResult<()> {
+    fn read_library_end(&mut self) -> GdsReadResult<()> {
         self.ensure_record(4, GdsRecordType::EndLib)?;
         self.jump_bytes(4)?;
         Ok(())
     }
 }
 
-impl<R: Read + Seek> GdsReader<R> {
+impl<R: Read> GdsReader<R> {
     fn read_library_options(&mut self, builder: &mut GdsLibraryBuilder) -> GdsReadResult<()> {
         loop {
             let tp = self.peek_record_type()?;
+            if self.tolerant && !LIBRARY_OPTIONS_END.contains(&tp) && !LIBRARY_OPTIONS.contains(&tp) {
+                self.skip_record()?;
+                continue;
+            }
             match tp {
                 GdsRecordType::RefLibs => self.read_reflibs(builder).map_err(|e| e.wrap("read reflibs"))?,
                 GdsRecordType::Fonts => self.read_fonts(builder).map_err(|e| e.wrap("read reflibs"))?,
@@ -189,36 +278,37 @@ macro_rules! read_required_field {
     };
 }
 
-impl<R: Read + Seek> GdsReader<R> {
-    fn read_structures(&mut self) -> GdsReadResult<HashMap<String, Arc<RwLock<GdsStructure>>>> {
-        let mut structures = HashMap::new();
-        let mut size = 0;
-        while self.check_record_type(GdsRecordType::BgnStr)? {
-            let structure = self.read_structure().map_err(|e| e.wrap(format!("read {size} structure")))?;
-            let name = structure.name.clone();
-            structures.insert(name, Arc::new(RwLock::new(structure)));
-            size += 1;
-        }
-        Ok(structures)
+impl<R: Read> GdsReader<R> {
+    /// Read a whole structure, BGNSTR to ENDSTR, in place of its `next_event` events
+    pub fn read_structure(&mut self) -> GdsReadResult<GdsStructure> {
+        self.read_structure_impl().map_err(|e| self.locate(e))
     }
 
-    fn read_structure(&mut self) -> GdsReadResult<GdsStructure> {
-        let mut s = GdsStructure::default();
-
-        self.read_structure_begin(&mut s).map_err(|e| e.wrap("read structure begin"))?;
-        self.read_structure_name(&mut s).map_err(|e| e.wrap("read structure name"))?;
-
+    fn read_structure_impl(&mut self) -> GdsReadResult<GdsStructure> {
+        let mut s = self.read_structure_header()?;
         self.read_structure_elements(&mut s).map_err(|e| e.wrap("read structure elements"))?;
         self.read_structure_end().map_err(|e| e.wrap("read structure end"))?;
+        s.extra_records.append(&mut self.structure_records);
+        Ok(s)
+    }
 
+    fn read_structure_header(&mut self) -> GdsReadResult<GdsStructure> {
+        let mut s = GdsStructure::default();
+        self.read_structure_begin(&mut s).map_err(|e| e.wrap("read structure begin"))?;
+        self.read_structure_name(&mut s).map_err(|e| e.wrap("read structure name"))?;
+        self.structure = Some(s.name.clone());
+        self.element_count = 0;
+        self.skip_unexpected(&STRUCTURE_CONTENT)?;
+        s.extra_records = std::mem::take(&mut self.skipped);
+        self.structure_records.clear();
         Ok(s)
     }
 
     fn read_structure_begin(&mut self, structure: &mut GdsStructure) -> GdsReadResult<()> {
         self.ensure_record(28, GdsRecordType::BgnStr)?;
         self.jump_bytes(4)?;
-        structure.modify_date = self.take_datetime()?;
         structure.create_date = self.take_datetime()?;
+        structure.modify_date = self.take_datetime()?;
         Ok(())
     }
 
@@ -231,32 +321,51 @@ impl<R: Read + Seek> GdsReader<R> {
     fn read_structure_end(&mut self) -> GdsReadResult<()> {
         self.ensure_record(4, GdsRecordType::EndStr)?;
         self.jump_bytes(4)?;
+        self.structure = None;
         Ok(())
     }
 
     fn read_structure_elements(&mut self, s: &mut GdsStructure) -> GdsReadResult<()> {
-        loop {
-            match self.peek_record_type()? {
-                GdsRecordType::Boundary => 
-                    s.boundarys.push(self.read_element_boundary().map_err(|e| e.wrap("read boundary"))?),
-                GdsRecordType::Path => 
-                    s.paths.push(self.read_element_path().map_err(|e| e.wrap("read path"))?),
-                GdsRecordType::Text => 
-                    s.texts.push(self.read_element_text().map_err(|e| e.wrap("read text"))?),
-                GdsRecordType::ARef => 
-                    s.arefs.push(self.read_element_aref().map_err(|e| e.wrap("read aref"))?),
-                GdsRecordType::SRef => 
-                    s.srefs.push(self.read_element_sref().map_err(|e| e.wrap("read sref"))?),
-                GdsRecordType::Node => 
-                    s.nodes.push(self.read_element_node().map_err(|e| e.wrap("read node"))?),
-                GdsRecordType::Box => 
-                    s.boxes.push(self.read_element_box().map_err(|e| e.wrap("read box"))?),
-                _ => break
-            }
+        while let Some(element) = self.read_element()? {
+            s.push_element(element);
         }
         Ok(())
     }
 
+    /// Read the next element, `None` if the next record does not begin an element
+    fn read_element(&mut self) -> GdsReadResult<Option<GdsElement>> {
+        self.skip_unexpected(&STRUCTURE_CONTENT)?;
+        self.structure_records.append(&mut self.skipped);
+        let record_type = self.peek_record_type()?;
+        self.element = Some(self.element_count);
+        self.element_start = Some(self.records);
+        let element = match record_type {
+            GdsRecordType::Boundary => 
+                self.read_element_boundary().map_err(|e| e.wrap("read boundary"))?.into(),
+            GdsRecordType::Path => 
+                self.read_element_path().map_err(|e| e.wrap("read path"))?.into(),
+            GdsRecordType::Text => 
+                self.read_element_text().map_err(|e| e.wrap("read text"))?.into(),
+            GdsRecordType::ARef => 
+                self.read_element_aref().map_err(|e| e.wrap("read aref"))?.into(),
+            GdsRecordType::SRef => 
+                self.read_element_sref().map_err(|e| e.wrap("read sref"))?.into(),
+            GdsRecordType::Node => 
+                self.read_element_node().map_err(|e| e.wrap("read node"))?.into(),
+            GdsRecordType::Box => 
+                self.read_element_box().map_err(|e| e.wrap("read box"))?.into(),
+            _ => {
+                self.element = None;
+                self.element_start = None;
+                return Ok(None);
+            }
+        };
+        self.element = None;
+        self.element_start = None;
+        self.element_count += 1;
+        Ok(Some(element))
+    }
+
     fn read_element_boundary(&mut self) -> GdsReadResult<GdsBoundary> {
         self.read_element_header()?;
         let mut builder = GdsBoundaryBuilder::default();
@@ -267,7 +376,9 @@ impl<R: Read + Seek> GdsReader<R> {
         read_required_field!(builder.data_type <- self.take_i16_record if DataType  => BuildBoundary(GdsBoundaryBuilderError));
         read_required_field!(builder.xy        <- self.read_xy        if Xy        => BuildBoundary(GdsBoundaryBuilderError));
     
-        self.read_element_end()?;
+        builder.properties(self.read_properties()?);
+
+        builder.extra_records(self.read_element_end()?);
         Ok(builder.build()?)
     }
 
@@ -281,17 +392,18 @@ impl<R: Read + Seek> GdsReader<R> {
         read_required_field!(builder.data_type      <- self.take_i16_record    if DataType  => BuildPath(GdsPathBuilderError));
         read_optional_field!(builder.path_type      <- self.read_path_type     if PathType);
         read_optional_field!(builder.width          <- self.take_i32_record    if Width);
+        read_optional_field!(builder.bgn_extn       <- self.take_i32_record    if BgnExtn);
+        read_optional_field!(builder.end_extn       <- self.take_i32_record    if EndExtn);
         read_required_field!(builder.xy             <- self.read_xy            if Xy        => BuildPath(GdsPathBuilderError));
         read_optional_field!(builder.purpose_layer  <- self.take_i16_record    if TextType);
 
-        if self.peek_record_type()? == GdsRecordType::BgnExtn {
-            self.take_i32_record()?;
-        }
-        if self.peek_record_type()? == GdsRecordType::EndExtn {
-            self.take_i32_record()?;
-        }
+        // Some writers put the extensions after XY
+        read_optional_field!(builder.bgn_extn       <- self.take_i32_record    if BgnExtn);
+        read_optional_field!(builder.end_extn       <- self.take_i32_record    if EndExtn);
 
-        self.read_element_end()?;
+        builder.properties(self.read_properties()?);
+
+        builder.extra_records(self.read_element_end()?);
         Ok(builder.build()?)
     }
 
@@ -305,7 +417,9 @@ impl<R: Read + Seek> GdsReader<R> {
         read_optional_field!(builder.transform <- self.read_transform       if STrans);
         read_required_field!(builder.xy        <- self.read_xy              if Xy        => BuildSref(GdsSrefBuilderError));
 
-        self.read_element_end()?;
+        builder.properties(self.read_properties()?);
+
+        builder.extra_records(self.read_element_end()?);
         Ok(builder.build()?)
     }
 
@@ -325,7 +439,9 @@ impl<R: Read + Seek> GdsReader<R> {
         }
         read_required_field!(builder.xy        <- self.read_xy        if Xy        => BuildAref(GdsArefBuilderError));
 
-        self.read_element_end()?;
+        builder.properties(self.read_properties()?);
+
+        builder.extra_records(self.read_element_end()?);
         Ok(builder.build()?)
     }
 
@@ -333,8 +449,8 @@ impl<R: Read + Seek> GdsReader<R> {
         self.read_element_header()?;
         let mut builder = GdsTextBuilder::default();
 
-        read_optional_field!(builder.plex          <- self.take_i32_record     if Plex);
         read_optional_field!(builder.elf_flags     <- self.take_i16_record     if ElFlags);
+        read_optional_field!(builder.plex          <- self.take_i32_record     if Plex);
         read_required_field!(builder.layer         <- self.take_i16_record     if Layer     => BuildText(GdsTextBuilderError));
         read_required_field!(builder.text_type     <- self.take_i16_record     if TextType  => BuildText(GdsTextBuilderError));
         read_optional_field!(builder.presentation  <- self.read_presentation   if Presentation);
@@ -344,7 +460,9 @@ impl<R: Read + Seek> GdsReader<R> {
         read_required_field!(builder.xy            <- self.read_xy             if Xy        => BuildText(GdsTextBuilderError));
         read_required_field!(builder.string        <- self.take_string_record  if String    => BuildText(GdsTextBuilderError));
 
-        self.read_element_end()?;
+        builder.properties(self.read_properties()?);
+
+        builder.extra_records(self.read_element_end()?);
         Ok(builder.build()?)
     }
 
@@ -355,10 +473,12 @@ impl<R: Read + Seek> GdsReader<R> {
         read_optional_field!(builder.elf_flags     <- self.take_i16_record     if ElFlags);
         read_optional_field!(builder.plex          <- self.take_i32_record     if Plex);
         read_required_field!(builder.layer         <- self.take_i16_record     if Layer     => BuildNode(GdsNodeBuilderError));
-        read_required_field!(builder.node_type     <- self.take_i16_record     if Layer     => BuildNode(GdsNodeBuilderError));
-        read_required_field!(builder.xy            <- self.read_xy             if Layer     => BuildNode(GdsNodeBuilderError));
+        read_required_field!(builder.node_type     <- self.take_i16_record     if NodeType  => BuildNode(GdsNodeBuilderError));
+        read_required_field!(builder.xy            <- self.read_xy             if Xy        => BuildNode(GdsNodeBuilderError));
+
+        builder.properties(self.read_properties()?);
 
-        self.read_element_end()?;
+        builder.extra_records(self.read_element_end()?);
         Ok(builder.build()?)
     }
 
@@ -369,10 +489,12 @@ impl<R: Read + Seek> GdsReader<R> {
         read_optional_field!(builder.elf_flags     <- self.take_i16_record     if ElFlags);
         read_optional_field!(builder.plex          <- self.take_i32_record     if Plex);
         read_required_field!(builder.layer         <- self.take_i16_record     if Layer     => BuildBox(GdsBoxBuilderError));
-        read_required_field!(builder.box_type      <- self.take_i16_record     if Layer     => BuildBox(GdsBoxBuilderError));
-        read_required_field!(builder.xy            <- self.read_xy             if Layer     => BuildBox(GdsBoxBuilderError));
+        read_required_field!(builder.box_type      <- self.take_i16_record     if BoxType   => BuildBox(GdsBoxBuilderError));
+        read_required_field!(builder.xy            <- self.read_xy             if Xy        => BuildBox(GdsBoxBuilderError));
+
+        builder.properties(self.read_properties()?);
 
-        self.read_element_end()?;
+        builder.extra_records(self.read_element_end()?);
         Ok(builder.build()?)
     }
 
@@ -382,10 +504,24 @@ impl<R: Read + Seek> GdsReader<R> {
         Ok(())
     }
 
-    fn read_element_end(&mut self) -> GdsReadResult<()> {
+    /// Read ENDEL, return the records skipped in the element
+    fn read_element_end(&mut self) -> GdsReadResult<Vec<GdsRawRecord>> {
+        self.skip_unexpected(&ELEMENT_CONTENT_END)?;
         self.ensure_record(4, GdsRecordType::EndEle)?;
         self.jump_bytes(4)?;
-        Ok(())
+        Ok(std::mem::take(&mut self.skipped))
+    }
+
+    /// <property>: PROPATTR PROPVALUE
+    pub fn read_properties(&mut self) -> GdsReadResult<Vec<(i16, String)>> {
+        let mut properties = Vec::new();
+        while self.peek_record_type()? == GdsRecordType::PropAttr {
+            let attr = self.take_i16_record()?;
+            self.ensure_record_type(GdsRecordType::PropValue)?;
+            let value = self.take_string_record()?;
+            properties.push((attr, value));
+        }
+        Ok(properties)
     }
 
     pub fn read_col_row(&mut self) -> GdsReadResult<(i16, i16)> {
@@ -398,7 +534,7 @@ impl<R: Read + Seek> GdsReader<R> {
 
     pub fn read_xy(&mut self) -> GdsReadResult<Vec<GdsDbCoord>> {
         let mut header = [0u8; 4];
-        self.reader.read_exact(&mut header)?;
+        self.read_exact(&mut header)?;
         let record_size = u16::from_be_bytes([header[0], header[1]]) as usize;
         let record_type = u16::from_be_bytes([header[2], header[3]]);
         let rec_type = GdsRecordType::from_u16(record_type)
@@ -429,12 +565,12 @@ impl<R: Read + Seek> GdsReader<R> {
 
         // Is mag?
         if self.peek_record_type()? == GdsRecordType::Mag {
-            transform.magnification = self.take_f64_record()?;
+            transform.magnification = Some(self.take_f64_record()?);
         }
 
         // If angle
         if self.peek_record_type()? == GdsRecordType::Angle {
-            transform.angle = self.take_f64_record()?;
+            transform.angle = Some(self.take_f64_record()?);
         }
 
         Ok(transform)
@@ -455,7 +591,7 @@ impl<R: Read + Seek> GdsReader<R> {
 
 /// These method do not check record type, please ensure it correct!
 /// And will take all record!
-impl<R: Read + Seek> GdsReader<R> {
+impl<R: Read> GdsReader<R> {
     fn take_u16_record(&mut self) -> GdsReadResult<u16> {
         self.ensure_record_size(6)?;
         self.jump_bytes(4)?; // Jump record header
@@ -469,7 +605,7 @@ impl<R: Read + Seek> GdsReader<R> {
     }
 
     fn take_i32_record(&mut self) -> GdsReadResult<i32> {
-        self.ensure_record_size(6)?;
+        self.ensure_record_size(8)?;
         self.jump_bytes(4)?;
         self.take_i32()
     }
@@ -488,10 +624,10 @@ impl<R: Read + Seek> GdsReader<R> {
 }
 
 /// Methods for take value from `reader`
-impl<R: Read + Seek> GdsReader<R> {
+impl<R: Read> GdsReader<R> {
     fn take_record_size(&mut self) -> GdsReadResult<usize> {
         let mut buf = [0u8; 2];
-        self.reader.read_exact(&mut buf)?;
+        self.read_exact(&mut buf)?;
         let size = u16::from_be_bytes(buf) as usize;
         if size < 4 {
             return Err(GdsReadError::InvalidRecordSize(size));
@@ -501,25 +637,25 @@ impl<R: Read + Seek> GdsReader<R> {
 
     fn take_u16(&mut self) -> GdsReadResult<u16> {
         let mut buf = [0u8; 2];
-        self.reader.read_exact(&mut buf)?;
+        self.read_exact(&mut buf)?;
         Ok(u16::from_be_bytes(buf))
     }
 
     fn take_i16(&mut self) -> GdsReadResult<i16> {
         let mut buf = [0u8; 2];
-        self.reader.read_exact(&mut buf)?;
+        self.read_exact(&mut buf)?;
         Ok(i16::from_be_bytes(buf))
     }
 
     fn take_i32(&mut self) -> GdsReadResult<i32> {
         let mut buf = [0u8; 4];
-        self.reader.read_exact(&mut buf)?;
+        self.read_exact(&mut buf)?;
         Ok(i32::from_be_bytes(buf))
     }
 
     fn take_string(&mut self, len: usize) -> GdsReadResult<String> {
         let mut buf = vec![0u8; len];
-        self.reader.read_exact(&mut buf)?;
+        self.read_exact(&mut buf)?;
         while buf.last() == Some(&0) {
             buf.pop();
         }
@@ -527,36 +663,17 @@ impl<R: Read + Seek> GdsReader<R> {
         Ok(s)
     }
 
+    /// 8-byte excess-64 real, the 56 bits fraction is rounded to the nearest `f64`
     fn take_f64(&mut self) -> GdsReadResult<f64> {
         let mut buf = [0u8; 8];
-        self.reader.read_exact(&mut buf)?;
+        self.read_exact(&mut buf)?;
     
         let data = u64::from_be_bytes(buf); 
-    
-        let sign = (data >> 63) & 0x1;
-        let mut exponent = (data >> 56) & 0x7F;
-        let mut mantissa = data << 8; 
-    
-        if mantissa == 0 {
-            return Ok(0.0);
-        }
-    
-        exponent = ((exponent as i64 - 64) * 4 + 1023) as u64;
-    
-        while (mantissa & 0x8000_0000_0000_0000) == 0 {
-            mantissa <<= 1;
-            exponent -= 1;
-        }
-    
-        mantissa <<= 1;
-        exponent -= 1;
-    
-        let ieee_bits =
-            (sign << 63) |
-            (exponent << 52) |
-            ((mantissa >> 12) & 0x000F_FFFF_FFFF_FFFF);
-    
-        Ok(f64::from_bits(ieee_bits))
+        let exponent = ((data >> 56) & 0x7F) as i32 - 64;
+        let fraction = data & 0x00FF_FFFF_FFFF_FFFF;
+        let value = fraction as f64 * 2f64.powi(4 * exponent - 56);
+
+        Ok(if data >> 63 == 1 { -value } else { value })
     }
 
     fn take_datetime(&mut self) -> GdsReadResult<GdsDateTime> {
@@ -578,7 +695,7 @@ impl<R: Read + Seek> GdsReader<R> {
     }
 }
 
-impl<R: Read + Seek> GdsReader<R> {
+impl<R: Read> GdsReader<R> {
     fn check_record_type(&mut self, tp: GdsRecordType) -> GdsReadResult<bool> {
         let real_tp = self.peek_record_type()?;
         Ok(real_tp == tp)
@@ -613,32 +730,95 @@ impl<R: Read + Seek> GdsReader<R> {
         Ok(u16::from_be_bytes(bytes) as usize)
     }
 
+    /// Type of the next record. A tolerant reader skips the records of unknown type first
     fn peek_record_type(&mut self) -> GdsReadResult<GdsRecordType> {
-        let bytes = self.peek_bytes::<4>()?;
-        let bytes = [bytes[2], bytes[3]];
-        let value = u16::from_be_bytes(bytes);
-        match GdsRecordType::from_u16(value) {
-            Some(t) => Ok(t),
-            None => Err(GdsReadError::UnsupportRecordType(value))
+        loop {
+            let bytes = self.peek_bytes::<4>()?;
+            let bytes = [bytes[2], bytes[3]];
+            let value = u16::from_be_bytes(bytes);
+            match GdsRecordType::from_u16(value) {
+                Some(t) => {
+                    self.record_type = Some(t);
+                    return Ok(t);
+                }
+                None if self.tolerant => self.skip_record()?,
+                None => return Err(GdsReadError::UnsupportRecordType(value)),
+            }
         }
     }
 
-    fn peek_bytes<const L: usize>(&mut self) -> GdsReadResult<[u8; L]> {
-        let mut bytes = [0u8; L];
-        let buf = self.reader.fill_buf()?;
-        if buf.len() < L {
-            return Err(GdsReadError::Io(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "Not enough bytes to peek")));
+    fn locate(&self, error: GdsReadError) -> GdsReadError {
+        let location = GdsReadLocation {
+            offset: self.record_offset,
+            record_type: self.record_type,
+            structure: self.structure.clone(),
+            element: self.element,
+        };
+        GdsReadError::Located(location, Box::new(error))
+    }
+
+    /// A tolerant reader skips records until one of `expected`, a strict one does nothing
+    fn skip_unexpected(&mut self, expected: &[GdsRecordType]) -> GdsReadResult<()> {
+        while self.tolerant && !expected.contains(&self.peek_record_type()?) {
+            self.skip_record()?;
         }
+        Ok(())
+    }
+
+    /// Take the next record as is into `skipped` and warn about it
+    fn skip_record(&mut self) -> GdsReadResult<()> {
+        let offset = self.position;
+        let size = self.take_record_size()?;
+        let record_type = self.take_u16()?;
+        let mut data = vec![0u8; size - 4];
+        self.read_exact(&mut data)?;
+        self.warnings.push(GdsReadWarning { offset, record_type, size });
+        let position = self.element_start.map(|start| (self.records - start) as usize);
+        self.skipped.push(GdsRawRecord { record_type, data, position });
+        Ok(())
+    }
 
-        for i in 0..L {
-            bytes[i] = buf[i];
+    fn peek_bytes<const L: usize>(&mut self) -> GdsReadResult<[u8; L]> {
+        // Records are always peeked before being consumed, so this is the start of a record
+        if self.peeked.is_empty() {
+            self.record_offset = self.position;
+            self.record_type = None;
+            self.records += 1;
+        }
+        while self.peeked.len() < L {
+            let mut byte = [0u8; 1];
+            if self.reader.read(&mut byte)? == 0 {
+                return Err(GdsReadError::Io(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "Not enough bytes to peek")));
+            }
+            self.peeked.push(byte[0]);
         }
 
+        let mut bytes = [0u8; L];
+        bytes.copy_from_slice(&self.peeked[..L]);
         Ok(bytes)
     }
 
-    fn jump_bytes(&mut self, size: i64) -> GdsReadResult<()> {
-        self.reader.seek(SeekFrom::Current(size))?;
+    fn jump_bytes(&mut self, size: usize) -> GdsReadResult<()> {
+        let from_peeked = size.min(self.peeked.len());
+        self.peeked.drain(..from_peeked);
+
+        let rest = (size - from_peeked) as u64;
+        let jumped = std::io::copy(&mut (&mut self.reader).take(rest), &mut std::io::sink())?;
+        if jumped != rest {
+            return Err(GdsReadError::Io(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "Not enough bytes to jump")));
+        }
+
+        self.position += size as u64;
         Ok(())
     }
-}
\ No newline at end of file
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> GdsReadResult<()> {
+        let from_peeked = buf.len().min(self.peeked.len());
+        buf[..from_peeked].copy_from_slice(&self.peeked[..from_peeked]);
+        self.peeked.drain(..from_peeked);
+
+        self.reader.read_exact(&mut buf[from_peeked..])?;
+        self.position += buf.len() as u64;
+        Ok(())
+    }
+}
@@ -11,12 +11,16 @@ pub enum GdsRecordType {
     Format = 0x3602,
     Mask = 0x3706,
     EndMasks = 0x3800,
+    LibDirSize = 0x3902,
+    SrfName = 0x3A06,
+    LibSecur = 0x3B02,
     Uints = 0x0305,
 
     EndLib = 0x0400,
 
     BgnStr = 0x0502,
     StrName = 0x0606,
+    StrClass = 0x3401,
 
     EndStr = 0x0700,
     EndEle = 0x1100,
@@ -46,6 +50,8 @@ pub enum GdsRecordType {
     String = 0x1906,
     NodeType = 0x2A02,
     BoxType = 0x2E02,
+    PropAttr = 0x2B02,
+    PropValue = 0x2C06,
 
     BgnExtn = 0x3003,
     EndExtn = 0x3103,
@@ -65,10 +71,14 @@ impl GdsRecordType {
             0x3602 => Some(Format),
             0x3706 => Some(Mask),
             0x3800 => Some(EndMasks),
+            0x3902 => Some(LibDirSize),
+            0x3A06 => Some(SrfName),
+            0x3B02 => Some(LibSecur),
             0x0305 => Some(Uints),
             0x0400 => Some(EndLib),
             0x0502 => Some(BgnStr),
             0x0606 => Some(StrName),
+            0x3401 => Some(StrClass),
             0x0700 => Some(EndStr),
             0x1100 => Some(EndEle),
             0x0800 => Some(Boundary),
@@ -95,6 +105,8 @@ impl GdsRecordType {
             0x1906 => Some(String),
             0x2A02 => Some(NodeType),
             0x2E02 => Some(BoxType),
+            0x2B02 => Some(PropAttr),
+            0x2C06 => Some(PropValue),
             0x3003 => Some(BgnExtn),
             0x3103 => Some(EndExtn),
             _ => None,
@@ -122,12 +134,16 @@ impl std::fmt::Display for GdsRecordType {
             Format => "Format",
             Mask => "Mask",
             EndMasks => "EndMasks",
+            LibDirSize => "LibDirSize",
+            SrfName => "SrfName",
+            LibSecur => "LibSecur",
             Uints => "Uints",
 
             EndLib => "EndLib",
 
             BgnStr => "BgnStr",
             StrName => "StrName",
+            StrClass => "StrClass",
 
             EndStr => "EndStr",
             EndEle => "EndEle",
@@ -157,6 +173,8 @@ impl std::fmt::Display for GdsRecordType {
             String => "String",
             NodeType => "NodeType",
             BoxType => "BoxType",
+            PropAttr => "PropAttr",
+            PropValue => "PropValue",
 
             BgnExtn => "BgnExtn",
             EndExtn => "EndExtn",
@@ -1,22 +1,60 @@
-use std::{fs::File, io::Write, path::Path};
-use crate::{GdsAref, GdsBoundary, GdsBox, GdsDateTime, GdsDbCoord, GdsFormat, GdsLibrary, GdsNode, GdsPath, GdsPathType, GdsPresentation, GdsSref, GdsStructure, GdsText, GdsTransform};
-use crate::io::{record::GdsRecordType, GdsWriteResult};
+use std::{collections::VecDeque, fs::File, io::{BufWriter, Write}, path::Path};
+use crate::{GdsAref, GdsBoundary, GdsBox, GdsDateTime, GdsDbCoord, GdsElement, GdsElementKind, GdsFormat, GdsLibrary, GdsNode, GdsPath, GdsPathType, GdsPresentation, GdsRawRecord, GdsSref, GdsStructure, GdsText, GdsTransform};
+use crate::io::{record::GdsRecordType, GdsWriteError, GdsWriteResult};
+
+/// Library header records written before LIBNAME, the other kept records go before UNITS
+const BEFORE_LIBNAME: [u16; 3] = [
+    GdsRecordType::LibDirSize as u16,
+    GdsRecordType::SrfName as u16,
+    GdsRecordType::LibSecur as u16,
+];
 
 pub struct GdsWriter<W> {
     writer: W,
+    validate: bool,
+    /// Kept records of the element being written and not written yet, by position
+    pending: VecDeque<GdsRawRecord>,
+    /// Count of records written since the element header
+    element_records: usize,
 }
 
-impl GdsWriter<File> {
+impl GdsWriter<BufWriter<File>> {
     pub fn open<P: AsRef<Path>>(path: P) -> GdsWriteResult<Self> {
         let file = File::create(path)?;
-        Ok(Self { writer: file })
+        Ok(Self::new(BufWriter::new(file)))
     }
 }
 
 impl<W: Write> GdsWriter<W> {
+    /// Create a writer over any byte sink
+    pub fn new(writer: W) -> Self {
+        Self { writer, validate: false, pending: VecDeque::new(), element_records: 0 }
+    }
+
+    /// Run `GdsLibrary::validate` before writing and refuse libraries with any issue
+    pub fn with_validation(mut self, validate: bool) -> Self {
+        self.validate = validate;
+        self
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Write a whole library. Structures and elements keep the order they were read in, so
+    /// reading then writing a file gives the same bytes, but for the padding after ENDLIB
+    /// and reals more precise than an `f64`
     pub fn write(&mut self, gds: &GdsLibrary) -> GdsWriteResult<()> {
+        if self.validate {
+            let diagnostics = gds.validate();
+            if !diagnostics.is_empty() {
+                return Err(GdsWriteError::Invalid(diagnostics));
+            }
+        }
         self.write_header(&gds)?;
-        self.write_library(gds)
+        self.write_library(gds)?;
+        self.writer.flush()?;
+        Ok(())
     }
 }
 
@@ -26,9 +64,17 @@ impl<W: std::io::Write> GdsWriter<W> {
     }
 
     pub fn write_library(&mut self, gds: &GdsLibrary) -> GdsWriteResult<()> {
+        let (before_name, before_units): (Vec<_>, Vec<_>) = gds.extra_records.iter()
+            .partition(|record| BEFORE_LIBNAME.contains(&record.record_type));
         self.write_begin_library(gds)?;
+        for record in before_name {
+            self.write_raw_record(record)?;
+        }
         self.write_library_name(gds)?;
         self.write_library_options(gds)?;
+        for record in before_units {
+            self.write_raw_record(record)?;
+        }
         self.write_units(gds)?;
         self.write_structures(gds)?;
         self.write_end_library()
@@ -128,48 +174,82 @@ impl<W: std::io::Write> GdsWriter<W> {
     }
 
     pub fn write_structure_name(&mut self, structure: &GdsStructure) -> GdsWriteResult<()> {
-        self.write_string_record(GdsRecordType::StrName, &structure.name)
+        self.write_string_record(GdsRecordType::StrName, &structure.name)?;
+        self.write_raw_records(&structure.extra_records)
     }
 
     pub fn write_structure_end(&mut self) -> GdsWriteResult<()> {
         self.write_empty_record(GdsRecordType::EndStr)
     }
 
+    /// Elements in `element_order`, then the ones it does not cover grouped by kind
     pub fn write_structure_elements(&mut self, structure: &GdsStructure) -> GdsWriteResult<()> {
-        for boundary in &structure.boundarys {
+        let mut boundarys = structure.boundarys.iter();
+        let mut paths = structure.paths.iter();
+        let mut srefs = structure.srefs.iter();
+        let mut arefs = structure.arefs.iter();
+        let mut texts = structure.texts.iter();
+        let mut nodes = structure.nodes.iter();
+        let mut boxes = structure.boxes.iter();
+
+        for kind in &structure.element_order {
+            match kind {
+                GdsElementKind::Boundary => if let Some(e) = boundarys.next() { self.write_boundary_element(e)? },
+                GdsElementKind::Path => if let Some(e) = paths.next() { self.write_path_element(e)? },
+                GdsElementKind::Sref => if let Some(e) = srefs.next() { self.write_sref_element(e)? },
+                GdsElementKind::Aref => if let Some(e) = arefs.next() { self.write_aref_element(e)? },
+                GdsElementKind::Text => if let Some(e) = texts.next() { self.write_text_element(e)? },
+                GdsElementKind::Node => if let Some(e) = nodes.next() { self.write_node_element(e)? },
+                GdsElementKind::Box => if let Some(e) = boxes.next() { self.write_box_element(e)? },
+            }
+        }
+
+        for boundary in boundarys {
             self.write_boundary_element(boundary)?;
         }
 
-        for path in &structure.paths {
+        for path in paths {
             self.write_path_element(path)?;
         }
 
-        for sref in &structure.srefs {
+        for sref in srefs {
             self.write_sref_element(sref)?;
         }
 
-        for aref in &structure.arefs {
+        for aref in arefs {
             self.write_aref_element(aref)?;
         }
 
-        for text in &structure.texts {
+        for text in texts {
             self.write_text_element(text)?;
         }
 
-        for node in &structure.nodes {
+        for node in nodes {
             self.write_node_element(node)?;
         }
 
-        for bx in &structure.boxes {
+        for bx in boxes {
             self.write_box_element(bx)?;
         }
 
         Ok(())
     }
 
-    /// <boundary>: BOUNDARY [ELFLAGS] [PLEX] LAYER DATATYPE XY
+    pub fn write_element(&mut self, element: &GdsElement) -> GdsWriteResult<()> {
+        match element {
+            GdsElement::Boundary(e) => self.write_boundary_element(e),
+            GdsElement::Path(e) => self.write_path_element(e),
+            GdsElement::Sref(e) => self.write_sref_element(e),
+            GdsElement::Aref(e) => self.write_aref_element(e),
+            GdsElement::Text(e) => self.write_text_element(e),
+            GdsElement::Node(e) => self.write_node_element(e),
+            GdsElement::Box(e) => self.write_box_element(e),
+        }
+    }
+
+    /// <boundary>: BOUNDARY [ELFLAGS] [PLEX] LAYER DATATYPE XY {<property>}*
     pub fn write_boundary_element(&mut self, boundary: &GdsBoundary) -> GdsWriteResult<()> {
-        self.write_empty_record(GdsRecordType::Boundary)?;
+        self.write_element_begin_record(GdsRecordType::Boundary, &boundary.extra_records)?;
         if let Some(flags) = boundary.elf_flags {
             self.write_elflags_record(flags)?;
         }
@@ -179,12 +259,13 @@ impl<W: std::io::Write> GdsWriter<W> {
         self.write_layer_record(boundary.layer)?;
         self.write_datatype_record(boundary.data_type)?;
         self.write_xy_record(&boundary.xy)?;
+        self.write_properties(&boundary.properties)?;
         self.write_element_end_record()
     }
 
-    /// <path>: PATH [ELFLAGS] [PLEX] LAYER DATATYPE [PATHTYPE][WIDTH] XY
+    /// <path>: PATH [ELFLAGS] [PLEX] LAYER DATATYPE [PATHTYPE] [WIDTH] [BGNEXTN] [ENDEXTN] XY {<property>}*
     pub fn write_path_element(&mut self, path: &GdsPath) -> GdsWriteResult<()> {
-        self.write_empty_record(GdsRecordType::Path)?;
+        self.write_element_begin_record(GdsRecordType::Path, &path.extra_records)?;
         if let Some(flags) = path.elf_flags {
             self.write_elflags_record(flags)?;
         }
@@ -193,18 +274,27 @@ impl<W: std::io::Write> GdsWriter<W> {
         }
         self.write_layer_record(path.layer)?;
         self.write_datatype_record(path.data_type)?;
-        self.write_pathtype_record(path.path_type)?;
+        if let Some(path_type) = path.path_type {
+            self.write_pathtype_record(path_type)?;
+        }
         if let Some(width) = path.width {
             self.write_width_record(width)?;
         }
+        if let Some(extn) = path.bgn_extn {
+            self.write_i32_record(GdsRecordType::BgnExtn, extn)?;
+        }
+        if let Some(extn) = path.end_extn {
+            self.write_i32_record(GdsRecordType::EndExtn, extn)?;
+        }
         self.write_xy_record(&path.xy)?;
+        self.write_properties(&path.properties)?;
         self.write_element_end_record()
     }
 
-    /// <sref>:   SREF [ELFLAGS] [PLEX] SNAME [<strans>] XY
+    /// <sref>:   SREF [ELFLAGS] [PLEX] SNAME [<strans>] XY {<property>}*
     /// <strans>: STRANS [MAG] [ANGLE]
     pub fn write_sref_element(&mut self, sref: &GdsSref) -> GdsWriteResult<()> {
-        self.write_empty_record(GdsRecordType::SRef)?;
+        self.write_element_begin_record(GdsRecordType::SRef, &sref.extra_records)?;
         if let Some(flags) = sref.elf_flags {
             self.write_elflags_record(flags)?;
         }
@@ -216,32 +306,35 @@ impl<W: std::io::Write> GdsWriter<W> {
             self.write_transform_record(transform)?;
         }
         self.write_xy_record(&sref.xy)?;
+        self.write_properties(&sref.properties)?;
         self.write_element_end_record()
     }
 
-    /// <aref>:   AREF [ELFLAGS] [PLEX] SNAME [<strans>] COLROW XY
+    /// <aref>:   AREF [ELFLAGS] [PLEX] SNAME [<strans>] COLROW XY {<property>}*
     /// <strans>: STRANS [MAG] [ANGLE]
     pub fn write_aref_element(&mut self, aref: &GdsAref) -> GdsWriteResult<()> {
-        self.write_empty_record(GdsRecordType::ARef)?;
+        self.write_element_begin_record(GdsRecordType::ARef, &aref.extra_records)?;
         if let Some(flags) = aref.elf_flags {
             self.write_elflags_record(flags)?;
         }
         if let Some(plex) = aref.plex {
             self.write_plex_record(plex)?;
         }
+        self.write_sname_record(&aref.s_name)?;
         if let Some(transform) = &aref.transform {
             self.write_transform_record(transform)?;
         }
         self.write_colrow_record(aref.col, aref.row)?;
         self.write_xy_record(&aref.xy)?;
+        self.write_properties(&aref.properties)?;
         self.write_element_end_record()
     }
 
-    /// <text>:     TEXT [ELFLAGS] [PLEX] LAYER <textbody>
+    /// <text>:     TEXT [ELFLAGS] [PLEX] LAYER <textbody> {<property>}*
     /// <textbody>: TEXTYPE [PRESENTATION] [PATHTYPE] [WIDTH] [<strans>] XY STRING
     /// <strans>:   STRANS [MAG] [ANGLE]
     pub fn write_text_element(&mut self, text: &GdsText) -> GdsWriteResult<()> {
-        self.write_empty_record(GdsRecordType::Text)?;
+        self.write_element_begin_record(GdsRecordType::Text, &text.extra_records)?;
         if let Some(flags) = text.elf_flags {
             self.write_elflags_record(flags)?;
         }
@@ -253,7 +346,9 @@ impl<W: std::io::Write> GdsWriter<W> {
         if let Some(pres) = &text.presentation {
             self.write_presentation_record(pres)?;
         }
-        self.write_pathtype_record(text.path_type)?;
+        if let Some(path_type) = text.path_type {
+            self.write_pathtype_record(path_type)?;
+        }
         if let Some(width) = text.width {
             self.write_width_record(width)?;
         }
@@ -262,12 +357,13 @@ impl<W: std::io::Write> GdsWriter<W> {
         }
         self.write_xy_record(&text.xy)?;
         self.write_ascii_string_record(&text.string)?;
+        self.write_properties(&text.properties)?;
         self.write_element_end_record()
     }
 
-    /// <node>: NODE [ELFLAGS]. [PLEX] LAYER NODETYPE XY
+    /// <node>: NODE [ELFLAGS]. [PLEX] LAYER NODETYPE XY {<property>}*
     pub fn write_node_element(&mut self, node: &GdsNode) -> GdsWriteResult<()> {
-        self.write_empty_record(GdsRecordType::Node)?;
+        self.write_element_begin_record(GdsRecordType::Node, &node.extra_records)?;
         if let Some(flags) = node.elf_flags {
             self.write_elflags_record(flags)?;
         }
@@ -277,12 +373,13 @@ impl<W: std::io::Write> GdsWriter<W> {
         self.write_layer_record(node.layer)?;
         self.write_nodetype_record(node.node_type)?;
         self.write_xy_record(&node.xy)?;
+        self.write_properties(&node.properties)?;
         self.write_element_end_record()
     }
 
-    /// <box>: NODE [ELFLAGS]. [PLEX] LAYER BOXTYPE XY
+    /// <box>: NODE [ELFLAGS]. [PLEX] LAYER BOXTYPE XY {<property>}*
     pub fn write_box_element(&mut self, bx: &GdsBox) -> GdsWriteResult<()> {
-        self.write_empty_record(GdsRecordType::Box)?;
+        self.write_element_begin_record(GdsRecordType::Box, &bx.extra_records)?;
         if let Some(flags) = bx.elf_flags {
             self.write_elflags_record(flags)?;
         }
@@ -292,16 +389,39 @@ impl<W: std::io::Write> GdsWriter<W> {
         self.write_layer_record(bx.layer)?;
         self.write_boxtype_record(bx.box_type)?;
         self.write_xy_record(&bx.xy)?;
+        self.write_properties(&bx.properties)?;
         self.write_element_end_record()
     }
 }
 
 /// Method to write specify record
 impl<W: Write> GdsWriter<W> {
+    /// Element header, the kept records of the element are then written at their position
+    pub fn write_element_begin_record(&mut self, record_type: GdsRecordType, extra_records: &[GdsRawRecord]) -> GdsWriteResult<()> {
+        let mut pending: Vec<GdsRawRecord> = extra_records.to_vec();
+        pending.sort_by_key(|record| record.position.unwrap_or(usize::MAX));
+        self.pending = pending.into();
+        self.element_records = 0;
+        self.write_empty_record(record_type)
+    }
+
+    /// ENDEL, after the kept records of the element not written yet
     pub fn write_element_end_record(&mut self) -> GdsWriteResult<()> {
+        while let Some(record) = self.pending.pop_front() {
+            self.write_raw_record(&record)?;
+        }
         self.write_empty_record(GdsRecordType::EndEle)
     }
 
+    /// <property>: PROPATTR PROPVALUE
+    pub fn write_properties(&mut self, properties: &[(i16, String)]) -> GdsWriteResult<()> {
+        for (attr, value) in properties {
+            self.write_i16_record(GdsRecordType::PropAttr, *attr)?;
+            self.write_string_record(GdsRecordType::PropValue, value)?;
+        }
+        Ok(())
+    }
+
     pub fn write_elflags_record(&mut self, elf_flags: i16) -> GdsWriteResult<()> {
         self.write_i16_record(GdsRecordType::ElFlags, elf_flags)
     }
@@ -344,12 +464,12 @@ impl<W: Write> GdsWriter<W> {
         let value = tranform.flag.to_u16();
         self.write_u16_record(GdsRecordType::STrans, value)?;
 
-        if tranform.magnification != 1.0 {
-            self.write_f64_record(GdsRecordType::Mag, tranform.magnification)?;
+        if let Some(magnification) = tranform.magnification {
+            self.write_f64_record(GdsRecordType::Mag, magnification)?;
         }
 
-        if tranform.angle != 0.0 {
-            self.write_f64_record(GdsRecordType::Angle, tranform.angle)?;
+        if let Some(angle) = tranform.angle {
+            self.write_f64_record(GdsRecordType::Angle, angle)?;
         }
 
         Ok(())
@@ -420,6 +540,22 @@ impl<W: Write> GdsWriter<W> {
         Ok(())
     }
 
+    pub fn write_raw_record(&mut self, record: &GdsRawRecord) -> GdsWriteResult<()> {
+        let size = u16::try_from(record.data.len() + 4)
+            .map_err(|_| GdsWriteError::RawRecordTooLong(record.record_type, record.data.len()))?;
+        self.write_u16(size)?;
+        self.write_u16(record.record_type)?;
+        self.writer.write_all(&record.data)?;
+        Ok(())
+    }
+
+    pub fn write_raw_records(&mut self, records: &[GdsRawRecord]) -> GdsWriteResult<()> {
+        for record in records {
+            self.write_raw_record(record)?;
+        }
+        Ok(())
+    }
+
     pub fn write_empty_record(&mut self, record_type: GdsRecordType) -> GdsWriteResult<()> {
         self.write_record(4, record_type)
     }
@@ -437,7 +573,14 @@ impl<W: Write> GdsWriter<W> {
     }
 
     fn write_record(&mut self, size: usize, tp: GdsRecordType) -> GdsWriteResult<()> {
-        self.write_u16(size as u16)?;
+        while self.pending.front().is_some_and(|record| record.position.is_some_and(|p| p <= self.element_records)) {
+            let record = self.pending.pop_front().unwrap();
+            self.write_raw_record(&record)?;
+            self.element_records += 1;
+        }
+        let size = u16::try_from(size).map_err(|_| GdsWriteError::RecordTooLong(tp, size))?;
+        self.element_records += 1;
+        self.write_u16(size)?;
         self.write_u16(tp as u16)?;
         Ok(())
     }
@@ -480,32 +623,25 @@ impl<W: Write> GdsWriter<W> {
         Ok(())
     }
 
+    /// 8-byte excess-64 real: sign bit, 7 bits of base 16 exponent, 56 bits of fraction.
+    /// Every normal `f64` is written exactly, the fraction has room for its 53 bits
     fn write_f64_ibm(&mut self, value: f64) -> GdsWriteResult<()> {
-        let mut sign = 0i64;
-        let mut exponent = 0i64;
-        let mut mantissa = 0i64;
-
-        if value != 0.0 {
-            let bits = value.to_bits() as i64;
-            sign = (bits >> 63) & 0x1;
-            exponent = ((bits >> 52) & 0x7ff) - 1023;
-            mantissa = (bits & 0x000f_ffff_ffff_ffff) | 0x0010_0000_0000_0000;
-            mantissa <<= 11; // left align
-            mantissa >>= 1;  // discard one bit
-
-            let shift = (-exponent) & 0x3;
-            for _ in 0..shift {
-                mantissa >>= 1;
-            }
-
-            exponent = (exponent + 3) >> 2;
-            exponent += 64;
-        }
-
-        let ibm = ((sign << 63) | (exponent << 56) | ((mantissa >> 8) & 0x00ff_ffff_ffff_ffff)) as u64;
-        let ibm_bytes = ibm.to_be_bytes();
-        self.writer.write_all(&ibm_bytes)?;
-
+        let mut ibm = 0u64;
+
+        if value != 0.0 && value.is_normal() {
+            let bits = value.to_bits();
+            let sign = bits >> 63;
+            // value = mantissa / 2^53 * 2^shift, with mantissa / 2^53 in [0.5, 1)
+            let mantissa = (bits & 0x000f_ffff_ffff_ffff) | 0x0010_0000_0000_0000;
+            let shift = ((bits >> 52) & 0x7ff) as i64 - 1022;
+            // shift = 4 * exponent - s, so the fraction is mantissa / 2^53 / 2^s
+            let exponent = (shift + 3).div_euclid(4);
+            let s = 4 * exponent - shift;
+            let biased = (exponent + 64).clamp(0, 127) as u64;
+            ibm = (sign << 63) | (biased << 56) | (mantissa << (3 - s));
+        }
+
+        self.writer.write_all(&ibm.to_be_bytes())?;
         Ok(())
     }
 
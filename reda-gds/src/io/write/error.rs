@@ -1,8 +1,19 @@
+use crate::GdsDiagnostic;
+use crate::io::record::GdsRecordType;
 
 #[derive(Debug, thiserror::Error)]
 pub enum GdsWriteError {
     #[error("Io error '{0}'")]
     Io(#[from] std::io::Error),
+
+    #[error("{} validation issues, first: {}", .0.len(), .0[0])]
+    Invalid(Vec<GdsDiagnostic>),
+
+    #[error("Raw record 0x{0:04x} has {1} bytes of data, more than a record holds")]
+    RawRecordTooLong(u16, usize),
+
+    #[error("{0:?} record of {1} bytes, more than a record holds")]
+    RecordTooLong(GdsRecordType, usize),
 }
 
 pub type GdsWriteResult<T> = Result<T, GdsWriteError>;
\ No newline at end of file
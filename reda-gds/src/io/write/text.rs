@@ -1,20 +1,36 @@
-use std::{fs::File, path::Path};
-use crate::{GdsAref, GdsBoundary, GdsBox, GdsLibrary, GdsNode, GdsPath, GdsSref, GdsStructure, GdsText};
+use std::{fs::File, io::{BufWriter, Write}, path::Path};
+use crate::{GdsAref, GdsBoundary, GdsBox, GdsLayerMap, GdsLibrary, GdsNode, GdsPath, GdsSref, GdsStructure, GdsText};
 
 use super::GdsWriteResult;
 
 pub struct TextWriter<W> {
     writer: W,
+    layer_map: Option<GdsLayerMap>,
 }
 
-impl TextWriter<File> {
+impl TextWriter<BufWriter<File>> {
     pub fn open<P: AsRef<Path>>(path: P) -> GdsWriteResult<Self> {
         let file = File::create(path)?;
-        Ok(Self { writer: file })
+        Ok(Self::new(BufWriter::new(file)))
     }
 }
 
-impl<W: std::io::Write> TextWriter<W> {
+impl<W: Write> TextWriter<W> {
+    /// Create a writer over any byte sink
+    pub fn new(writer: W) -> Self {
+        Self { writer, layer_map: None }
+    }
+
+    /// Follow the layer of every element with the name `map` gives its (layer, datatype)
+    pub fn with_layer_map(mut self, map: GdsLayerMap) -> Self {
+        self.layer_map = Some(map);
+        self
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
     pub fn write(&mut self, layout: &GdsLibrary) -> GdsWriteResult<()> {
         let indent = 0;
         self.write_indent(indent)?;
@@ -41,6 +57,7 @@ impl<W: std::io::Write> TextWriter<W> {
             self.write_structure(&structure.read().unwrap(), attr_indent)?;
         }
 
+        self.writer.flush()?;
         Ok(())
     }
 }
@@ -113,7 +130,7 @@ impl<W: std::io::Write> TextWriter<W> {
         }
 
         self.write_indent(attr_indent)?;
-        writeln!(self.writer, "layer: {}", boundary.layer)?;
+        writeln!(self.writer, "layer: {}{}", boundary.layer, self.layer_name(boundary.layer, boundary.data_type))?;
 
         self.write_indent(attr_indent)?;
         writeln!(self.writer, "data_type: {}", boundary.data_type)?;
@@ -128,6 +145,8 @@ impl<W: std::io::Write> TextWriter<W> {
         }
         writeln!(self.writer, "]")?;
 
+        self.write_properties(&boundary.properties, attr_indent)?;
+
         Ok(())
     }
 
@@ -148,7 +167,7 @@ impl<W: std::io::Write> TextWriter<W> {
         }
 
         self.write_indent(attr_indent)?;
-        writeln!(self.writer, "layer: {}", path.layer)?;
+        writeln!(self.writer, "layer: {}{}", path.layer, self.layer_name(path.layer, path.data_type))?;
 
         if let Some(purpose) = path.purpose_layer {
             self.write_indent(attr_indent)?;
@@ -158,14 +177,26 @@ impl<W: std::io::Write> TextWriter<W> {
         self.write_indent(attr_indent)?;
         writeln!(self.writer, "data_type: {}", path.data_type)?;
 
-        self.write_indent(attr_indent)?;
-        writeln!(self.writer, "path_type: {}", path.path_type)?;
+        if let Some(path_type) = path.path_type {
+            self.write_indent(attr_indent)?;
+            writeln!(self.writer, "path_type: {}", path_type)?;
+        }
 
         if let Some(width) = path.width {
             self.write_indent(attr_indent)?;
             writeln!(self.writer, "width: {}", width)?;
         }
 
+        if let Some(extn) = path.bgn_extn {
+            self.write_indent(attr_indent)?;
+            writeln!(self.writer, "bgn_extn: {}", extn)?;
+        }
+
+        if let Some(extn) = path.end_extn {
+            self.write_indent(attr_indent)?;
+            writeln!(self.writer, "end_extn: {}", extn)?;
+        }
+
         self.write_indent(attr_indent)?;
         write!(self.writer, "xy: [")?;
         for (i, coord) in path.xy.iter().enumerate() {
@@ -176,6 +207,8 @@ impl<W: std::io::Write> TextWriter<W> {
         }
         writeln!(self.writer, "]")?;
 
+        self.write_properties(&path.properties, attr_indent)?;
+
         Ok(())
     }
 
@@ -213,9 +246,9 @@ impl<W: std::io::Write> TextWriter<W> {
             writeln!(self.writer)?;
         
             self.write_indent(attr_indent)?;
-            writeln!(self.writer, "mag: {}", transform.magnification)?;
+            writeln!(self.writer, "mag: {}", transform.magnification())?;
             self.write_indent(attr_indent)?;
-            writeln!(self.writer, "angle: {}", transform.angle)?;
+            writeln!(self.writer, "angle: {}", transform.angle())?;
         }
 
         if let Some(coord) = sref.xy.get(0) {
@@ -223,6 +256,8 @@ impl<W: std::io::Write> TextWriter<W> {
             writeln!(self.writer, "coordinate: [{}, {}]", coord.x, coord.y)?;
         }
 
+        self.write_properties(&sref.properties, attr_indent)?;
+
         Ok(())
     }
 
@@ -260,9 +295,9 @@ impl<W: std::io::Write> TextWriter<W> {
             writeln!(self.writer)?;
         
             self.write_indent(attr_indent)?;
-            writeln!(self.writer, "mag: {}", transform.magnification)?;
+            writeln!(self.writer, "mag: {}", transform.magnification())?;
             self.write_indent(attr_indent)?;
-            writeln!(self.writer, "angle: {}", transform.angle)?;
+            writeln!(self.writer, "angle: {}", transform.angle())?;
         }
 
         self.write_indent(attr_indent)?;
@@ -276,6 +311,8 @@ impl<W: std::io::Write> TextWriter<W> {
             writeln!(self.writer, "coordinate: [{}, {}]", coord.x, coord.y)?;
         }
 
+        self.write_properties(&aref.properties, attr_indent)?;
+
         Ok(())
     }
 
@@ -296,7 +333,7 @@ impl<W: std::io::Write> TextWriter<W> {
         }
 
         self.write_indent(attr_indent)?;
-        writeln!(self.writer, "layer: {}", text.layer)?;
+        writeln!(self.writer, "layer: {}{}", text.layer, self.layer_name(text.layer, text.text_type))?;
 
         self.write_indent(attr_indent)?;
         writeln!(self.writer, "data_type: {}", text.text_type)?;
@@ -306,8 +343,10 @@ impl<W: std::io::Write> TextWriter<W> {
             writeln!(self.writer, "presentation: {}", presentation)?;
         }
 
-        self.write_indent(attr_indent)?;
-        writeln!(self.writer, "path_type: {}", text.path_type)?;
+        if let Some(path_type) = text.path_type {
+            self.write_indent(attr_indent)?;
+            writeln!(self.writer, "path_type: {}", path_type)?;
+        }
 
         if let Some(width) = text.width {
             self.write_indent(attr_indent)?;
@@ -329,9 +368,9 @@ impl<W: std::io::Write> TextWriter<W> {
             writeln!(self.writer)?;
             
             self.write_indent(attr_indent)?;
-            writeln!(self.writer, "mag: {}", transform.magnification)?;
+            writeln!(self.writer, "mag: {}", transform.magnification())?;
             self.write_indent(attr_indent)?;
-            writeln!(self.writer, "angle: {}", transform.angle)?;
+            writeln!(self.writer, "angle: {}", transform.angle())?;
         }
 
         if let Some(coord) = text.xy.get(0) {
@@ -342,6 +381,8 @@ impl<W: std::io::Write> TextWriter<W> {
         self.write_indent(attr_indent)?;
         writeln!(self.writer, "string: {}", text.string)?;
 
+        self.write_properties(&text.properties, attr_indent)?;
+
         Ok(())
     }
 
@@ -362,7 +403,7 @@ impl<W: std::io::Write> TextWriter<W> {
         }
 
         self.write_indent(attr_indent)?;
-        writeln!(self.writer, "layer: {}", node.layer)?;
+        writeln!(self.writer, "layer: {}{}", node.layer, self.layer_name(node.layer, node.node_type))?;
 
         self.write_indent(attr_indent)?;
         writeln!(self.writer, "data_type: {}", node.node_type)?;
@@ -374,6 +415,8 @@ impl<W: std::io::Write> TextWriter<W> {
         }
         writeln!(self.writer, "]")?;
 
+        self.write_properties(&node.properties, attr_indent)?;
+
         Ok(())
     }
 
@@ -394,7 +437,7 @@ impl<W: std::io::Write> TextWriter<W> {
         }
 
         self.write_indent(attr_indent)?;
-        writeln!(self.writer, "layer: {}", boxx.layer)?;
+        writeln!(self.writer, "layer: {}{}", boxx.layer, self.layer_name(boxx.layer, boxx.box_type))?;
 
         self.write_indent(attr_indent)?;
         writeln!(self.writer, "box_type: {}", boxx.box_type)?;
@@ -406,11 +449,29 @@ impl<W: std::io::Write> TextWriter<W> {
         }
         writeln!(self.writer, "]")?;
 
+        self.write_properties(&boxx.properties, attr_indent)?;
+
         Ok(())
     }
 }
 
 impl<W: std::io::Write> TextWriter<W> {
+    fn write_properties(&mut self, properties: &[(i16, String)], indent: usize) -> GdsWriteResult<()> {
+        for (attr, value) in properties {
+            self.write_indent(indent)?;
+            writeln!(self.writer, "property: {} = {}", attr, value)?;
+        }
+        Ok(())
+    }
+
+    /// ` (M1.drawing)` for a named pair, empty otherwise
+    fn layer_name(&self, layer: i16, data_type: i16) -> String {
+        match self.layer_map.as_ref().and_then(|map| map.get((layer, data_type))) {
+            Some(name) => format!(" ({})", name),
+            None => String::new(),
+        }
+    }
+
     fn write_indent(&mut self, level: usize) -> GdsWriteResult<()> {
         for _ in 0..level {
             write!(self.writer, "    ")?;
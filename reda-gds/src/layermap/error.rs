@@ -0,0 +1,14 @@
+
+#[derive(Debug, thiserror::Error)]
+pub enum GdsLayerMapError {
+    #[error("Io error '{0}'")]
+    Io(#[from] std::io::Error),
+
+    #[error("Xml error '{0}'")]
+    Xml(#[from] quick_xml::Error),
+
+    #[error("Line {0}: expect 'name [purpose] layer datatype', but got '{1}'")]
+    InvalidLine(usize, String),
+}
+
+pub type GdsLayerMapResult<T> = Result<T, GdsLayerMapError>;
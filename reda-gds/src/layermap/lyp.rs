@@ -0,0 +1,68 @@
+use quick_xml::{events::Event, Reader};
+use crate::{GdsLayerKey, GdsLayerMap, GdsLayerName};
+use super::GdsLayerMapResult;
+
+/// Name and source of one `properties` or `group-members` entry
+#[derive(Default)]
+struct Entry {
+    name: Option<String>,
+    source: Option<String>,
+}
+
+/// Parse a KLayout layer properties file.
+///
+/// Every entry whose source is a single `layer/datatype` is kept, named by its `name`
+/// or else by the layer name of its source. Wildcard sources and unnamed entries are ignored.
+pub(super) fn parse_lyp(content: &str) -> GdsLayerMapResult<GdsLayerMap> {
+    let mut reader = Reader::from_str(content);
+    reader.config_mut().trim_text(true);
+
+    let mut map = GdsLayerMap::new();
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut tag: Vec<u8> = Vec::new();
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => match e.name().as_ref() {
+                b"properties" | b"group-members" => entries.push(Entry::default()),
+                name => tag = name.to_vec(),
+            },
+            Event::Text(text) => {
+                let text = text.unescape()?.trim().to_string();
+                if let Some(entry) = entries.last_mut() {
+                    match tag.as_slice() {
+                        b"name" if !text.is_empty() => entry.name = Some(text),
+                        b"source" => entry.source = Some(text),
+                        _ => {}
+                    }
+                }
+            }
+            Event::End(e) => match e.name().as_ref() {
+                b"properties" | b"group-members" => {
+                    if let Some((key, name)) = entries.pop().and_then(resolve) {
+                        map.insert(key, name);
+                    }
+                }
+                _ => tag.clear(),
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(map)
+}
+
+/// A source is `[name] layer/datatype[@view]`, e.g. `M1 31/0@1`
+fn resolve(entry: Entry) -> Option<(GdsLayerKey, GdsLayerName)> {
+    let source = entry.source?;
+    let mut key = None;
+    let mut source_name = None;
+    for token in source.split_whitespace() {
+        let token = token.split('@').next().unwrap_or_default();
+        match token.contains('/') {
+            true => key = Some(token.parse::<GdsLayerKey>().ok()?),
+            false => source_name = Some(token),
+        }
+    }
+    let name = entry.name.or(source_name.map(str::to_string))?;
+    Some((key?, GdsLayerName::new(name, None)))
+}
@@ -0,0 +1,173 @@
+mod error;
+mod lyp;
+mod text;
+
+pub use error::*;
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+use crate::GdsLayerKey;
+
+/// Name of a (layer, datatype), with a purpose for Cadence layermaps
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GdsLayerName {
+    pub name: String,
+    pub purpose: Option<String>,
+}
+
+impl GdsLayerName {
+    pub fn new<S: Into<String>>(name: S, purpose: Option<S>) -> Self {
+        Self { name: name.into(), purpose: purpose.map(Into::into) }
+    }
+}
+
+/// `name.purpose`, or `name` alone
+impl fmt::Display for GdsLayerName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.purpose {
+            Some(purpose) => write!(f, "{}.{}", self.name, purpose),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+/// Names of (layer, datatype) pairs, read from a KLayout `.lyp` file, a Cadence
+/// layermap or a `name layer datatype` table
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GdsLayerMap {
+    names: BTreeMap<GdsLayerKey, GdsLayerName>,
+}
+
+impl GdsLayerMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read a `.lyp` file by its extension, any other file as a layermap or a table
+    pub fn read<P: AsRef<Path>>(path: P) -> GdsLayerMapResult<Self> {
+        let content = std::fs::read_to_string(&path)?;
+        match path.as_ref().extension().is_some_and(|e| e.eq_ignore_ascii_case("lyp")) {
+            true => Self::from_lyp(&content),
+            false => Self::from_text(&content),
+        }
+    }
+
+    pub fn from_lyp(content: &str) -> GdsLayerMapResult<Self> {
+        lyp::parse_lyp(content)
+    }
+
+    /// Parse Cadence layermap lines, `name purpose layer datatype`, and
+    /// `name layer datatype` table lines
+    pub fn from_text(content: &str) -> GdsLayerMapResult<Self> {
+        text::parse_text(content)
+    }
+
+    /// Name a pair, return the name it replaces
+    pub fn insert<K: Into<GdsLayerKey>>(&mut self, key: K, name: GdsLayerName) -> Option<GdsLayerName> {
+        self.names.insert(key.into(), name)
+    }
+
+    pub fn get<K: Into<GdsLayerKey>>(&self, key: K) -> Option<&GdsLayerName> {
+        self.names.get(&key.into())
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Named pairs in (layer, datatype) order
+    pub fn iter(&self) -> impl Iterator<Item = (GdsLayerKey, &GdsLayerName)> {
+        self.names.iter().map(|(key, name)| (*key, name))
+    }
+
+    /// Pair given as `layer/datatype`, `layer`, `name.purpose` or `name`.
+    ///
+    /// A bare name matches the first pair of that name with any purpose.
+    pub fn resolve(&self, spec: &str) -> Option<GdsLayerKey> {
+        if let Ok(key) = spec.parse::<GdsLayerKey>() {
+            return Some(key);
+        }
+        self.iter().find(|(_, name)| name.to_string() == spec)
+            .or_else(|| self.iter().find(|(_, name)| name.name == spec))
+            .map(|(key, _)| key)
+    }
+
+    /// `31/0 (M1.drawing)`, or `31/0` for a pair without name
+    pub fn label<K: Into<GdsLayerKey>>(&self, key: K) -> String {
+        let key = key.into();
+        match self.get(key) {
+            Some(name) => format!("{} ({})", key, name),
+            None => key.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_formats() {
+        let map = GdsLayerMap::from_text("
+            # Cadence layermap
+            M1    drawing  31 0
+            M1    pin      31 2  extra
+            VIA1  drawing  51 0
+            # simple table
+            marker 63 0
+        ").unwrap();
+
+        assert_eq!(map.len(), 4);
+        assert_eq!(map.get((31, 2)), Some(&GdsLayerName::new("M1", Some("pin"))));
+        assert_eq!(map.label((31, 0)), "31/0 (M1.drawing)");
+        assert_eq!(map.label((1, 0)), "1/0");
+        assert_eq!(map.resolve("M1.pin"), Some(GdsLayerKey::new(31, 2)));
+        assert_eq!(map.resolve("M1"), Some(GdsLayerKey::new(31, 0)));
+        assert_eq!(map.resolve("marker"), Some(GdsLayerKey::new(63, 0)));
+        assert_eq!(map.resolve("7/1"), Some(GdsLayerKey::new(7, 1)));
+        assert_eq!(map.resolve("unknown"), None);
+
+        assert!(matches!(GdsLayerMap::from_text("M1 drawing\n"), Err(GdsLayerMapError::InvalidLine(1, _))));
+    }
+
+    #[test]
+    fn test_lyp() {
+        let map = GdsLayerMap::from_lyp(r#"<?xml version="1.0" encoding="utf-8"?>
+            <layer-properties>
+             <properties>
+              <frame-color>#ff0000</frame-color>
+              <name>Metal 1</name>
+              <source>M1 31/0@1</source>
+             </properties>
+             <properties>
+              <name/>
+              <source>VIA1 51/0@1</source>
+             </properties>
+             <properties>
+              <name>Group</name>
+              <source>*/*@*</source>
+              <group-members>
+               <name>Marker &amp; text</name>
+               <source>63/0@1</source>
+              </group-members>
+             </properties>
+             <properties>
+              <source>1/0@1</source>
+             </properties>
+            </layer-properties>
+        "#).unwrap();
+
+        let names: Vec<(GdsLayerKey, String)> = map.iter().map(|(key, name)| (key, name.to_string())).collect();
+        assert_eq!(names, vec![
+            (GdsLayerKey::new(31, 0), "Metal 1".to_string()),
+            (GdsLayerKey::new(51, 0), "VIA1".to_string()),
+            (GdsLayerKey::new(63, 0), "Marker & text".to_string()),
+        ]);
+    }
+}
@@ -0,0 +1,27 @@
+use crate::{GdsLayerKey, GdsLayerMap, GdsLayerName};
+use super::{GdsLayerMapError, GdsLayerMapResult};
+
+/// Parse a Cadence layermap, `name purpose layer datatype` per line, or a simple
+/// `name layer datatype` table. Both may be mixed, `#` starts a comment.
+pub(super) fn parse_text(content: &str) -> GdsLayerMapResult<GdsLayerMap> {
+    let mut map = GdsLayerMap::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let columns: Vec<&str> = line.split_whitespace().collect();
+        let number = |column: usize| columns.get(column).and_then(|c| c.parse::<i16>().ok());
+        let (key, name) = match (number(1), number(2), number(3)) {
+            // Cadence maps may carry more columns after the datatype
+            (_, Some(layer), Some(data_type)) if columns.len() >= 4 =>
+                (GdsLayerKey::new(layer, data_type), GdsLayerName::new(columns[0], Some(columns[1]))),
+            (Some(layer), Some(data_type), None) if columns.len() == 3 =>
+                (GdsLayerKey::new(layer, data_type), GdsLayerName::new(columns[0], None)),
+            _ => return Err(GdsLayerMapError::InvalidLine(index + 1, line.to_string())),
+        };
+        map.insert(key, name);
+    }
+    Ok(map)
+}
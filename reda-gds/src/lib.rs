@@ -1,5 +1,9 @@
 mod models;
 mod io;
+mod geometry;
+mod ops;
+mod render;
+mod layermap;
 mod library;
 
 pub use library::*;
@@ -1,5 +1,5 @@
 
-use std::collections::HashMap;
+use indexmap::IndexMap;
 use std::path::Path;
 use std::sync::Arc;
 use std::sync::RwLock;
@@ -7,8 +7,12 @@ use derive_builder::Builder;
 
 pub use crate::models::*;
 pub use crate::io::*;
+pub use crate::geometry::*;
+pub use crate::ops::*;
+pub use crate::render::*;
+pub use crate::layermap::*;
 
-#[derive(Clone, Builder)]
+#[derive(Debug, Clone, Builder)]
 #[builder(setter(strip_option))]
 pub struct GdsLibrary {
     pub version: i16,
@@ -30,7 +34,13 @@ pub struct GdsLibrary {
     pub usrunits_per_dbunit: f64,
     pub meters_per_dbunit: f64,
 
-    pub structures: HashMap<String, Arc<RwLock<GdsStructure>>>,
+    /// Records kept by a tolerant reader in the library header. LIBDIRSIZE, SRFNAME and
+    /// LIBSECUR are written back before LIBNAME, the others before UNITS
+    #[builder(default)]
+    pub extra_records: Vec<GdsRawRecord>,
+
+    /// Structures in file order, which writers keep
+    pub structures: IndexMap<String, Arc<RwLock<GdsStructure>>>,
 }
 
 
@@ -40,11 +50,43 @@ impl GdsLibrary {
         reader.read()
     }
 
+    pub fn from_bytes(bytes: &[u8]) -> GdsReadResult<Self> {
+        let mut reader = GdsReader::new(bytes);
+        reader.read()
+    }
+
+    pub fn to_bytes(&self) -> GdsWriteResult<Vec<u8>> {
+        let mut writer = GdsWriter::new(Vec::new());
+        writer.write(self)?;
+        Ok(writer.into_inner())
+    }
+
     pub fn write_gds<P: AsRef<Path>>(&self, path: P) -> GdsWriteResult<()> {
         let mut writer = GdsWriter::open(path)?;
         writer.write(self)
     }
 
+    pub fn read_oasis<P: AsRef<Path>>(path: P) -> OasisReadResult<Self> {
+        let mut reader = OasisReader::open(path)?;
+        reader.read()
+    }
+
+    pub fn from_oasis_bytes(bytes: &[u8]) -> OasisReadResult<Self> {
+        let mut reader = OasisReader::new(bytes);
+        reader.read()
+    }
+
+    pub fn to_oasis_bytes(&self) -> OasisWriteResult<Vec<u8>> {
+        let mut writer = OasisWriter::new(Vec::new());
+        writer.write(self)?;
+        Ok(writer.into_inner())
+    }
+
+    pub fn write_oasis<P: AsRef<Path>>(&self, path: P) -> OasisWriteResult<()> {
+        let mut writer = OasisWriter::open(path)?;
+        writer.write(self)
+    }
+
     pub fn write_text<P: AsRef<Path>>(&self, path: P) -> GdsWriteResult<()> {
         let mut writer = TextWriter::open(path)?;
         writer.write(self)
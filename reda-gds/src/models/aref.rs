@@ -1,7 +1,7 @@
 use derive_builder::Builder;
-use crate::{GdsDbCoord, GdsTransform};
+use crate::{GdsDbCoord, GdsMatrix, GdsRawRecord, GdsTransform, round_to_db};
 
-#[derive(Debug, Clone, Builder)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Builder)]
 #[builder(setter(strip_option))]
 pub struct GdsAref {
     #[builder(default)]
@@ -18,4 +18,69 @@ pub struct GdsAref {
     pub row: i16,
 
     pub xy: Vec<GdsDbCoord>,
+
+    /// PROPATTR/PROPVALUE pairs attached to the element
+    #[builder(default)]
+    pub properties: Vec<(i16, String)>,
+
+    /// Records kept by a tolerant reader without being modelled, written back before ENDEL
+    #[builder(default)]
+    pub extra_records: Vec<GdsRawRecord>,
+}
+
+impl GdsAref {
+    /// Displacements between adjacent columns and between adjacent rows, 
+    /// in the coordinates of the structure holding the AREF
+    pub fn lattice(&self) -> ((f64, f64), (f64, f64)) {
+        let origin = self.xy.first().copied().unwrap_or_default();
+        let step = |corner: Option<&GdsDbCoord>, count: i16| match corner {
+            Some(corner) if count > 0 => (
+                (corner.x as f64 - origin.x as f64) / count as f64,
+                (corner.y as f64 - origin.y as f64) / count as f64,
+            ),
+            _ => (0.0, 0.0),
+        };
+        (step(self.xy.get(1), self.col), step(self.xy.get(2), self.row))
+    }
+
+    /// Reference point of the instance at column `col` and row `row`
+    pub fn instance_origin(&self, col: i16, row: i16) -> GdsDbCoord {
+        let origin = self.xy.first().copied().unwrap_or_default();
+        let ((cx, cy), (rx, ry)) = self.lattice();
+        let (c, r) = (col as f64, row as f64);
+        GdsDbCoord::new(
+            round_to_db(origin.x as f64 + c * cx + r * rx),
+            round_to_db(origin.y as f64 + c * cy + r * ry),
+        )
+    }
+
+    /// Reference point of every instance, row after row
+    pub fn instance_origins(&self) -> Vec<GdsDbCoord> {
+        let mut origins = Vec::with_capacity(self.col.max(0) as usize * self.row.max(0) as usize);
+        for r in 0..self.row.max(0) {
+            for c in 0..self.col.max(0) {
+                origins.push(self.instance_origin(c, r));
+            }
+        }
+        origins
+    }
+
+    /// Matrix from the referenced structure to this structure for every instance
+    pub fn matrices(&self) -> Vec<GdsMatrix> {
+        self.instance_origins()
+            .iter()
+            .map(|origin| GdsMatrix::placement(origin, self.transform.as_ref()))
+            .collect()
+    }
+
+    /// Place the array in the coordinates given by `matrix`
+    pub fn transformed(&self, matrix: &GdsMatrix) -> Self {
+        let origin = self.xy.first().copied().unwrap_or_default();
+        let (_, transform) = matrix.place(&origin, self.transform.as_ref()).to_placement();
+        let transform = match self.transform.is_none() && transform.is_identity() {
+            true => None,
+            false => Some(transform),
+        };
+        Self { xy: matrix.apply_all(&self.xy), transform, ..self.clone() }
+    }
 }
@@ -1,7 +1,7 @@
 use derive_builder::Builder;
-use crate::GdsDbCoord;
+use crate::{GdsDbCoord, GdsLayerKey, GdsMatrix, GdsRawRecord};
 
-#[derive(Debug, Clone, Builder)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Builder)]
 #[builder(setter(strip_option))]
 pub struct GdsBoundary {
     #[builder(default)]
@@ -14,4 +14,22 @@ pub struct GdsBoundary {
     #[builder(default)]
     pub data_type: i16,
     pub xy: Vec<GdsDbCoord>,
+
+    /// PROPATTR/PROPVALUE pairs attached to the element
+    #[builder(default)]
+    pub properties: Vec<(i16, String)>,
+
+    /// Records kept by a tolerant reader without being modelled, written back before ENDEL
+    #[builder(default)]
+    pub extra_records: Vec<GdsRawRecord>,
+}
+
+impl GdsBoundary {
+    pub fn layer_key(&self) -> GdsLayerKey {
+        GdsLayerKey::new(self.layer, self.data_type)
+    }
+
+    pub fn transformed(&self, matrix: &GdsMatrix) -> Self {
+        Self { xy: matrix.apply_all(&self.xy), ..self.clone() }
+    }
 }
@@ -0,0 +1,61 @@
+use crate::{GdsAref, GdsBoundary, GdsBox, GdsNode, GdsPath, GdsSref, GdsText};
+
+/// One element of a structure, whatever its kind
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GdsElement {
+    Boundary(GdsBoundary),
+    Path(GdsPath),
+    Sref(GdsSref),
+    Aref(GdsAref),
+    Text(GdsText),
+    Node(GdsNode),
+    Box(GdsBox),
+}
+
+/// Kind of an element, without its content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GdsElementKind {
+    Boundary,
+    Path,
+    Sref,
+    Aref,
+    Text,
+    Node,
+    Box,
+}
+
+impl GdsElement {
+    pub fn kind(&self) -> GdsElementKind {
+        match self {
+            Self::Boundary(_) => GdsElementKind::Boundary,
+            Self::Path(_) => GdsElementKind::Path,
+            Self::Sref(_) => GdsElementKind::Sref,
+            Self::Aref(_) => GdsElementKind::Aref,
+            Self::Text(_) => GdsElementKind::Text,
+            Self::Node(_) => GdsElementKind::Node,
+            Self::Box(_) => GdsElementKind::Box,
+        }
+    }
+}
+
+macro_rules! impl_from_element {
+    ($($variant:ident($ty:ty)),* $(,)?) => {
+        $(
+            impl From<$ty> for GdsElement {
+                fn from(value: $ty) -> Self {
+                    Self::$variant(value)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_element!(
+    Boundary(GdsBoundary),
+    Path(GdsPath),
+    Sref(GdsSref),
+    Aref(GdsAref),
+    Text(GdsText),
+    Node(GdsNode),
+    Box(GdsBox),
+);
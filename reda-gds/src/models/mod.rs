@@ -7,6 +7,7 @@ mod path;
 mod sref;
 mod text;
 mod structure;
+mod element;
 
 pub use primitive::*;
 pub use aref::*;
@@ -16,4 +17,5 @@ pub use node::*;
 pub use path::*;
 pub use sref::*;
 pub use text::*;
-pub use structure::*;
\ No newline at end of file
+pub use structure::*;
+pub use element::*;
\ No newline at end of file
@@ -1,10 +1,10 @@
 use derive_builder::Builder;
 
-use crate::GdsDbCoord;
+use crate::{GdsDbCoord, GdsLayerKey, GdsMatrix, GdsRawRecord, round_to_db};
 
 use super::GdsPathType;
 
-#[derive(Debug, Default, Clone, Builder)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Builder)]
 #[builder(setter(strip_option))]
 pub struct GdsPath {
     #[builder(default)]
@@ -17,12 +17,40 @@ pub struct GdsPath {
     pub data_type: i16,
 
     #[builder(default)]
-    pub path_type: GdsPathType,
+    pub path_type: Option<GdsPathType>,
     #[builder(default)]
     pub width: Option<i32>,
+    /// BGNEXTN/ENDEXTN extensions of the path ends
+    #[builder(default)]
+    pub bgn_extn: Option<i32>,
+    #[builder(default)]
+    pub end_extn: Option<i32>,
 
     pub xy: Vec<GdsDbCoord>,
     
     #[builder(default)]
     pub purpose_layer: Option<i16>,
-}
\ No newline at end of file
+
+    /// PROPATTR/PROPVALUE pairs attached to the element
+    #[builder(default)]
+    pub properties: Vec<(i16, String)>,
+
+    /// Records kept by a tolerant reader without being modelled, written back before ENDEL
+    #[builder(default)]
+    pub extra_records: Vec<GdsRawRecord>,
+}
+
+impl GdsPath {
+    pub fn layer_key(&self) -> GdsLayerKey {
+        GdsLayerKey::new(self.layer, self.data_type)
+    }
+
+    /// A negative width is absolute and is not magnified
+    pub fn transformed(&self, matrix: &GdsMatrix) -> Self {
+        let width = self.width.map(|width| match width < 0 {
+            true => width,
+            false => round_to_db(width as f64 * matrix.magnification_factor()),
+        });
+        Self { xy: matrix.apply_all(&self.xy), width, ..self.clone() }
+    }
+}
@@ -0,0 +1,40 @@
+use std::fmt;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+/// A layer number with a datatype, or a texttype, boxtype or nodetype depending on the element
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct GdsLayerKey {
+    pub layer: i16,
+    pub data_type: i16,
+}
+
+impl GdsLayerKey {
+    pub fn new(layer: i16, data_type: i16) -> Self {
+        Self { layer, data_type }
+    }
+}
+
+impl From<(i16, i16)> for GdsLayerKey {
+    fn from((layer, data_type): (i16, i16)) -> Self {
+        Self::new(layer, data_type)
+    }
+}
+
+impl fmt::Display for GdsLayerKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.layer, self.data_type)
+    }
+}
+
+/// Parses `layer/datatype`, or `layer` alone for datatype 0
+impl FromStr for GdsLayerKey {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().split_once('/') {
+            Some((layer, data_type)) => Ok(Self::new(layer.trim().parse()?, data_type.trim().parse()?)),
+            None => Ok(Self::new(s.trim().parse()?, 0)),
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use std::ops::Mul;
+use crate::{GdsDbCoord, GdsTransform, GdsTransformFlag};
+
+/// Affine transformation of the plane:
+///
+/// ```text
+/// x' = a * x + b * y + tx
+/// y' = c * x + d * y + ty
+/// ```
+///
+/// A GDS placement is built as: reflection about the X-axis, then magnification,
+/// then counterclockwise rotation, then translation to the reference point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GdsMatrix {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub tx: f64,
+    pub ty: f64,
+}
+
+impl Default for GdsMatrix {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl GdsMatrix {
+    pub const IDENTITY: Self = Self { a: 1.0, b: 0.0, c: 0.0, d: 1.0, tx: 0.0, ty: 0.0 };
+
+    pub fn translation(dx: f64, dy: f64) -> Self {
+        Self { tx: dx, ty: dy, ..Self::IDENTITY }
+    }
+
+    /// Counterclockwise rotation in degrees, exact for multiples of 90
+    pub fn rotation(angle: f64) -> Self {
+        let (sin, cos) = sin_cos_degrees(angle);
+        Self { a: cos, b: -sin, c: sin, d: cos, tx: 0.0, ty: 0.0 }
+    }
+
+    pub fn magnification(mag: f64) -> Self {
+        Self { a: mag, d: mag, ..Self::IDENTITY }
+    }
+
+    /// Reflection about the X-axis: `(x, y) -> (x, -y)`
+    pub fn reflection() -> Self {
+        Self { d: -1.0, ..Self::IDENTITY }
+    }
+
+    /// Build from its parts, applied in GDS order: reflection, magnification, rotation, translation
+    pub fn from_parts(reflect: bool, magnification: f64, angle: f64, dx: f64, dy: f64) -> Self {
+        let mut matrix = Self::magnification(magnification);
+        if reflect {
+            matrix = matrix * Self::reflection();
+        }
+        Self::translation(dx, dy) * Self::rotation(angle) * matrix
+    }
+
+    /// Matrix of an SREF/AREF/TEXT placed at `origin` with an optional STRANS
+    pub fn placement(origin: &GdsDbCoord, transform: Option<&GdsTransform>) -> Self {
+        match transform {
+            Some(t) => Self::from_parts(t.flag.reflect, t.magnification(), t.angle(), origin.x as f64, origin.y as f64),
+            None => Self::translation(origin.x as f64, origin.y as f64),
+        }
+    }
+
+    /// Matrix of an instance placed in a cell which is itself placed by `self`.
+    ///
+    /// Without absolute flags this is `self * placement`. With absolute magnification
+    /// (angle) the magnification (rotation) of `self` is not applied to the instance.
+    pub fn place(&self, origin: &GdsDbCoord, transform: Option<&GdsTransform>) -> Self {
+        let local = Self::placement(origin, transform);
+        let flag = transform.map(|t| t.flag).unwrap_or_default();
+        if !flag.absolute_magnification && !flag.absolute_angle {
+            return *self * local;
+        }
+
+        let mag = if flag.absolute_magnification { 1.0 } else { self.magnification_factor() };
+        let angle = if flag.absolute_angle { 0.0 } else { self.angle() };
+        let (x, y) = self.apply_f64(origin.x as f64, origin.y as f64);
+        let parent = Self::from_parts(self.is_reflected(), mag, angle, x, y);
+        parent * local.linear()
+    }
+
+    /// The same matrix without translation
+    pub fn linear(&self) -> Self {
+        Self { tx: 0.0, ty: 0.0, ..*self }
+    }
+
+    pub fn determinant(&self) -> f64 {
+        self.a * self.d - self.b * self.c
+    }
+
+    /// Mirrored transformations have a negative determinant
+    pub fn is_reflected(&self) -> bool {
+        self.determinant() < 0.0
+    }
+
+    pub fn magnification_factor(&self) -> f64 {
+        self.determinant().abs().sqrt()
+    }
+
+    /// Rotation in degrees within `[0, 360)`, applied after the reflection
+    pub fn angle(&self) -> f64 {
+        let angle = self.c.atan2(self.a).to_degrees();
+        if angle < 0.0 { angle + 360.0 } else { angle }
+    }
+
+    /// Split into the reference point and STRANS of an equivalent placement.
+    /// Only meaningful for matrices made of GDS placements.
+    pub fn to_placement(&self) -> (GdsDbCoord, GdsTransform) {
+        let origin = GdsDbCoord::new(round_to_db(self.tx), round_to_db(self.ty));
+        let transform = GdsTransform {
+            flag: GdsTransformFlag { reflect: self.is_reflected(), ..Default::default() },
+            magnification: Some(self.magnification_factor()).filter(|mag| *mag != 1.0),
+            angle: Some(self.angle()).filter(|angle| *angle != 0.0),
+        };
+        (origin, transform)
+    }
+
+    /// `self` applied after `inner`
+    pub fn compose(&self, inner: &Self) -> Self {
+        Self {
+            a: self.a * inner.a + self.b * inner.c,
+            b: self.a * inner.b + self.b * inner.d,
+            c: self.c * inner.a + self.d * inner.c,
+            d: self.c * inner.b + self.d * inner.d,
+            tx: self.a * inner.tx + self.b * inner.ty + self.tx,
+            ty: self.c * inner.tx + self.d * inner.ty + self.ty,
+        }
+    }
+
+    /// `None` if the matrix is singular (zero magnification)
+    pub fn inverse(&self) -> Option<Self> {
+        let det = self.determinant();
+        if det == 0.0 || !det.is_finite() {
+            return None;
+        }
+        let a = self.d / det;
+        let b = -self.b / det;
+        let c = -self.c / det;
+        let d = self.a / det;
+        Some(Self {
+            a, b, c, d,
+            tx: -(a * self.tx + b * self.ty),
+            ty: -(c * self.tx + d * self.ty),
+        })
+    }
+
+    pub fn apply_f64(&self, x: f64, y: f64) -> (f64, f64) {
+        (self.a * x + self.b * y + self.tx, self.c * x + self.d * y + self.ty)
+    }
+
+    /// Transform a coordinate, rounding the result to the database grid
+    pub fn apply(&self, coord: &GdsDbCoord) -> GdsDbCoord {
+        let (x, y) = self.apply_f64(coord.x as f64, coord.y as f64);
+        GdsDbCoord::new(round_to_db(x), round_to_db(y))
+    }
+
+    pub fn apply_all(&self, coords: &[GdsDbCoord]) -> Vec<GdsDbCoord> {
+        coords.iter().map(|c| self.apply(c)).collect()
+    }
+}
+
+impl Mul for GdsMatrix {
+    type Output = GdsMatrix;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.compose(&rhs)
+    }
+}
+
+impl GdsTransform {
+    /// Matrix of this transform applied at `origin`
+    pub fn matrix(&self, origin: &GdsDbCoord) -> GdsMatrix {
+        GdsMatrix::placement(origin, Some(self))
+    }
+}
+
+impl GdsDbCoord {
+    pub fn transform(&self, matrix: &GdsMatrix) -> GdsDbCoord {
+        matrix.apply(self)
+    }
+}
+
+/// Round half away from zero and saturate to the `i32` range
+pub fn round_to_db(value: f64) -> i32 {
+    value.round().clamp(i32::MIN as f64, i32::MAX as f64) as i32
+}
+
+fn sin_cos_degrees(angle: f64) -> (f64, f64) {
+    let normalized = angle.rem_euclid(360.0);
+    if normalized == 0.0 {
+        (0.0, 1.0)
+    } else if normalized == 90.0 {
+        (1.0, 0.0)
+    } else if normalized == 180.0 {
+        (0.0, -1.0)
+    } else if normalized == 270.0 {
+        (-1.0, 0.0)
+    } else {
+        normalized.to_radians().sin_cos()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transform(reflect: bool, magnification: f64, angle: f64) -> GdsTransform {
+        let mut t = GdsTransform::with_flag(0);
+        t.flag.reflect = reflect;
+        t.magnification = Some(magnification);
+        t.angle = Some(angle);
+        t
+    }
+
+    #[test]
+    fn test_placement() {
+        // Reflect (1, 2) to (1, -2), magnify to (2, -4), rotate to (4, 2), translate to (14, 12)
+        let m = GdsMatrix::placement(&GdsDbCoord::new(10, 10), Some(&transform(true, 2.0, 90.0)));
+        assert_eq!(m.apply(&GdsDbCoord::new(1, 2)), GdsDbCoord::new(14, 12));
+        assert!(m.is_reflected());
+        assert_eq!(m.magnification_factor(), 2.0);
+        assert_eq!(m.angle(), 90.0);
+
+        let (origin, t) = m.to_placement();
+        assert_eq!(origin, GdsDbCoord::new(10, 10));
+        assert_eq!(t, transform(true, 2.0, 90.0));
+    }
+
+    #[test]
+    fn test_compose_inverse() {
+        let outer = GdsMatrix::placement(&GdsDbCoord::new(-5, 7), Some(&transform(false, 1.0, 270.0)));
+        let inner = GdsMatrix::placement(&GdsDbCoord::new(100, 0), Some(&transform(true, 3.0, 45.0)));
+        let m = outer * inner;
+
+        let p = GdsDbCoord::new(12, -34);
+        assert_eq!(m.apply(&p), outer.apply(&inner.apply(&p)));
+
+        let back = m.inverse().unwrap().apply(&m.apply(&p));
+        assert_eq!(back, p);
+        assert!(GdsMatrix::magnification(0.0).inverse().is_none());
+    }
+
+    #[test]
+    fn test_absolute_flags() {
+        let parent = GdsMatrix::placement(&GdsDbCoord::new(0, 0), Some(&transform(false, 2.0, 90.0)));
+
+        let mut t = transform(false, 1.0, 0.0);
+        t.flag.absolute_magnification = true;
+        t.flag.absolute_angle = true;
+        let m = parent.place(&GdsDbCoord::new(10, 0), Some(&t));
+
+        // The reference point still goes through the parent, the instance keeps its own size and angle
+        assert_eq!(m.apply(&GdsDbCoord::new(0, 0)), GdsDbCoord::new(0, 20));
+        assert_eq!(m.apply(&GdsDbCoord::new(1, 0)), GdsDbCoord::new(1, 20));
+
+        let relative = parent.place(&GdsDbCoord::new(10, 0), None);
+        assert_eq!(relative.apply(&GdsDbCoord::new(1, 0)), GdsDbCoord::new(0, 22));
+    }
+}
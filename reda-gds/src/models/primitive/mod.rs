@@ -1,8 +1,16 @@
 mod presentation;
 mod transform;
+mod matrix;
+mod rect;
+mod layer;
+mod record;
 
 pub use presentation::*;
 pub use transform::*;
+pub use matrix::*;
+pub use rect::*;
+pub use layer::*;
+pub use record::*;
 
 use std::fmt;
 use chrono::{Datelike, Timelike, Local};
@@ -11,7 +19,7 @@ use chrono::{Datelike, Timelike, Local};
 /// - 0 for square-ended paths that endflush with their endpoints
 /// - 1 for round-ended paths
 /// - 2 for square-ended paths that extend a half-width beyond their endpoints
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum GdsPathType {
     SquareEnd = 0,
     RoundEnd = 1,
@@ -89,7 +97,7 @@ impl GdsFormat {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct GdsDateTime {
     pub year: i16,
     pub month: i16,
@@ -126,7 +134,7 @@ impl fmt::Display for GdsDateTime {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct GdsDbCoord {
     pub x: i32,
     pub y: i32,
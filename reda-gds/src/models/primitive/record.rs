@@ -0,0 +1,11 @@
+/// A record kept as read, for record types or positions the models do not cover
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GdsRawRecord {
+    /// Record type and data type bytes, as in the record header
+    pub record_type: u16,
+    /// Payload after the 4 bytes header
+    pub data: Vec<u8>,
+    /// Index of the record in its element, the element header being 0. `None` outside
+    /// elements, or to write it just before ENDEL
+    pub position: Option<usize>,
+}
@@ -0,0 +1,113 @@
+use std::fmt;
+use crate::{GdsDbCoord, GdsMatrix};
+
+/// Axis-aligned rectangle in database units, borders included
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GdsRect {
+    pub min: GdsDbCoord,
+    pub max: GdsDbCoord,
+}
+
+impl GdsRect {
+    /// Rectangle with corners `(x0, y0)` and `(x1, y1)`, in any order
+    pub fn new(x0: i32, y0: i32, x1: i32, y1: i32) -> Self {
+        Self {
+            min: GdsDbCoord::new(x0.min(x1), y0.min(y1)),
+            max: GdsDbCoord::new(x0.max(x1), y0.max(y1)),
+        }
+    }
+
+    /// Smallest rectangle holding every point, `None` without points
+    pub fn from_points<'a, I: IntoIterator<Item = &'a GdsDbCoord>>(points: I) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        let mut rect = Self { min: *first, max: *first };
+        for p in points {
+            rect.min.x = rect.min.x.min(p.x);
+            rect.min.y = rect.min.y.min(p.y);
+            rect.max.x = rect.max.x.max(p.x);
+            rect.max.y = rect.max.y.max(p.y);
+        }
+        Some(rect)
+    }
+
+    pub fn width(&self) -> i64 {
+        self.max.x as i64 - self.min.x as i64
+    }
+
+    pub fn height(&self) -> i64 {
+        self.max.y as i64 - self.min.y as i64
+    }
+
+    pub fn area(&self) -> i64 {
+        self.width() * self.height()
+    }
+
+    pub fn center(&self) -> GdsDbCoord {
+        GdsDbCoord::new(
+            ((self.min.x as i64 + self.max.x as i64) / 2) as i32,
+            ((self.min.y as i64 + self.max.y as i64) / 2) as i32,
+        )
+    }
+
+    /// Corners counterclockwise from `min`
+    pub fn corners(&self) -> [GdsDbCoord; 4] {
+        [
+            self.min,
+            GdsDbCoord::new(self.max.x, self.min.y),
+            self.max,
+            GdsDbCoord::new(self.min.x, self.max.y),
+        ]
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            min: GdsDbCoord::new(self.min.x.min(other.min.x), self.min.y.min(other.min.y)),
+            max: GdsDbCoord::new(self.max.x.max(other.max.x), self.max.y.max(other.max.y)),
+        }
+    }
+
+    /// Common part, `None` if the rectangles do not touch
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        let min = GdsDbCoord::new(self.min.x.max(other.min.x), self.min.y.max(other.min.y));
+        let max = GdsDbCoord::new(self.max.x.min(other.max.x), self.max.y.min(other.max.y));
+        (min.x <= max.x && min.y <= max.y).then_some(Self { min, max })
+    }
+
+    /// True if the rectangles share at least one point
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min.x <= other.max.x && other.min.x <= self.max.x &&
+        self.min.y <= other.max.y && other.min.y <= self.max.y
+    }
+
+    pub fn contains(&self, point: &GdsDbCoord) -> bool {
+        self.min.x <= point.x && point.x <= self.max.x &&
+        self.min.y <= point.y && point.y <= self.max.y
+    }
+
+    pub fn contains_rect(&self, other: &Self) -> bool {
+        self.contains(&other.min) && self.contains(&other.max)
+    }
+
+    /// Grow each side by `distance`
+    pub fn expanded(&self, distance: i32) -> Self {
+        Self::new(
+            self.min.x.saturating_sub(distance), 
+            self.min.y.saturating_sub(distance),
+            self.max.x.saturating_add(distance), 
+            self.max.y.saturating_add(distance),
+        )
+    }
+
+    /// Bounding box of the transformed rectangle
+    pub fn transformed(&self, matrix: &GdsMatrix) -> Self {
+        let corners = self.corners().map(|c| matrix.apply(&c));
+        Self::from_points(&corners).unwrap()
+    }
+}
+
+impl fmt::Display for GdsRect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}, {}]", self.min, self.max)
+    }
+}
@@ -7,7 +7,7 @@
 /// - Bit 14 flags absolute angle. 
 /// - Bit 15 (the rightmost bit) and all remaining bits are reserved for future use and must be cleared. 
 ///   If this record is omitted, the element is assumed to have no reflection, non-absolute magnification, and non- absolute angle.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub struct GdsTransformFlag {
     /// Reflect the element about the X-axis before rotation.
     pub reflect: bool,              // Bit 0 (leftmost bit)
@@ -43,7 +43,9 @@ impl GdsTransformFlag {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+/// Transforms compare and hash their effective reals bit for bit, with `-0.0` equal to `0.0`,
+/// so an explicit MAG 1.0 or ANGLE 0 equals an omitted one
+#[derive(Debug, Clone, Copy)]
 pub struct GdsTransform {
     /// STRANS record flags
     pub flag: GdsTransformFlag,
@@ -51,19 +53,54 @@ pub struct GdsTransform {
     /// MAG record 
     /// Eight-Byte Real Contains a double-precision real number (8 bytes), which is the magnification factor. 
     /// If this record is omitted, a magnification factor of one is assumed.
-    pub magnification: f64,
+    pub magnification: Option<f64>,
 
     /// ANGLE record 
     /// Eight-Byte Real Contains a double-precision real number (8 bytes), which is the angular rotation factor. 
-    pub angle: f64,
+    /// If this record is omitted, an angle of zero is assumed.
+    pub angle: Option<f64>,
 }
 
 impl GdsTransform {
     pub fn with_flag(bits: u16) -> Self {
         Self {
             flag: GdsTransformFlag::from_u16(bits),
-            magnification: 1.0,
-            angle: 0.0,
+            magnification: None,
+            angle: None,
         }
     }
-}
\ No newline at end of file
+
+    /// Magnification factor, one when MAG is omitted
+    pub fn magnification(&self) -> f64 {
+        self.magnification.unwrap_or(1.0)
+    }
+
+    /// Rotation in degrees, zero when ANGLE is omitted
+    pub fn angle(&self) -> f64 {
+        self.angle.unwrap_or(0.0)
+    }
+
+    fn key(&self) -> (GdsTransformFlag, u64, u64) {
+        let bits = |value: f64| if value == 0.0 { 0 } else { value.to_bits() };
+        (self.flag, bits(self.magnification()), bits(self.angle()))
+    }
+
+    /// No reflection, magnification or rotation
+    pub fn is_identity(&self) -> bool {
+        !self.flag.reflect && self.magnification() == 1.0 && self.angle() == 0.0
+    }
+}
+
+impl PartialEq for GdsTransform {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for GdsTransform {}
+
+impl std::hash::Hash for GdsTransform {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.key().hash(state);
+    }
+}
@@ -1,7 +1,7 @@
 use derive_builder::Builder;
-use crate::{GdsDbCoord, GdsTransform};
+use crate::{GdsDbCoord, GdsMatrix, GdsRawRecord, GdsTransform};
 
-#[derive(Debug, Clone, Builder)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Builder)]
 #[builder(setter(strip_option))]
 pub struct GdsSref {
     #[builder(default)]
@@ -15,4 +15,31 @@ pub struct GdsSref {
     pub transform: Option<GdsTransform>,
     
     pub xy: Vec<GdsDbCoord>,
+
+    /// PROPATTR/PROPVALUE pairs attached to the element
+    #[builder(default)]
+    pub properties: Vec<(i16, String)>,
+
+    /// Records kept by a tolerant reader without being modelled, written back before ENDEL
+    #[builder(default)]
+    pub extra_records: Vec<GdsRawRecord>,
+}
+
+impl GdsSref {
+    /// Matrix from the referenced structure to this structure
+    pub fn matrix(&self) -> GdsMatrix {
+        let origin = self.xy.first().copied().unwrap_or_default();
+        GdsMatrix::placement(&origin, self.transform.as_ref())
+    }
+
+    /// Place the reference in the coordinates given by `matrix`
+    pub fn transformed(&self, matrix: &GdsMatrix) -> Self {
+        let origin = self.xy.first().copied().unwrap_or_default();
+        let (origin, transform) = matrix.place(&origin, self.transform.as_ref()).to_placement();
+        let transform = match self.transform.is_none() && transform.is_identity() {
+            true => None,
+            false => Some(transform),
+        };
+        Self { xy: vec![origin], transform, ..self.clone() }
+    }
 }
@@ -1,4 +1,4 @@
-use crate::{GdsDateTime, GdsBoundary, GdsPath, GdsSref, GdsAref, GdsText};
+use crate::{GdsDateTime, GdsBoundary, GdsPath, GdsSref, GdsAref, GdsText, GdsElement, GdsElementKind, GdsRawRecord};
 use super::{GdsBox, GdsNode};
 
 #[derive(Debug, Default, Clone)]
@@ -13,6 +13,13 @@ pub struct GdsStructure {
     pub texts: Vec<GdsText>,
     pub nodes: Vec<GdsNode>,
     pub boxes: Vec<GdsBox>,
+    /// Kinds of the elements in stream order, appended by `push_element`.
+    ///
+    /// Writers follow it to interleave the lists of each kind as they were read, then
+    /// write the elements it does not cover grouped by kind.
+    pub element_order: Vec<GdsElementKind>,
+    /// Records kept by a tolerant reader between STRNAME and ENDSTR, written back after STRNAME
+    pub extra_records: Vec<GdsRawRecord>,
 }
 
 impl GdsStructure {
@@ -22,4 +29,30 @@ impl GdsStructure {
             ..Default::default()
         }
     }
-}
\ No newline at end of file
+
+    /// Append an element to the list of its kind and record its position
+    pub fn push_element<E: Into<GdsElement>>(&mut self, element: E) {
+        let element = element.into();
+        self.element_order.push(element.kind());
+        match element {
+            GdsElement::Boundary(e) => self.boundarys.push(e),
+            GdsElement::Path(e) => self.paths.push(e),
+            GdsElement::Sref(e) => self.srefs.push(e),
+            GdsElement::Aref(e) => self.arefs.push(e),
+            GdsElement::Text(e) => self.texts.push(e),
+            GdsElement::Node(e) => self.nodes.push(e),
+            GdsElement::Box(e) => self.boxes.push(e),
+        }
+    }
+
+    /// Whether both structures hold the same elements, whatever their names and dates
+    pub fn same_content(&self, other: &Self) -> bool {
+        self.boundarys == other.boundarys
+            && self.paths == other.paths
+            && self.srefs == other.srefs
+            && self.arefs == other.arefs
+            && self.texts == other.texts
+            && self.nodes == other.nodes
+            && self.boxes == other.boxes
+    }
+}
@@ -1,8 +1,8 @@
-use crate::{GdsDbCoord, GdsTransform};
+use crate::{GdsDbCoord, GdsLayerKey, GdsMatrix, GdsRawRecord, GdsTransform};
 use derive_builder::Builder;
 use super::{GdsPathType, GdsPresentation};
 
-#[derive(Debug, Clone, Builder)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Builder)]
 #[builder(setter(strip_option))]
 pub struct GdsText {
     #[builder(default)]
@@ -18,10 +18,35 @@ pub struct GdsText {
     #[builder(default)]
     pub presentation: Option<GdsPresentation>,
     #[builder(default)]
-    pub path_type: GdsPathType,
+    pub path_type: Option<GdsPathType>,
     #[builder(default)]
     pub width: Option<i32>,
 
     #[builder(default)]
     pub transform: Option<GdsTransform>,
+
+    /// PROPATTR/PROPVALUE pairs attached to the element
+    #[builder(default)]
+    pub properties: Vec<(i16, String)>,
+
+    /// Records kept by a tolerant reader without being modelled, written back before ENDEL
+    #[builder(default)]
+    pub extra_records: Vec<GdsRawRecord>,
+}
+
+impl GdsText {
+    pub fn layer_key(&self) -> GdsLayerKey {
+        GdsLayerKey::new(self.layer, self.text_type)
+    }
+
+    /// The text's own STRANS is composed with `matrix`
+    pub fn transformed(&self, matrix: &GdsMatrix) -> Self {
+        let origin = self.xy.first().copied().unwrap_or_default();
+        let (origin, transform) = matrix.place(&origin, self.transform.as_ref()).to_placement();
+        let transform = match self.transform.is_none() && transform.is_identity() {
+            true => None,
+            false => Some(transform),
+        };
+        Self { xy: vec![origin], transform, ..self.clone() }
+    }
 }
@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+use crate::{GdsAref, GdsBoundary, GdsBox, GdsLibrary, GdsMatrix, GdsNode, GdsPath, GdsRect, GdsSref, GdsStructure, GdsText};
+use super::{GdsOpError, GdsOpResult};
+
+/// Options of the hierarchical bounding box computation
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GdsBBoxOptions {
+    /// Leave TEXT elements out of the bounding box
+    pub ignore_texts: bool,
+}
+
+impl GdsBoundary {
+    pub fn bbox(&self) -> Option<GdsRect> {
+        GdsRect::from_points(&self.xy)
+    }
+}
+
+impl GdsBox {
+    pub fn bbox(&self) -> Option<GdsRect> {
+        GdsRect::from_points(&self.xy)
+    }
+}
+
+impl GdsNode {
+    pub fn bbox(&self) -> Option<GdsRect> {
+        GdsRect::from_points(&self.xy)
+    }
+}
+
+impl GdsText {
+    /// Texts have no extent, this is their reference point
+    pub fn bbox(&self) -> Option<GdsRect> {
+        GdsRect::from_points(self.xy.first())
+    }
+}
+
+impl GdsPath {
+    /// Bounding box of the outline, or of the centreline for a path without width
+    pub fn bbox(&self) -> Option<GdsRect> {
+        match self.to_polygon() {
+            Some(polygon) => polygon.bbox(),
+            None => GdsRect::from_points(&self.xy),
+        }
+    }
+}
+
+impl GdsSref {
+    /// Bounding box of the instance, given the bounding box of the referenced structure
+    pub fn bbox(&self, child: &GdsRect) -> GdsRect {
+        child.transformed(&self.matrix())
+    }
+}
+
+impl GdsAref {
+    /// Bounding box of all instances, given the bounding box of the referenced structure.
+    /// `None` for an empty array.
+    pub fn bbox(&self, child: &GdsRect) -> Option<GdsRect> {
+        if self.col <= 0 || self.row <= 0 {
+            return None;
+        }
+
+        // The lattice is linear, so the corner instances hold every other one
+        let (last_col, last_row) = (self.col - 1, self.row - 1);
+        [(0, 0), (last_col, 0), (0, last_row), (last_col, last_row)]
+            .iter()
+            .map(|&(c, r)| GdsMatrix::placement(&self.instance_origin(c, r), self.transform.as_ref()))
+            .map(|matrix| child.transformed(&matrix))
+            .reduce(|a, b| a.union(&b))
+    }
+}
+
+impl GdsStructure {
+    /// Bounding box of the elements of this structure alone, references left out
+    pub fn local_bbox(&self, options: &GdsBBoxOptions) -> Option<GdsRect> {
+        let texts = self.texts.iter()
+            .filter(|_| !options.ignore_texts)
+            .filter_map(|e| e.bbox());
+
+        self.boundarys.iter().filter_map(|e| e.bbox())
+            .chain(self.paths.iter().filter_map(|e| e.bbox()))
+            .chain(self.boxes.iter().filter_map(|e| e.bbox()))
+            .chain(self.nodes.iter().filter_map(|e| e.bbox()))
+            .chain(texts)
+            .reduce(|a, b| a.union(&b))
+    }
+
+    /// Bounding box including every structure referenced through `library`.
+    /// `None` if the structure holds no geometry at all.
+    pub fn bbox(&self, library: &GdsLibrary, options: &GdsBBoxOptions) -> GdsOpResult<Option<GdsRect>> {
+        GdsBBoxCache::new(library, *options).structure_bbox(self)
+    }
+}
+
+impl GdsLibrary {
+    /// Hierarchical bounding box of the structure named `name`
+    pub fn bbox(&self, name: &str, options: &GdsBBoxOptions) -> GdsOpResult<Option<GdsRect>> {
+        GdsBBoxCache::new(self, *options).bbox(name)
+    }
+}
+
+/// Hierarchical bounding boxes of the structures of a library, each structure is computed once.
+///
+/// Every instance is placed by its own STRANS only: absolute magnification or angle 
+/// are not resolved against the instances above it.
+pub struct GdsBBoxCache<'a> {
+    library: &'a GdsLibrary,
+    options: GdsBBoxOptions,
+    cache: HashMap<String, Option<GdsRect>>,
+    visiting: Vec<String>,
+}
+
+impl<'a> GdsBBoxCache<'a> {
+    pub fn new(library: &'a GdsLibrary, options: GdsBBoxOptions) -> Self {
+        Self {
+            library,
+            options,
+            cache: HashMap::new(),
+            visiting: Vec::new(),
+        }
+    }
+
+    /// Bounding box of the structure named `name`
+    pub fn bbox(&mut self, name: &str) -> GdsOpResult<Option<GdsRect>> {
+        if let Some(bbox) = self.cache.get(name) {
+            return Ok(*bbox);
+        }
+        if self.visiting.iter().any(|n| n == name) {
+            return Err(GdsOpError::ReferenceCycle(name.to_string()));
+        }
+
+        let structure = self.library.structures.get(name)
+            .ok_or_else(|| GdsOpError::StructureNotFound(name.to_string()))?;
+        let structure = structure.read().unwrap();
+
+        self.visiting.push(name.to_string());
+        let bbox = self.structure_bbox(&structure);
+        self.visiting.pop();
+
+        let bbox = bbox?;
+        self.cache.insert(name.to_string(), bbox);
+        Ok(bbox)
+    }
+
+    /// Bounding box of a structure which may not belong to the library
+    pub fn structure_bbox(&mut self, structure: &GdsStructure) -> GdsOpResult<Option<GdsRect>> {
+        let mut bbox = structure.local_bbox(&self.options);
+        let mut add = |rect: Option<GdsRect>| {
+            bbox = match (bbox, rect) {
+                (Some(a), Some(b)) => Some(a.union(&b)),
+                (a, b) => a.or(b),
+            };
+        };
+
+        for sref in &structure.srefs {
+            if let Some(child) = self.bbox(&sref.s_name)? {
+                add(Some(sref.bbox(&child)));
+            }
+        }
+
+        for aref in &structure.arefs {
+            if let Some(child) = self.bbox(&aref.s_name)? {
+                add(aref.bbox(&child));
+            }
+        }
+
+        Ok(bbox)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{GdsDbCoord, GdsPathBuilder, GdsPathType};
+    use super::super::test_utils::*;
+    use super::*;
+
+    #[test]
+    fn test_path_bbox() {
+        let path = GdsPathBuilder::default()
+            .layer(1)
+            .data_type(0)
+            .path_type(GdsPathType::SquareEndExtend)
+            .width(10)
+            .xy(vec![GdsDbCoord::new(0, 0), GdsDbCoord::new(100, 0)])
+            .build().unwrap();
+        assert_eq!(path.bbox(), Some(GdsRect::new(-5, -5, 105, 5)));
+    }
+
+    #[test]
+    fn test_hierarchical_bbox() {
+        let lib = hierarchy();
+        let options = GdsBBoxOptions::default();
+
+        assert_eq!(lib.bbox("leaf", &options).unwrap(), Some(GdsRect::new(0, 0, 10, 10)));
+        // The array spans (0, 0) to (30, 30), the reflected and rotated instance (0, 100) to (10, 110)
+        assert_eq!(lib.bbox("mid", &options).unwrap(), Some(GdsRect::new(0, 0, 30, 110)));
+        assert_eq!(lib.bbox("top", &options).unwrap(), Some(GdsRect::new(100, 0, 130, 110)));
+    }
+
+    #[test]
+    fn test_large_aref_bbox() {
+        // Only the corner instances are placed, not the billion in between
+        let array = aref("leaf", i16::MAX, i16::MAX, (0, 0), (10, 20));
+        let bbox = array.bbox(&GdsRect::new(0, 0, 5, 5));
+        assert_eq!(bbox, Some(GdsRect::new(0, 0, 10 * 32766 + 5, 20 * 32766 + 5)));
+    }
+
+    #[test]
+    fn test_ignore_texts() {
+        let mut top = crate::GdsStructure::new("top");
+        top.boundarys.push(rect(1, 0, 0, 10, 10));
+        top.texts.push(crate::GdsTextBuilder::default()
+            .layer(1)
+            .text_type(0)
+            .string("label".to_string())
+            .xy(vec![GdsDbCoord::new(50, 50)])
+            .build().unwrap());
+        let lib = library(vec![top]);
+
+        let with_texts = lib.bbox("top", &GdsBBoxOptions::default()).unwrap();
+        let without_texts = lib.bbox("top", &GdsBBoxOptions { ignore_texts: true }).unwrap();
+        assert_eq!(with_texts, Some(GdsRect::new(0, 0, 50, 50)));
+        assert_eq!(without_texts, Some(GdsRect::new(0, 0, 10, 10)));
+    }
+}
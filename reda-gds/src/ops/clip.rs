@@ -0,0 +1,305 @@
+use std::sync::{Arc, RwLock};
+use crate::{GdsBoundary, GdsDbCoord, GdsLayerKey, GdsLibrary, GdsPath, GdsRect, GdsRegion, GdsSref, GdsStructure, round_to_db};
+use super::{GdsBBoxCache, GdsBBoxOptions, GdsOpError, GdsOpResult};
+
+/// Options of `GdsLibrary::clip`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GdsClipOptions {
+    /// Flatten references crossing the window edge and cut their shapes,
+    /// instead of keeping them whole as SREF
+    pub flatten_partial: bool,
+}
+
+impl GdsLibrary {
+    /// The part of a structure inside `rect`, as a new structure named `<cell>_clip`.
+    ///
+    /// Shapes inside the window are copied, shapes crossing its edge are cut into boundaries,
+    /// except paths without width which are cut into shorter paths. Texts and nodes are kept
+    /// when they lie inside. References inside the window are kept,
+    /// an AREF crossing the edge is split into one SREF per instance.
+    pub fn clip(&self, cell_name: &str, rect: &GdsRect, options: &GdsClipOptions) -> GdsOpResult<GdsStructure> {
+        let structure = self.structures.get(cell_name)
+            .ok_or_else(|| GdsOpError::StructureNotFound(cell_name.to_string()))?;
+        let structure = structure.read().unwrap();
+
+        let mut clipped = GdsStructure::new(format!("{}_clip", cell_name));
+        clipped.create_date = structure.create_date.clone();
+        clipped.modify_date = structure.modify_date.clone();
+        clip_shapes(&structure, rect, &mut clipped);
+
+        let mut clipper = Clipper {
+            library: self,
+            cell_name,
+            rect,
+            options,
+            bboxes: GdsBBoxCache::new(self, GdsBBoxOptions::default()),
+        };
+        for sref in &structure.srefs {
+            clipper.clip_instance(sref.clone(), &mut clipped)?;
+        }
+        for aref in &structure.arefs {
+            let Some(child) = clipper.bboxes.bbox(&aref.s_name)? else { continue };
+            match aref.bbox(&child) {
+                Some(bbox) if rect.contains_rect(&bbox) => clipped.arefs.push(aref.clone()),
+                Some(bbox) if rect.intersects(&bbox) => {
+                    for matrix in aref.matrices() {
+                        let sref = GdsSref {
+                            elf_flags: aref.elf_flags,
+                            plex: aref.plex,
+                            s_name: aref.s_name.clone(),
+                            transform: None,
+                            xy: vec![Default::default()],
+                            properties: aref.properties.clone(),
+                            extra_records: aref.extra_records.clone(),
+                        };
+                        let sref = sref.transformed(&matrix);
+                        clipper.clip_instance(sref, &mut clipped)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(clipped)
+    }
+
+    /// A library holding the clipped structure and the structures it still references.
+    /// Fails if the library already has a structure named `<cell>_clip`.
+    pub fn clip_library(&self, cell_name: &str, rect: &GdsRect, options: &GdsClipOptions) -> GdsOpResult<GdsLibrary> {
+        let clipped = self.clip(cell_name, rect, options)?;
+        let name = clipped.name.clone();
+        if self.structures.contains_key(&name) {
+            return Err(GdsOpError::NameTaken(name));
+        }
+
+        let mut library = self.clone();
+        library.structures.insert(name.clone(), Arc::new(RwLock::new(clipped)));
+        library.prune_unreachable_from(&[name]);
+        library.structures = library.structures.into_iter()
+            .map(|(name, structure)| {
+                let structure = structure.read().unwrap().clone();
+                (name, Arc::new(RwLock::new(structure)))
+            })
+            .collect();
+        Ok(library)
+    }
+}
+
+struct Clipper<'a> {
+    library: &'a GdsLibrary,
+    cell_name: &'a str,
+    rect: &'a GdsRect,
+    options: &'a GdsClipOptions,
+    bboxes: GdsBBoxCache<'a>,
+}
+
+impl Clipper<'_> {
+    fn clip_instance(&mut self, sref: GdsSref, clipped: &mut GdsStructure) -> GdsOpResult<()> {
+        let Some(child) = self.bboxes.bbox(&sref.s_name)? else { return Ok(()) };
+        let bbox = sref.bbox(&child);
+        if !self.rect.intersects(&bbox) {
+            return Ok(());
+        }
+        if self.rect.contains_rect(&bbox) || !self.options.flatten_partial {
+            clipped.srefs.push(sref);
+            return Ok(());
+        }
+
+        let mut flat = GdsStructure::new(&sref.s_name);
+        let mut stack = vec![self.cell_name.to_string()];
+        self.library.flatten_reference(&sref.s_name, &sref.matrix(), None, &mut flat, &mut stack)?;
+        clip_shapes(&flat, self.rect, clipped);
+        Ok(())
+    }
+}
+
+/// Copy the shapes, texts and nodes of `source` inside the window, cutting those crossing its edge
+fn clip_shapes(source: &GdsStructure, rect: &GdsRect, clipped: &mut GdsStructure) {
+    let window = GdsRegion::from_rect(rect);
+    let cut = |region: GdsRegion, key: GdsLayerKey, template: &GdsBoundary| -> Vec<GdsBoundary> {
+        region.and(&window)
+            .to_boundaries(key)
+            .into_iter()
+            .map(|piece| GdsBoundary { xy: piece.xy, ..template.clone() })
+            .collect()
+    };
+
+    for boundary in &source.boundarys {
+        match boundary.bbox() {
+            Some(bbox) if rect.contains_rect(&bbox) => clipped.boundarys.push(boundary.clone()),
+            Some(bbox) if rect.intersects(&bbox) => {
+                let mut region = GdsRegion::new();
+                region.push_boundary(boundary);
+                clipped.boundarys.extend(cut(region, boundary.layer_key(), boundary));
+            }
+            _ => {}
+        }
+    }
+
+    for path in &source.paths {
+        match path.bbox() {
+            Some(bbox) if rect.contains_rect(&bbox) => clipped.paths.push(path.clone()),
+            Some(bbox) if rect.intersects(&bbox) => match path.to_polygon() {
+                Some(polygon) => {
+                    let mut region = GdsRegion::new();
+                    region.push_boundary(&polygon);
+                    clipped.boundarys.extend(cut(region, path.layer_key(), &polygon));
+                }
+                None => clipped.paths.extend(clip_polyline(&path.xy, rect)
+                    .into_iter()
+                    .map(|xy| GdsPath { xy, ..path.clone() })),
+            },
+            _ => {}
+        }
+    }
+
+    for boxx in &source.boxes {
+        match boxx.bbox() {
+            Some(bbox) if rect.contains_rect(&bbox) => clipped.boxes.push(boxx.clone()),
+            Some(bbox) if rect.intersects(&bbox) => {
+                let mut region = GdsRegion::new();
+                region.push_box(boxx);
+                let template = GdsBoundary {
+                    elf_flags: boxx.elf_flags,
+                    plex: boxx.plex,
+                    layer: boxx.layer,
+                    data_type: boxx.box_type,
+                    xy: Vec::new(),
+                    properties: boxx.properties.clone(),
+                    extra_records: Vec::new(),
+                };
+                clipped.boundarys.extend(cut(region, boxx.layer_key(), &template));
+            }
+            _ => {}
+        }
+    }
+
+    clipped.texts.extend(source.texts.iter()
+        .filter(|text| text.xy.first().is_some_and(|p| rect.contains(p)))
+        .cloned());
+    clipped.nodes.extend(source.nodes.iter()
+        .filter(|node| node.bbox().is_some_and(|bbox| rect.contains_rect(&bbox)))
+        .cloned());
+}
+
+/// Pieces of the polyline inside the window, with the points where it crosses the window
+/// edge rounded to the grid
+fn clip_polyline(xy: &[GdsDbCoord], rect: &GdsRect) -> Vec<Vec<GdsDbCoord>> {
+    let mut pieces: Vec<Vec<GdsDbCoord>> = Vec::new();
+    let mut open = false;
+    for segment in xy.windows(2) {
+        let Some((a, b)) = clip_segment(segment[0], segment[1], rect) else {
+            open = false;
+            continue;
+        };
+        if !open {
+            pieces.push(vec![a]);
+        }
+        let piece = pieces.last_mut().unwrap();
+        if piece.last() != Some(&b) {
+            piece.push(b);
+        }
+        // The next segment goes on with this piece unless this one left the window
+        open = b == segment[1];
+    }
+    pieces.retain(|piece| piece.len() > 1);
+    pieces
+}
+
+/// Liang-Barsky clipping of the segment `p -> q`
+fn clip_segment(p: GdsDbCoord, q: GdsDbCoord, rect: &GdsRect) -> Option<(GdsDbCoord, GdsDbCoord)> {
+    let (dx, dy) = ((q.x - p.x) as f64, (q.y - p.y) as f64);
+    let (mut t0, mut t1) = (0.0f64, 1.0f64);
+    for (d, v, low, high) in [(dx, p.x, rect.min.x, rect.max.x), (dy, p.y, rect.min.y, rect.max.y)] {
+        let (v, low, high) = (v as f64, low as f64, high as f64);
+        if d == 0.0 {
+            if v < low || v > high {
+                return None;
+            }
+        } else {
+            let (a, b) = ((low - v) / d, (high - v) / d);
+            t0 = t0.max(a.min(b));
+            t1 = t1.min(a.max(b));
+        }
+    }
+    if t0 > t1 {
+        return None;
+    }
+
+    let at = |t: f64| GdsDbCoord::new(round_to_db(p.x as f64 + t * dx), round_to_db(p.y as f64 + t * dy));
+    Some((if t0 == 0.0 { p } else { at(t0) }, if t1 == 1.0 { q } else { at(t1) }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::GdsPathBuilder;
+    use super::*;
+    use super::super::test_utils::{hierarchy, library, rect};
+
+    #[test]
+    fn test_clip_shapes() {
+        let mut top = GdsStructure::new("top");
+        top.boundarys.push(rect(1, 0, 0, 100, 100));
+        top.boundarys.push(rect(1, 20, 20, 30, 30));
+        top.boundarys.push(rect(2, 200, 200, 300, 300));
+        let library = library(vec![top]);
+
+        let clipped = library.clip("top", &GdsRect::new(-50, -50, 50, 50), &GdsClipOptions::default()).unwrap();
+        assert_eq!(clipped.name, "top_clip");
+        assert_eq!(clipped.boundarys.len(), 2);
+        assert_eq!(clipped.boundarys[0].bbox(), Some(GdsRect::new(0, 0, 50, 50)));
+        assert_eq!(clipped.boundarys[0].xy.first(), clipped.boundarys[0].xy.last());
+        assert_eq!(clipped.boundarys[1].xy[0], GdsDbCoord::new(20, 20));
+    }
+
+    #[test]
+    fn test_clip_zero_width_path() {
+        let mut top = GdsStructure::new("top");
+        let xy = [(-100, 10), (10, 10), (10, -10), (100, -10), (100, 30), (0, 30), (0, 0)];
+        top.paths.push(GdsPathBuilder::default()
+            .layer(1).data_type(0).width(0)
+            .xy(xy.iter().map(|&(x, y)| GdsDbCoord::new(x, y)).collect())
+            .build().unwrap());
+        let library = library(vec![top]);
+
+        let clipped = library.clip("top", &GdsRect::new(-20, -20, 20, 20), &GdsClipOptions::default()).unwrap();
+        let pieces: Vec<Vec<GdsDbCoord>> = clipped.paths.iter().map(|p| p.xy.clone()).collect();
+        let points = |xy: &[(i32, i32)]| xy.iter().map(|&(x, y)| GdsDbCoord::new(x, y)).collect::<Vec<_>>();
+        assert_eq!(pieces, vec![
+            points(&[(-20, 10), (10, 10), (10, -10), (20, -10)]),
+            points(&[(0, 20), (0, 0)]),
+        ]);
+        assert!(clipped.paths.iter().all(|p| p.width == Some(0)));
+    }
+
+    #[test]
+    fn test_clip_hierarchy() {
+        let library = hierarchy();
+        let window = GdsRect::new(105, 5, 125, 15);
+
+        let kept = library.clip("top", &window, &GdsClipOptions::default()).unwrap();
+        assert_eq!(kept.srefs.len(), 1);
+        assert!(kept.boundarys.is_empty());
+
+        let options = GdsClipOptions { flatten_partial: true };
+        let flat = library.clip("top", &window, &options).unwrap();
+        assert!(flat.srefs.is_empty());
+        assert_eq!(flat.boundarys.len(), 2);
+        assert_eq!(flat.boundarys.iter().map(|b| b.area()).sum::<f64>(), 50.0);
+
+        // The instances of the array are decided one by one
+        let mid = library.clip("mid", &GdsRect::new(-5, -5, 15, 15), &options).unwrap();
+        assert_eq!(mid.boundarys.len(), 0);
+        assert_eq!(mid.srefs.len(), 1);
+        assert!(mid.arefs.is_empty());
+
+        let clipped = library.clip_library("top", &window, &GdsClipOptions::default()).unwrap();
+        let mut names: Vec<_> = clipped.structures.keys().cloned().collect();
+        names.sort();
+        assert_eq!(names, vec!["leaf", "mid", "top_clip"]);
+
+        let mut taken = library.clone();
+        taken.structures.insert("top_clip".to_string(), Arc::new(RwLock::new(GdsStructure::new("top_clip"))));
+        assert!(matches!(taken.clip_library("top", &window, &GdsClipOptions::default()), Err(GdsOpError::NameTaken(_))));
+    }
+}
@@ -0,0 +1,187 @@
+use std::collections::{BTreeSet, HashMap};
+use crate::{GdsElement, GdsLibrary, GdsStructure};
+use super::GdsOpResult;
+
+/// Elements found in only one of two same-name structures, whatever their order
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GdsStructureDiff {
+    pub name: String,
+    /// Elements of the first structure missing from the second
+    pub removed: Vec<GdsElement>,
+    /// Elements of the second structure missing from the first
+    pub added: Vec<GdsElement>,
+}
+
+impl GdsStructureDiff {
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.added.is_empty()
+    }
+}
+
+/// Structural differences between two libraries, timestamps are ignored
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GdsLibraryDiff {
+    /// Whether the database or user units differ, coordinates are compared as they are
+    pub units_differ: bool,
+    /// Structures of the first library only, sorted
+    pub removed_structures: Vec<String>,
+    /// Structures of the second library only, sorted
+    pub added_structures: Vec<String>,
+    /// Structures of both libraries with different elements, sorted by name
+    pub changed_structures: Vec<GdsStructureDiff>,
+}
+
+impl GdsLibraryDiff {
+    pub fn is_empty(&self) -> bool {
+        !self.units_differ
+            && self.removed_structures.is_empty()
+            && self.added_structures.is_empty()
+            && self.changed_structures.is_empty()
+    }
+}
+
+impl GdsStructure {
+    /// Elements of this structure and `other` that have no equal counterpart on the other side
+    pub fn diff(&self, other: &GdsStructure) -> GdsStructureDiff {
+        let removed = elements(self);
+        let mut unmatched: HashMap<&GdsElement, Vec<usize>> = HashMap::new();
+        for (i, element) in removed.iter().enumerate().rev() {
+            unmatched.entry(element).or_default().push(i);
+        }
+
+        let mut added = Vec::new();
+        for element in elements(other) {
+            match unmatched.get_mut(&element).and_then(|indices| indices.pop()) {
+                Some(_) => {}
+                None => added.push(element),
+            }
+        }
+
+        let mut left: Vec<usize> = unmatched.into_values().flatten().collect();
+        left.sort_unstable();
+        let mut removed: Vec<Option<GdsElement>> = removed.into_iter().map(Some).collect();
+        let removed = left.into_iter().filter_map(|i| removed[i].take()).collect();
+
+        GdsStructureDiff { name: self.name.clone(), removed, added }
+    }
+}
+
+impl GdsLibrary {
+    /// Structures added, removed and changed from this library to `other`
+    pub fn diff(&self, other: &GdsLibrary) -> GdsLibraryDiff {
+        let names: BTreeSet<&String> = self.structures.keys().collect();
+        let other_names: BTreeSet<&String> = other.structures.keys().collect();
+
+        let changed_structures = names.intersection(&other_names)
+            .map(|name| {
+                let structure = self.structures[*name].read().unwrap();
+                let other_structure = other.structures[*name].read().unwrap();
+                structure.diff(&other_structure)
+            })
+            .filter(|diff| !diff.is_empty())
+            .collect();
+
+        GdsLibraryDiff {
+            units_differ: self.usrunits_per_dbunit != other.usrunits_per_dbunit
+                || self.meters_per_dbunit != other.meters_per_dbunit,
+            removed_structures: names.difference(&other_names).map(|name| name.to_string()).collect(),
+            added_structures: other_names.difference(&names).map(|name| name.to_string()).collect(),
+            changed_structures,
+        }
+    }
+
+    /// Per layer XOR of the flattened shapes of `cell` and of `other_cell` in `other`.
+    ///
+    /// The result holds the differing areas as boundaries on their layer, it is empty when both
+    /// cells cover the same areas. `other` is brought to the database unit of this library.
+    pub fn geometric_diff(&self, cell: &str, other: &GdsLibrary, other_cell: &str) -> GdsOpResult<GdsStructure> {
+        let flat = self.flatten(cell, None)?;
+        let mut other_flat = other.flatten(other_cell, None)?;
+        if other.meters_per_dbunit != self.meters_per_dbunit {
+            other_flat = other_flat.scaled(other.meters_per_dbunit / self.meters_per_dbunit);
+        }
+
+        let mut keys = BTreeSet::new();
+        for structure in [&flat, &other_flat] {
+            keys.extend(structure.boundarys.iter().map(|e| e.layer_key()));
+            keys.extend(structure.boxes.iter().map(|e| e.layer_key()));
+            keys.extend(structure.paths.iter().map(|e| e.layer_key()));
+        }
+
+        let mut diff = GdsStructure::new(cell);
+        for key in keys {
+            let xor = flat.region(key).xor(&other_flat.region(key));
+            diff.insert_region(key, &xor);
+        }
+        Ok(diff)
+    }
+}
+
+fn elements(structure: &GdsStructure) -> Vec<GdsElement> {
+    let mut elements: Vec<GdsElement> = Vec::new();
+    elements.extend(structure.boundarys.iter().cloned().map(GdsElement::from));
+    elements.extend(structure.paths.iter().cloned().map(GdsElement::from));
+    elements.extend(structure.srefs.iter().cloned().map(GdsElement::from));
+    elements.extend(structure.arefs.iter().cloned().map(GdsElement::from));
+    elements.extend(structure.texts.iter().cloned().map(GdsElement::from));
+    elements.extend(structure.nodes.iter().cloned().map(GdsElement::from));
+    elements.extend(structure.boxes.iter().cloned().map(GdsElement::from));
+    elements
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{GdsLayerKey, GdsStructure};
+    use super::super::test_utils::{hierarchy, rect, sref, transform};
+
+    #[test]
+    fn test_structural_diff() {
+        let a = hierarchy();
+        assert!(a.diff(&hierarchy()).is_empty());
+
+        let b = hierarchy();
+        b.structures.get("leaf").unwrap().write().unwrap().boundarys.insert(0, rect(2, 0, 0, 5, 5));
+        b.structures.get("mid").unwrap().write().unwrap().boundarys.push(rect(1, 0, 0, 1, 1));
+        b.structures.get("mid").unwrap().write().unwrap().boundarys.push(rect(1, 0, 0, 2, 2));
+        let mut b = b;
+        b.structures.shift_remove("top");
+
+        let diff = a.diff(&b);
+        assert!(!diff.units_differ);
+        assert_eq!(diff.removed_structures, vec!["top"]);
+        assert!(diff.added_structures.is_empty());
+        assert_eq!(diff.changed_structures.len(), 2);
+        assert_eq!(diff.changed_structures[0].name, "leaf");
+        assert_eq!(diff.changed_structures[0].added.len(), 1);
+        assert_eq!(diff.changed_structures[1].added.len(), 2);
+
+        // Element order does not matter
+        let c = hierarchy();
+        c.structures.get("mid").unwrap().write().unwrap().boundarys.push(rect(1, 0, 0, 2, 2));
+        c.structures.get("mid").unwrap().write().unwrap().boundarys.push(rect(1, 0, 0, 1, 1));
+        assert!(b.structures["mid"].read().unwrap().diff(&c.structures["mid"].read().unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_transforms_compare_by_value() {
+        let mut a = GdsStructure::new("a");
+        a.srefs.push(sref("leaf", 0, 0, Some(transform(false, 2.0, 0.0))));
+        let mut b = GdsStructure::new("a");
+        b.srefs.push(sref("leaf", 0, 0, Some(transform(false, 2.0, -0.0))));
+        assert!(a.diff(&b).is_empty());
+
+        b.srefs[0].transform = Some(transform(false, 2.0, 90.0));
+        assert_eq!(a.diff(&b).added.len(), 1);
+    }
+
+    #[test]
+    fn test_geometric_diff() {
+        let a = hierarchy();
+        let b = hierarchy();
+        assert!(a.geometric_diff("top", &b, "top").unwrap().boundarys.is_empty());
+
+        b.structures.get("leaf").unwrap().write().unwrap().boundarys[0] = rect(1, 0, 0, 10, 12);
+        let diff = a.geometric_diff("top", &b, "top").unwrap();
+        assert_eq!(diff.region(GdsLayerKey::new(1, 0)).area(), 5.0 * 20.0);
+    }
+}
@@ -0,0 +1,22 @@
+#[derive(Debug, thiserror::Error)]
+pub enum GdsOpError {
+    #[error("Structure '{0}' not found")]
+    StructureNotFound(String),
+
+    #[error("Reference cycle through structure '{0}'")]
+    ReferenceCycle(String),
+
+    #[error("Structures named '{0}' have different content")]
+    StructureConflict(String),
+
+    #[error("Structure name '{0}' is already used")]
+    NameTaken(String),
+
+    #[error("Grid must be positive, got '{0}'")]
+    InvalidGrid(i32),
+
+    #[error("Database unit must be finite and positive, got '{0}'")]
+    InvalidUnit(f64),
+}
+
+pub type GdsOpResult<T> = Result<T, GdsOpError>;
@@ -0,0 +1,141 @@
+use crate::{GdsLibrary, GdsMatrix, GdsStructure};
+use super::{GdsOpError, GdsOpResult};
+
+impl GdsLibrary {
+    /// Flatten a structure: the result holds its elements and the elements of every
+    /// structure it references, transformed into its coordinates.
+    ///
+    /// `depth` is the number of SREF/AREF levels to expand, `None` expands all of them.
+    /// References past the limit are kept, placed in the coordinates of the result.
+    pub fn flatten(&self, cell_name: &str, depth: Option<usize>) -> GdsOpResult<GdsStructure> {
+        let structure = self.structures.get(cell_name)
+            .ok_or_else(|| GdsOpError::StructureNotFound(cell_name.to_string()))?;
+        let structure = structure.read().unwrap();
+
+        let mut flat = GdsStructure::new(&structure.name);
+        flat.create_date = structure.create_date.clone();
+        flat.modify_date = structure.modify_date.clone();
+
+        let mut stack = vec![cell_name.to_string()];
+        self.flatten_into(&structure, &GdsMatrix::IDENTITY, depth, &mut flat, &mut stack)?;
+        Ok(flat)
+    }
+
+    fn flatten_into(
+        &self, 
+        structure: &GdsStructure, 
+        matrix: &GdsMatrix, 
+        depth: Option<usize>, 
+        flat: &mut GdsStructure,
+        stack: &mut Vec<String>,
+    ) -> GdsOpResult<()> {
+        flat.boundarys.extend(structure.boundarys.iter().map(|e| e.transformed(matrix)));
+        flat.paths.extend(structure.paths.iter().map(|e| e.transformed(matrix)));
+        flat.texts.extend(structure.texts.iter().map(|e| e.transformed(matrix)));
+        flat.nodes.extend(structure.nodes.iter().map(|e| e.transformed(matrix)));
+        flat.boxes.extend(structure.boxes.iter().map(|e| e.transformed(matrix)));
+
+        if depth == Some(0) {
+            flat.srefs.extend(structure.srefs.iter().map(|e| e.transformed(matrix)));
+            flat.arefs.extend(structure.arefs.iter().map(|e| e.transformed(matrix)));
+            return Ok(());
+        }
+
+        let depth = depth.map(|d| d - 1);
+        for sref in &structure.srefs {
+            let origin = sref.xy.first().copied().unwrap_or_default();
+            let child_matrix = matrix.place(&origin, sref.transform.as_ref());
+            self.flatten_reference(&sref.s_name, &child_matrix, depth, flat, stack)?;
+        }
+
+        for aref in &structure.arefs {
+            for origin in aref.instance_origins() {
+                let child_matrix = matrix.place(&origin, aref.transform.as_ref());
+                self.flatten_reference(&aref.s_name, &child_matrix, depth, flat, stack)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn flatten_reference(
+        &self, 
+        name: &str, 
+        matrix: &GdsMatrix, 
+        depth: Option<usize>, 
+        flat: &mut GdsStructure,
+        stack: &mut Vec<String>,
+    ) -> GdsOpResult<()> {
+        if stack.iter().any(|n| n == name) {
+            return Err(GdsOpError::ReferenceCycle(name.to_string()));
+        }
+
+        let structure = self.structures.get(name)
+            .ok_or_else(|| GdsOpError::StructureNotFound(name.to_string()))?;
+        let structure = structure.read().unwrap();
+
+        stack.push(name.to_string());
+        self.flatten_into(&structure, matrix, depth, flat, stack)?;
+        stack.pop();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::GdsDbCoord;
+    use super::super::test_utils::*;
+    use super::*;
+
+    #[test]
+    fn test_flatten_all() {
+        let flat = hierarchy().flatten("top", None).unwrap();
+        assert_eq!(flat.boundarys.len(), 5);
+        assert!(flat.srefs.is_empty() && flat.arefs.is_empty());
+
+        let mut origins: Vec<_> = flat.boundarys.iter().map(|b| b.xy[0]).collect();
+        origins.sort_by_key(|c| (c.x, c.y));
+        assert_eq!(origins, vec![
+            GdsDbCoord::new(100, 0), 
+            GdsDbCoord::new(100, 20), 
+            GdsDbCoord::new(100, 100),
+            GdsDbCoord::new(120, 0), 
+            GdsDbCoord::new(120, 20), 
+        ]);
+
+        // Reflected then rotated: (10, 0) -> (10, 0) -> (0, 10)
+        let reflected = flat.boundarys.iter().find(|b| b.xy[0] == GdsDbCoord::new(100, 100)).unwrap();
+        assert_eq!(reflected.xy[1], GdsDbCoord::new(100, 110));
+    }
+
+    #[test]
+    fn test_flatten_depth() {
+        let lib = hierarchy();
+
+        let flat = lib.flatten("top", Some(0)).unwrap();
+        assert_eq!(flat.srefs.len(), 1);
+        assert!(flat.boundarys.is_empty());
+
+        let flat = lib.flatten("top", Some(1)).unwrap();
+        assert!(flat.boundarys.is_empty());
+        assert_eq!(flat.arefs[0].xy[0], GdsDbCoord::new(100, 0));
+        assert_eq!(flat.arefs[0].xy[1], GdsDbCoord::new(140, 0));
+        assert_eq!(flat.srefs[0].xy[0], GdsDbCoord::new(100, 100));
+        assert_eq!(flat.srefs[0].transform, Some(transform(true, 1.0, 90.0)));
+    }
+
+    #[test]
+    fn test_flatten_errors() {
+        let mut a = crate::GdsStructure::new("a");
+        a.srefs.push(sref("b", 0, 0, None));
+        let mut b = crate::GdsStructure::new("b");
+        b.srefs.push(sref("a", 0, 0, None));
+        let mut c = crate::GdsStructure::new("c");
+        c.srefs.push(sref("missing", 0, 0, None));
+        let lib = library(vec![a, b, c]);
+
+        assert!(matches!(lib.flatten("a", None), Err(GdsOpError::ReferenceCycle(name)) if name == "a"));
+        assert!(matches!(lib.flatten("c", None), Err(GdsOpError::StructureNotFound(name)) if name == "missing"));
+        assert!(matches!(lib.flatten("nope", None), Err(GdsOpError::StructureNotFound(_))));
+    }
+}
@@ -0,0 +1,283 @@
+use std::collections::{BTreeMap, BTreeSet};
+use crate::GdsLibrary;
+use super::{GdsOpError, GdsOpResult};
+
+/// An SREF or AREF whose `s_name` is not a structure of the library
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GdsDanglingReference {
+    pub parent: String,
+    pub s_name: String,
+    /// Instances placed by the dangling references, an AREF counts `col × row`
+    pub instances: usize,
+}
+
+/// Reference graph of the structures of a library.
+///
+/// Instance counts are the number of placed instances: 1 for an SREF and `col × row` for an AREF.
+#[derive(Debug, Clone, Default)]
+pub struct GdsHierarchy {
+    structures: BTreeSet<String>,
+    children: BTreeMap<String, BTreeMap<String, usize>>,
+    parents: BTreeMap<String, BTreeMap<String, usize>>,
+    dangling: Vec<GdsDanglingReference>,
+}
+
+impl GdsHierarchy {
+    pub fn new(library: &GdsLibrary) -> Self {
+        let mut hierarchy = Self::default();
+        hierarchy.structures.extend(library.structures.keys().cloned());
+
+        for (name, structure) in &library.structures {
+            let structure = structure.read().unwrap();
+            let references = structure.srefs.iter().map(|r| (&r.s_name, 1))
+                .chain(structure.arefs.iter().map(|r| (&r.s_name, r.col.max(0) as usize * r.row.max(0) as usize)));
+
+            let mut children: BTreeMap<String, usize> = BTreeMap::new();
+            let mut dangling: BTreeMap<String, usize> = BTreeMap::new();
+            for (s_name, instances) in references {
+                let map = if hierarchy.structures.contains(s_name) { &mut children } else { &mut dangling };
+                *map.entry(s_name.clone()).or_default() += instances;
+            }
+
+            for (child, instances) in &children {
+                hierarchy.parents.entry(child.clone()).or_default().insert(name.clone(), *instances);
+            }
+            hierarchy.children.insert(name.clone(), children);
+            hierarchy.dangling.extend(dangling.into_iter().map(|(s_name, instances)| GdsDanglingReference {
+                parent: name.clone(),
+                s_name,
+                instances,
+            }));
+        }
+
+        hierarchy.dangling.sort_by(|a, b| (&a.parent, &a.s_name).cmp(&(&b.parent, &b.s_name)));
+        hierarchy
+    }
+
+    /// Names of all structures, sorted
+    pub fn structures(&self) -> impl Iterator<Item = &str> {
+        self.structures.iter().map(|s| s.as_str())
+    }
+
+    /// Structures referenced by `name` with their instance counts
+    pub fn children(&self, name: &str) -> impl Iterator<Item = (&str, usize)> {
+        self.children.get(name).into_iter().flatten().map(|(n, c)| (n.as_str(), *c))
+    }
+
+    /// Structures referencing `name` with their instance counts
+    pub fn parents(&self, name: &str) -> impl Iterator<Item = (&str, usize)> {
+        self.parents.get(name).into_iter().flatten().map(|(n, c)| (n.as_str(), *c))
+    }
+
+    /// Structures no other structure references
+    pub fn top_cells(&self) -> Vec<&str> {
+        self.structures()
+            .filter(|name| self.parents.get(*name).is_none_or(|p| p.is_empty()))
+            .collect()
+    }
+
+    /// References to structures missing from the library
+    pub fn dangling_references(&self) -> &[GdsDanglingReference] {
+        &self.dangling
+    }
+
+    /// Every set of structures referencing each other in a loop, 
+    /// including a structure referencing itself
+    pub fn cycles(&self) -> Vec<Vec<String>> {
+        let mut tarjan = Tarjan::default();
+        for name in &self.structures {
+            if !tarjan.index.contains_key(name.as_str()) {
+                tarjan.visit(self, name);
+            }
+        }
+
+        tarjan.components
+            .into_iter()
+            .filter(|c| c.len() > 1 || self.children.get(&c[0]).is_some_and(|ch| ch.contains_key(&c[0])))
+            .collect()
+    }
+
+    /// Bottom-up order: every structure comes after all the structures it references
+    pub fn topological_order(&self) -> GdsOpResult<Vec<String>> {
+        let roots: Vec<&str> = self.structures.iter().map(String::as_str).collect();
+        self.topological_order_from(&roots)
+    }
+
+    /// Bottom-up order of the structures reachable from `roots`, roots included.
+    /// Only a cycle among them is an error.
+    pub fn topological_order_from<S: AsRef<str>>(&self, roots: &[S]) -> GdsOpResult<Vec<String>> {
+        #[derive(Clone, Copy, PartialEq)]
+        enum Mark { Visiting, Done }
+
+        fn visit<'a>(
+            hierarchy: &'a GdsHierarchy, 
+            name: &'a str, 
+            marks: &mut BTreeMap<&'a str, Mark>, 
+            order: &mut Vec<String>,
+        ) -> GdsOpResult<()> {
+            match marks.get(name) {
+                Some(Mark::Done) => return Ok(()),
+                Some(Mark::Visiting) => return Err(GdsOpError::ReferenceCycle(name.to_string())),
+                None => {}
+            }
+            marks.insert(name, Mark::Visiting);
+            for child in hierarchy.children.get(name).into_iter().flat_map(|c| c.keys()) {
+                visit(hierarchy, child, marks, order)?;
+            }
+            marks.insert(name, Mark::Done);
+            order.push(name.to_string());
+            Ok(())
+        }
+
+        let mut marks = BTreeMap::new();
+        let mut order = Vec::new();
+        for name in roots.iter().map(|r| r.as_ref()).filter(|r| self.structures.contains(*r)) {
+            visit(self, name, &mut marks, &mut order)?;
+        }
+        Ok(order)
+    }
+
+    /// Structures reachable from `roots`, roots included
+    pub fn reachable_from<S: AsRef<str>>(&self, roots: &[S]) -> BTreeSet<String> {
+        let mut reached = BTreeSet::new();
+        let mut pending: Vec<&str> = roots.iter()
+            .map(|r| r.as_ref())
+            .filter(|r| self.structures.contains(*r))
+            .collect();
+
+        while let Some(name) = pending.pop() {
+            if reached.insert(name.to_string()) {
+                pending.extend(self.children(name).map(|(child, _)| child));
+            }
+        }
+        reached
+    }
+
+    /// Structures that no top cell reaches: reference cycles and what only they reference
+    pub fn unreachable(&self) -> Vec<String> {
+        let reached = self.reachable_from(&self.top_cells());
+        self.structures.iter().filter(|s| !reached.contains(*s)).cloned().collect()
+    }
+}
+
+#[derive(Default)]
+struct Tarjan<'a> {
+    counter: usize,
+    index: BTreeMap<&'a str, usize>,
+    lowlink: BTreeMap<&'a str, usize>,
+    stack: Vec<&'a str>,
+    on_stack: BTreeSet<&'a str>,
+    components: Vec<Vec<String>>,
+}
+
+impl<'a> Tarjan<'a> {
+    fn visit(&mut self, hierarchy: &'a GdsHierarchy, name: &'a str) {
+        self.index.insert(name, self.counter);
+        self.lowlink.insert(name, self.counter);
+        self.counter += 1;
+        self.stack.push(name);
+        self.on_stack.insert(name);
+
+        for child in hierarchy.children.get(name).into_iter().flat_map(|c| c.keys()) {
+            let child = child.as_str();
+            if !self.index.contains_key(child) {
+                self.visit(hierarchy, child);
+                let low = self.lowlink[name].min(self.lowlink[child]);
+                self.lowlink.insert(name, low);
+            } else if self.on_stack.contains(child) {
+                let low = self.lowlink[name].min(self.index[child]);
+                self.lowlink.insert(name, low);
+            }
+        }
+
+        if self.lowlink[name] == self.index[name] {
+            let mut component = Vec::new();
+            while let Some(member) = self.stack.pop() {
+                self.on_stack.remove(member);
+                component.push(member.to_string());
+                if member == name {
+                    break;
+                }
+            }
+            component.sort();
+            self.components.push(component);
+        }
+    }
+}
+
+impl GdsLibrary {
+    pub fn hierarchy(&self) -> GdsHierarchy {
+        GdsHierarchy::new(self)
+    }
+
+    /// Remove the structures no top cell reaches, return their names
+    pub fn prune_unreachable(&mut self) -> Vec<String> {
+        let hierarchy = self.hierarchy();
+        let tops = hierarchy.top_cells();
+        self.prune_unreachable_from(&tops)
+    }
+
+    /// Remove the structures not reachable from `roots`, return their names
+    pub fn prune_unreachable_from<S: AsRef<str>>(&mut self, roots: &[S]) -> Vec<String> {
+        let reached = self.hierarchy().reachable_from(roots);
+        let removed: Vec<String> = self.structures.keys()
+            .filter(|name| !reached.contains(*name))
+            .cloned()
+            .collect();
+        for name in &removed {
+            self.structures.shift_remove(name);
+        }
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::GdsStructure;
+    use super::super::test_utils::*;
+    use super::*;
+
+    #[test]
+    fn test_parents_children() {
+        let h = hierarchy().hierarchy();
+        assert_eq!(h.top_cells(), ["top"]);
+        assert_eq!(h.children("mid").collect::<Vec<_>>(), [("leaf", 5)]);
+        assert_eq!(h.parents("leaf").collect::<Vec<_>>(), [("mid", 5)]);
+        assert_eq!(h.topological_order().unwrap(), ["leaf", "mid", "top"]);
+        assert!(h.cycles().is_empty());
+        assert!(h.dangling_references().is_empty());
+    }
+
+    #[test]
+    fn test_cycles_and_dangling() {
+        let mut a = GdsStructure::new("a");
+        a.srefs.push(sref("b", 0, 0, None));
+        let mut b = GdsStructure::new("b");
+        b.srefs.push(sref("a", 0, 0, None));
+        let mut c = GdsStructure::new("c");
+        c.srefs.push(sref("c", 0, 0, None));
+        c.arefs.push(aref("missing", 2, 3, (0, 0), (1, 1)));
+        let mut top = GdsStructure::new("top");
+        top.srefs.push(sref("leaf", 0, 0, None));
+        let leaf = GdsStructure::new("leaf");
+
+        let mut lib = library(vec![a, b, c, top, leaf]);
+        let h = lib.hierarchy();
+
+        assert_eq!(h.cycles(), vec![vec!["a".to_string(), "b".to_string()], vec!["c".to_string()]]);
+        assert!(matches!(h.topological_order(), Err(GdsOpError::ReferenceCycle(_))));
+        assert_eq!(h.topological_order_from(&["top"]).unwrap(), ["leaf", "top"]);
+        assert!(matches!(h.topological_order_from(&["b"]), Err(GdsOpError::ReferenceCycle(_))));
+        assert_eq!(h.dangling_references(), [GdsDanglingReference { 
+            parent: "c".to_string(), 
+            s_name: "missing".to_string(), 
+            instances: 6 
+        }]);
+        assert_eq!(h.unreachable(), ["a", "b", "c"]);
+
+        let mut removed = lib.prune_unreachable();
+        removed.sort();
+        assert_eq!(removed, ["a", "b", "c"]);
+        assert_eq!(lib.structures.len(), 2);
+    }
+}
@@ -0,0 +1,273 @@
+use std::collections::BTreeMap;
+use rstar::{AABB, PointDistance, RTree, RTreeObject};
+use crate::{GdsDbCoord, GdsElement, GdsLayerKey, GdsLibrary, GdsRect, GdsStructure};
+use crate::geometry::winding;
+use super::GdsOpResult;
+
+/// A layer element with the geometry used to query it
+#[derive(Debug, Clone)]
+pub struct GdsIndexedShape {
+    pub element: GdsElement,
+    /// Polygon of boundaries, boxes and paths with a width; points of texts, nodes and
+    /// paths without width
+    pub outline: Vec<GdsDbCoord>,
+    /// Whether the outline encloses an area or is a point or a line
+    pub closed: bool,
+    pub bbox: GdsRect,
+}
+
+impl GdsIndexedShape {
+    /// Shape of an element on a layer, `None` for references and empty elements
+    pub fn new(element: GdsElement) -> Option<Self> {
+        let (outline, closed) = match &element {
+            GdsElement::Boundary(e) => (e.xy.clone(), true),
+            GdsElement::Box(e) => (e.xy.clone(), true),
+            GdsElement::Path(e) => match e.to_polygon() {
+                Some(polygon) => (polygon.xy, true),
+                None => (e.xy.clone(), false),
+            },
+            GdsElement::Text(e) => (e.xy.first().copied().into_iter().collect(), false),
+            GdsElement::Node(e) => (e.xy.clone(), false),
+            GdsElement::Sref(_) | GdsElement::Aref(_) => return None,
+        };
+        let bbox = GdsRect::from_points(&outline)?;
+        Some(Self { element, outline, closed, bbox })
+    }
+
+    /// Whether the shape and the rectangle share at least one point
+    pub fn intersects(&self, rect: &GdsRect) -> bool {
+        if !self.bbox.intersects(rect) {
+            return false;
+        }
+        if rect.contains_rect(&self.bbox) || self.outline.iter().any(|p| rect.contains(p)) {
+            return true;
+        }
+        if self.edges().any(|(p, q)| segment_intersects_rect(p, q, rect)) {
+            return true;
+        }
+        self.closed && self.contains_point(&rect.min)
+    }
+
+    /// Whether the shape lies completely inside the rectangle
+    pub fn is_inside(&self, rect: &GdsRect) -> bool {
+        rect.contains_rect(&self.bbox)
+    }
+
+    /// Whether the point is inside the shape or on its outline
+    pub fn contains_point(&self, point: &GdsDbCoord) -> bool {
+        self.distance_2(&[point.x as f64, point.y as f64]) == 0.0
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (GdsDbCoord, GdsDbCoord)> + '_ {
+        let n = self.outline.len();
+        let count = match (self.closed, n) {
+            (_, 1) => 1,
+            (true, _) => n,
+            (false, _) => n - 1,
+        };
+        (0..count).map(move |i| (self.outline[i], self.outline[(i + 1) % n]))
+    }
+}
+
+impl RTreeObject for GdsIndexedShape {
+    type Envelope = AABB<[f64; 2]>;
+
+    fn envelope(&self) -> Self::Envelope {
+        AABB::from_corners(
+            [self.bbox.min.x as f64, self.bbox.min.y as f64],
+            [self.bbox.max.x as f64, self.bbox.max.y as f64],
+        )
+    }
+}
+
+impl PointDistance for GdsIndexedShape {
+    /// Squared distance to the outline, zero inside a closed shape
+    fn distance_2(&self, point: &[f64; 2]) -> f64 {
+        let (x, y) = (point[0], point[1]);
+        if self.closed && winding(&self.outline, x, y) != 0 {
+            return 0.0;
+        }
+        self.edges()
+            .map(|(p, q)| segment_distance_2(p, q, x, y))
+            .fold(f64::INFINITY, f64::min)
+    }
+}
+
+/// Layer shapes of a structure in one R-tree per (layer, datatype).
+///
+/// Boundaries, boxes, paths, texts and nodes are indexed, SREF and AREF are not: build the
+/// index from a flattened structure to query the shapes they place.
+#[derive(Debug, Clone, Default)]
+pub struct GdsSpatialIndex {
+    layers: BTreeMap<GdsLayerKey, RTree<GdsIndexedShape>>,
+}
+
+impl GdsSpatialIndex {
+    pub fn new(structure: &GdsStructure) -> Self {
+        let mut shapes: BTreeMap<GdsLayerKey, Vec<GdsIndexedShape>> = BTreeMap::new();
+        let mut insert = |key: GdsLayerKey, element: GdsElement| {
+            if let Some(shape) = GdsIndexedShape::new(element) {
+                shapes.entry(key).or_default().push(shape);
+            }
+        };
+        structure.boundarys.iter().for_each(|e| insert(e.layer_key(), e.clone().into()));
+        structure.boxes.iter().for_each(|e| insert(e.layer_key(), e.clone().into()));
+        structure.paths.iter().for_each(|e| insert(e.layer_key(), e.clone().into()));
+        structure.texts.iter().for_each(|e| insert(e.layer_key(), e.clone().into()));
+        structure.nodes.iter().for_each(|e| insert(e.layer_key(), e.clone().into()));
+
+        let layers = shapes.into_iter()
+            .map(|(key, shapes)| (key, RTree::bulk_load(shapes)))
+            .collect();
+        Self { layers }
+    }
+
+    /// Layers holding at least one shape, sorted
+    pub fn layers(&self) -> impl Iterator<Item = GdsLayerKey> + '_ {
+        self.layers.keys().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.layers.values().map(|tree| tree.size()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn shapes(&self, key: GdsLayerKey) -> impl Iterator<Item = &GdsIndexedShape> {
+        self.layers.get(&key).into_iter().flat_map(|tree| tree.iter())
+    }
+
+    /// Shapes of the layer sharing at least one point with the rectangle
+    pub fn intersecting(&self, key: GdsLayerKey, rect: &GdsRect) -> Vec<&GdsIndexedShape> {
+        self.candidates(key, rect)
+            .filter(|shape| shape.intersects(rect))
+            .collect()
+    }
+
+    /// Shapes of the layer lying completely inside the rectangle
+    pub fn contained(&self, key: GdsLayerKey, rect: &GdsRect) -> Vec<&GdsIndexedShape> {
+        self.candidates(key, rect)
+            .filter(|shape| shape.is_inside(rect))
+            .collect()
+    }
+
+    /// Shapes of the layer covering the point
+    pub fn at_point(&self, key: GdsLayerKey, point: &GdsDbCoord) -> Vec<&GdsIndexedShape> {
+        self.intersecting(key, &GdsRect::new(point.x, point.y, point.x, point.y))
+    }
+
+    /// Shape of the layer closest to the point, measured to its outline
+    pub fn nearest(&self, key: GdsLayerKey, point: &GdsDbCoord) -> Option<&GdsIndexedShape> {
+        self.layers.get(&key)?.nearest_neighbor(&[point.x as f64, point.y as f64])
+    }
+
+    fn candidates(&self, key: GdsLayerKey, rect: &GdsRect) -> impl Iterator<Item = &GdsIndexedShape> {
+        let envelope = AABB::from_corners(
+            [rect.min.x as f64, rect.min.y as f64],
+            [rect.max.x as f64, rect.max.y as f64],
+        );
+        self.layers.get(&key)
+            .into_iter()
+            .flat_map(move |tree| tree.locate_in_envelope_intersecting(&envelope))
+    }
+}
+
+impl GdsLibrary {
+    /// Spatial index of a structure, of its own elements or of the flattened hierarchy
+    pub fn spatial_index(&self, cell_name: &str, flatten: bool) -> GdsOpResult<GdsSpatialIndex> {
+        let flat = self.flatten(cell_name, if flatten { None } else { Some(0) })?;
+        Ok(GdsSpatialIndex::new(&flat))
+    }
+}
+
+
+fn segment_distance_2(p: GdsDbCoord, q: GdsDbCoord, x: f64, y: f64) -> f64 {
+    let (px, py) = (p.x as f64, p.y as f64);
+    let (dx, dy) = (q.x as f64 - px, q.y as f64 - py);
+    let length_2 = dx * dx + dy * dy;
+    let t = if length_2 == 0.0 { 0.0 } else { (((x - px) * dx + (y - py) * dy) / length_2).clamp(0.0, 1.0) };
+    let (ex, ey) = (px + t * dx - x, py + t * dy - y);
+    ex * ex + ey * ey
+}
+
+fn orientation(a: GdsDbCoord, b: GdsDbCoord, c: GdsDbCoord) -> i64 {
+    ((b.x as i64 - a.x as i64) * (c.y as i64 - a.y as i64) - (b.y as i64 - a.y as i64) * (c.x as i64 - a.x as i64)).signum()
+}
+
+fn segments_intersect(p: GdsDbCoord, q: GdsDbCoord, r: GdsDbCoord, s: GdsDbCoord) -> bool {
+    let (o1, o2) = (orientation(p, q, r), orientation(p, q, s));
+    let (o3, o4) = (orientation(r, s, p), orientation(r, s, q));
+    let on = |a: GdsDbCoord, b: GdsDbCoord, c: GdsDbCoord| {
+        c.x >= a.x.min(b.x) && c.x <= a.x.max(b.x) && c.y >= a.y.min(b.y) && c.y <= a.y.max(b.y)
+    };
+    (o1 != o2 && o3 != o4)
+        || (o1 == 0 && on(p, q, r))
+        || (o2 == 0 && on(p, q, s))
+        || (o3 == 0 && on(r, s, p))
+        || (o4 == 0 && on(r, s, q))
+}
+
+fn segment_intersects_rect(p: GdsDbCoord, q: GdsDbCoord, rect: &GdsRect) -> bool {
+    if rect.contains(&p) || rect.contains(&q) {
+        return true;
+    }
+    let corners = rect.corners();
+    (0..4).any(|i| segments_intersect(p, q, corners[i], corners[(i + 1) % 4]))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{GdsPathBuilder, GdsTextBuilder};
+    use super::*;
+    use super::super::test_utils::{hierarchy, rect};
+
+    #[test]
+    fn test_region_queries() {
+        let mut structure = GdsStructure::new("top");
+        structure.boundarys.push(rect(1, 0, 0, 100, 100));
+        structure.boundarys.push(rect(1, 200, 200, 300, 300));
+        structure.boundarys.push(rect(2, 0, 0, 10, 10));
+        // A diagonal path whose bounding box covers the query window but not its outline
+        structure.paths.push(GdsPathBuilder::default()
+            .layer(1).data_type(0).width(10)
+            .xy(vec![GdsDbCoord::new(400, 0), GdsDbCoord::new(600, 200)])
+            .build().unwrap());
+        structure.texts.push(GdsTextBuilder::default()
+            .layer(5).text_type(0).string("vdd".to_string())
+            .xy(vec![GdsDbCoord::new(250, 250)])
+            .build().unwrap());
+
+        let index = GdsSpatialIndex::new(&structure);
+        let metal = GdsLayerKey::new(1, 0);
+        assert_eq!(index.len(), 5);
+        assert_eq!(index.layers().collect::<Vec<_>>(), vec![metal, GdsLayerKey::new(2, 0), GdsLayerKey::new(5, 0)]);
+
+        assert_eq!(index.intersecting(metal, &GdsRect::new(50, 50, 250, 250)).len(), 2);
+        assert_eq!(index.intersecting(metal, &GdsRect::new(100, 100, 200, 200)).len(), 2);
+        assert!(index.intersecting(metal, &GdsRect::new(540, 10, 590, 60)).is_empty());
+        assert_eq!(index.contained(metal, &GdsRect::new(-10, -10, 250, 250)).len(), 1);
+
+        let label = index.shapes(GdsLayerKey::new(5, 0)).next().unwrap();
+        let under = index.at_point(metal, &label.outline[0]);
+        assert_eq!(under.len(), 1);
+        assert_eq!(under[0].bbox, GdsRect::new(200, 200, 300, 300));
+
+        let nearest = index.nearest(metal, &GdsDbCoord::new(150, 120)).unwrap();
+        assert_eq!(nearest.bbox, GdsRect::new(0, 0, 100, 100));
+        assert_eq!(index.nearest(metal, &GdsDbCoord::new(50, 50)).unwrap().distance_2(&[50.0, 50.0]), 0.0);
+        assert!(index.nearest(GdsLayerKey::new(9, 0), &GdsDbCoord::new(0, 0)).is_none());
+    }
+
+    #[test]
+    fn test_flattened_index() {
+        let library = hierarchy();
+        let leaf = GdsLayerKey::new(1, 0);
+        assert!(library.spatial_index("top", false).unwrap().is_empty());
+
+        let index = library.spatial_index("top", true).unwrap();
+        assert_eq!(index.shapes(leaf).count(), 5);
+        assert_eq!(index.contained(leaf, &GdsRect::new(100, 0, 130, 30)).len(), 4);
+    }
+}
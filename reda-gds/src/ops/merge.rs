@@ -0,0 +1,257 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use crate::{GdsLibrary, GdsStructure};
+use super::rename::rename_references;
+use super::{GdsOpError, GdsOpResult};
+
+/// What to do with a structure of the merged library whose name is already used by a
+/// structure with a different content
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GdsMergePolicy {
+    /// Keep the existing structure, references in the merged library now point to it
+    KeepFirst,
+    /// Replace the existing structure by the merged one
+    Replace,
+    /// Add the merged structure under `prefix + name + suffix` and update its references
+    Rename { prefix: String, suffix: String },
+    /// Fail with `GdsOpError::StructureConflict`
+    ErrorIfDifferent,
+}
+
+/// What `GdsLibrary::merge` did with the structures of the merged library
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GdsMergeReport {
+    /// Structures added under their own name
+    pub added: Vec<String>,
+    /// Structures identical to the existing one of the same name
+    pub duplicates: Vec<String>,
+    /// Conflicting structures left out for the existing one
+    pub kept: Vec<String>,
+    /// Conflicting structures that replaced the existing one
+    pub replaced: Vec<String>,
+    /// Conflicting structures added under a new name, as (name, new name)
+    pub renamed: Vec<(String, String)>,
+}
+
+impl GdsLibrary {
+    /// Add the structures of `other` to this library.
+    ///
+    /// Both libraries are brought to the finer of their database units first, keeping the
+    /// user unit of this library. Same-name structures with the same content, down to the
+    /// structures they reference, are merged, the others are resolved by `policy`. Nothing is changed when an error is returned.
+    /// Added structures follow the existing ones, in the order of `other`.
+    ///
+    /// Structures are never modified in place, merging into a clone leaves the original intact.
+    pub fn merge(&mut self, other: &GdsLibrary, policy: &GdsMergePolicy) -> GdsOpResult<GdsMergeReport> {
+        let meters_per_dbunit = self.meters_per_dbunit.min(other.meters_per_dbunit);
+        let own_factor = unit_factor(self.meters_per_dbunit, meters_per_dbunit);
+        let other_factor = unit_factor(other.meters_per_dbunit, meters_per_dbunit);
+
+        let incoming_structures: Vec<GdsStructure> = other.structures.values()
+            .map(|structure| {
+                let structure = structure.read().unwrap();
+                match other_factor {
+                    Some(factor) => structure.scaled(factor),
+                    None => structure.clone(),
+                }
+            })
+            .collect();
+        let conflicts = self.merge_conflicts(&incoming_structures, own_factor);
+
+        let mut report = GdsMergeReport::default();
+        let mut renames: HashMap<String, String> = HashMap::new();
+        let mut incoming: Vec<GdsStructure> = Vec::new();
+        for structure in incoming_structures {
+            let name = structure.name.clone();
+            if !self.structures.contains_key(&name) {
+                report.added.push(name);
+                incoming.push(structure);
+                continue;
+            }
+            if !conflicts.contains(&name) {
+                report.duplicates.push(name);
+                continue;
+            }
+
+            match policy {
+                GdsMergePolicy::KeepFirst => report.kept.push(name),
+                GdsMergePolicy::Replace => {
+                    report.replaced.push(name);
+                    incoming.push(structure);
+                }
+                GdsMergePolicy::Rename { prefix, suffix } => {
+                    let new_name = format!("{}{}{}", prefix, name, suffix);
+                    if self.structures.contains_key(&new_name) || other.structures.contains_key(&new_name) {
+                        return Err(GdsOpError::NameTaken(new_name));
+                    }
+                    report.renamed.push((name.clone(), new_name.clone()));
+                    renames.insert(name, new_name);
+                    incoming.push(structure);
+                }
+                GdsMergePolicy::ErrorIfDifferent => {
+                    return Err(GdsOpError::StructureConflict(name));
+                }
+            }
+        }
+
+        if let Some(factor) = own_factor {
+            self.usrunits_per_dbunit *= meters_per_dbunit / self.meters_per_dbunit;
+            self.meters_per_dbunit = meters_per_dbunit;
+            for structure in self.structures.values_mut() {
+                let scaled = structure.read().unwrap().scaled(factor);
+                *structure = Arc::new(RwLock::new(scaled));
+            }
+        }
+
+        for mut structure in incoming {
+            if let Some(new_name) = renames.get(&structure.name) {
+                structure.name = new_name.clone();
+            }
+            rename_references(&mut structure, &renames);
+            self.structures.insert(structure.name.clone(), Arc::new(RwLock::new(structure)));
+        }
+
+        Ok(report)
+    }
+}
+
+impl GdsLibrary {
+    /// Names of the `incoming` structures that differ from the existing structure of the
+    /// same name, either by their own content or through a structure they reference
+    fn merge_conflicts(&self, incoming: &[GdsStructure], own_factor: Option<f64>) -> HashSet<String> {
+        let mut conflicts: HashSet<String> = HashSet::new();
+        let mut shared: Vec<&GdsStructure> = Vec::new();
+        for structure in incoming {
+            let Some(existing) = self.structures.get(&structure.name) else { continue };
+            let existing = existing.read().unwrap();
+            let same = match own_factor {
+                Some(factor) => existing.scaled(factor).same_content(structure),
+                None => existing.same_content(structure),
+            };
+            match same {
+                true => shared.push(structure),
+                false => { conflicts.insert(structure.name.clone()); }
+            }
+        }
+
+        // Same content but referencing a conflicting structure is a conflict too, until no
+        // structure changes side
+        loop {
+            let (conflicting, same): (Vec<&GdsStructure>, Vec<&GdsStructure>) = shared.into_iter()
+                .partition(|structure| structure.srefs.iter().map(|r| &r.s_name)
+                    .chain(structure.arefs.iter().map(|r| &r.s_name))
+                    .any(|child| conflicts.contains(child)));
+            shared = same;
+            if conflicting.is_empty() {
+                return conflicts;
+            }
+            conflicts.extend(conflicting.into_iter().map(|structure| structure.name.clone()));
+        }
+    }
+}
+
+/// Scale factor from one database unit to another, `None` when they are the same
+fn unit_factor(from: f64, to: f64) -> Option<f64> {
+    match (from - to).abs() <= to * 1e-9 {
+        true => None,
+        false => Some(from / to),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::GdsRect;
+    use super::*;
+    use super::super::test_utils::{library, rect, sref};
+
+    fn cell(name: &str, size: i32) -> GdsStructure {
+        let mut structure = GdsStructure::new(name);
+        structure.boundarys.push(rect(1, 0, 0, size, size));
+        structure
+    }
+
+    fn bbox(library: &GdsLibrary, name: &str) -> GdsRect {
+        library.structures[name].read().unwrap().boundarys[0].bbox().unwrap()
+    }
+
+    #[test]
+    fn test_merge_units() {
+        let mut a = library(vec![cell("a", 10)]);
+        let mut b = library(vec![cell("b", 10)]);
+        b.meters_per_dbunit = 0.5e-9;
+        b.usrunits_per_dbunit = 0.5e-3;
+
+        let report = a.merge(&b, &GdsMergePolicy::ErrorIfDifferent).unwrap();
+        assert_eq!(report.added, vec!["b"]);
+        assert_eq!(a.meters_per_dbunit, 0.5e-9);
+        assert!((a.usrunits_per_dbunit - 0.5e-3).abs() < 1e-15);
+        assert_eq!(bbox(&a, "a"), GdsRect::new(0, 0, 20, 20));
+        assert_eq!(bbox(&a, "b"), GdsRect::new(0, 0, 10, 10));
+    }
+
+    #[test]
+    fn test_merge_keeps_source_order() {
+        let mut a = library(vec![cell("z", 10), cell("m", 10)]);
+        let b = library(vec![cell("y", 10), cell("m", 20), cell("b", 10)]);
+        let report = a.merge(&b, &GdsMergePolicy::Replace).unwrap();
+        assert_eq!(report.added, vec!["y", "b"]);
+        assert_eq!(a.structures.keys().collect::<Vec<_>>(), ["z", "m", "y", "b"]);
+    }
+
+    #[test]
+    fn test_merge_conflicting_child() {
+        let mut top = GdsStructure::new("top");
+        top.srefs.push(sref("leaf", 0, 0, None));
+        let a = library(vec![cell("leaf", 10), top.clone()]);
+        let b = library(vec![cell("leaf", 20), top]);
+
+        // `top` of b is the same as the one of a, but not once its leaf is resolved
+        let mut merged = a.clone();
+        let policy = GdsMergePolicy::Rename { prefix: "b_".to_string(), suffix: String::new() };
+        let report = merged.merge(&b, &policy).unwrap();
+        assert!(report.duplicates.is_empty());
+        assert_eq!(report.renamed, vec![
+            ("leaf".to_string(), "b_leaf".to_string()),
+            ("top".to_string(), "b_top".to_string()),
+        ]);
+        assert_eq!(merged.structures["b_top"].read().unwrap().srefs[0].s_name, "b_leaf");
+        assert_eq!(merged.structures["top"].read().unwrap().srefs[0].s_name, "leaf");
+
+        let mut merged = a.clone();
+        let error = merged.merge(&b, &GdsMergePolicy::ErrorIfDifferent).unwrap_err();
+        assert!(matches!(error, GdsOpError::StructureConflict(name) if name == "leaf"));
+    }
+
+    #[test]
+    fn test_merge_conflicts() {
+        let mut top = GdsStructure::new("top_b");
+        top.srefs.push(sref("cell", 0, 0, None));
+        let a = library(vec![cell("leaf", 10), cell("cell", 10)]);
+        let b = library(vec![cell("leaf", 10), cell("cell", 20), top]);
+
+        let mut merged = a.clone();
+        let error = merged.merge(&b, &GdsMergePolicy::ErrorIfDifferent).unwrap_err();
+        assert!(matches!(error, GdsOpError::StructureConflict(name) if name == "cell"));
+        assert_eq!(merged.structures.len(), 2);
+
+        let mut merged = a.clone();
+        let report = merged.merge(&b, &GdsMergePolicy::KeepFirst).unwrap();
+        assert_eq!(report.duplicates, vec!["leaf"]);
+        assert_eq!(report.kept, vec!["cell"]);
+        assert_eq!(bbox(&merged, "cell"), GdsRect::new(0, 0, 10, 10));
+
+        let mut merged = a.clone();
+        merged.merge(&b, &GdsMergePolicy::Replace).unwrap();
+        assert_eq!(bbox(&merged, "cell"), GdsRect::new(0, 0, 20, 20));
+        assert_eq!(bbox(&a, "cell"), GdsRect::new(0, 0, 10, 10));
+
+        let mut merged = a.clone();
+        let policy = GdsMergePolicy::Rename { prefix: "b_".to_string(), suffix: String::new() };
+        let report = merged.merge(&b, &policy).unwrap();
+        assert_eq!(report.renamed, vec![("cell".to_string(), "b_cell".to_string())]);
+        assert_eq!(merged.structures.len(), 4);
+        assert_eq!(bbox(&merged, "b_cell"), GdsRect::new(0, 0, 20, 20));
+        assert_eq!(merged.structures["b_cell"].read().unwrap().name, "b_cell");
+        assert_eq!(merged.structures["top_b"].read().unwrap().srefs[0].s_name, "b_cell");
+    }
+}
@@ -0,0 +1,28 @@
+mod error;
+mod flatten;
+mod bbox;
+mod hierarchy;
+mod stats;
+mod index;
+mod clip;
+mod units;
+mod merge;
+mod rename;
+mod diff;
+mod validate;
+mod remap;
+
+pub use error::*;
+pub use bbox::*;
+pub use hierarchy::*;
+pub use stats::*;
+pub use index::*;
+pub use clip::*;
+pub use merge::*;
+pub use units::*;
+pub use diff::*;
+pub use validate::*;
+pub use remap::*;
+
+#[cfg(test)]
+mod test_utils;
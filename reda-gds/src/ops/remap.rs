@@ -0,0 +1,203 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+use crate::{GdsElementKind, GdsLayerKey, GdsLibrary, GdsStructure};
+
+/// Where `GdsLibrary::remap_layers` puts the shapes of each (layer, datatype).
+///
+/// Every source pair maps to a list of target pairs: shapes are written once on each
+/// target, and an empty list deletes them. Pairs without an entry are left untouched.
+/// Texts, nodes and boxes are matched on their texttype, nodetype and boxtype.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GdsLayerRemap {
+    targets: BTreeMap<GdsLayerKey, Vec<GdsLayerKey>>,
+}
+
+impl GdsLayerRemap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Put the shapes of `from` on `to` instead
+    pub fn move_layer(&mut self, from: GdsLayerKey, to: GdsLayerKey) -> &mut Self {
+        let targets = self.targets.entry(from).or_default();
+        targets.retain(|key| *key != from);
+        Self::add(targets, to);
+        self
+    }
+
+    /// Keep the shapes of `from` and add a copy of them on `to`
+    pub fn copy_layer(&mut self, from: GdsLayerKey, to: GdsLayerKey) -> &mut Self {
+        let targets = self.targets.entry(from).or_insert_with(|| vec![from]);
+        Self::add(targets, to);
+        self
+    }
+
+    /// Remove the shapes of `from`, along with the moves and copies set for it
+    pub fn delete_layer(&mut self, from: GdsLayerKey) -> &mut Self {
+        self.targets.insert(from, Vec::new());
+        self
+    }
+
+    /// Pairs the shapes of `key` end up on, `None` when they are left untouched
+    pub fn targets(&self, key: GdsLayerKey) -> Option<&[GdsLayerKey]> {
+        self.targets.get(&key).map(Vec::as_slice)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    fn add(targets: &mut Vec<GdsLayerKey>, key: GdsLayerKey) {
+        if !targets.contains(&key) {
+            targets.push(key);
+        }
+    }
+
+    /// Replace `elements` by their remapped copies, returning how many each element became,
+    /// `None` for the untouched ones
+    fn apply<E: Clone>(
+        &self,
+        elements: &mut Vec<E>,
+        key: fn(&E) -> GdsLayerKey,
+        set: fn(&mut E, GdsLayerKey),
+    ) -> Vec<Option<usize>> {
+        let mut counts = Vec::with_capacity(elements.len());
+        let mut remapped = Vec::with_capacity(elements.len());
+        for element in std::mem::take(elements) {
+            match self.targets(key(&element)) {
+                None => {
+                    counts.push(None);
+                    remapped.push(element);
+                }
+                Some(targets) => {
+                    counts.push(Some(targets.len()));
+                    for target in targets {
+                        let mut copy = element.clone();
+                        set(&mut copy, *target);
+                        remapped.push(copy);
+                    }
+                }
+            }
+        }
+        *elements = remapped;
+        counts
+    }
+}
+
+impl GdsStructure {
+    /// Move, copy or delete the shapes of the structure as `remap` says, keeping copies next
+    /// to their original in the element order. Returns the number of shapes matched.
+    pub fn remap_layers(&mut self, remap: &GdsLayerRemap) -> usize {
+        let counts = HashMap::from([
+            (GdsElementKind::Boundary, remap.apply(&mut self.boundarys, |e| e.layer_key(), |e, k| (e.layer, e.data_type) = (k.layer, k.data_type))),
+            (GdsElementKind::Path, remap.apply(&mut self.paths, |e| e.layer_key(), |e, k| (e.layer, e.data_type) = (k.layer, k.data_type))),
+            (GdsElementKind::Text, remap.apply(&mut self.texts, |e| e.layer_key(), |e, k| (e.layer, e.text_type) = (k.layer, k.data_type))),
+            (GdsElementKind::Node, remap.apply(&mut self.nodes, |e| e.layer_key(), |e, k| (e.layer, e.node_type) = (k.layer, k.data_type))),
+            (GdsElementKind::Box, remap.apply(&mut self.boxes, |e| e.layer_key(), |e, k| (e.layer, e.box_type) = (k.layer, k.data_type))),
+        ]);
+
+        let mut next: HashMap<GdsElementKind, usize> = HashMap::new();
+        let mut order = Vec::with_capacity(self.element_order.len());
+        for kind in &self.element_order {
+            let index = next.entry(*kind).or_default();
+            let count = counts.get(kind).and_then(|c| c.get(*index).copied().flatten()).unwrap_or(1);
+            *index += 1;
+            order.extend(std::iter::repeat_n(*kind, count));
+        }
+        self.element_order = order;
+
+        counts.values()
+            .flat_map(|c| c.iter())
+            .filter(|count| count.is_some())
+            .count()
+    }
+}
+
+impl GdsLibrary {
+    /// Move, copy or delete shapes by (layer, datatype) in every structure, for boundaries,
+    /// paths, texts, nodes and boxes. Returns the number of shapes matched.
+    pub fn remap_layers(&mut self, remap: &GdsLayerRemap) -> usize {
+        let mut matched = 0;
+        for structure in self.structures.values_mut() {
+            // Structures may be shared with clones of the library, the changed ones are replaced by copies
+            let mut copy = structure.read().unwrap().clone();
+            let count = copy.remap_layers(remap);
+            if count > 0 {
+                *structure = Arc::new(RwLock::new(copy));
+            }
+            matched += count;
+        }
+        matched
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{GdsDbCoord, GdsTextBuilder};
+    use super::*;
+    use super::super::test_utils::{library, rect, sref};
+
+    fn key(layer: i16, data_type: i16) -> GdsLayerKey {
+        GdsLayerKey::new(layer, data_type)
+    }
+
+    #[test]
+    fn test_remap_layers() {
+        let mut top = GdsStructure::new("top");
+        top.push_element(rect(1, 0, 0, 10, 10));
+        top.push_element(sref("child", 0, 0, None));
+        top.push_element(rect(2, 0, 0, 5, 5));
+        top.push_element(GdsTextBuilder::default()
+            .layer(1).text_type(0).string("a".to_string())
+            .xy(vec![GdsDbCoord::new(0, 0)])
+            .build().unwrap());
+        top.push_element(rect(3, 0, 0, 1, 1));
+        let mut child = GdsStructure::new("child");
+        child.push_element(rect(63, 0, 0, 1, 1));
+        let mut lib = library(vec![top, child]);
+
+        let mut remap = GdsLayerRemap::new();
+        remap.copy_layer(key(1, 0), key(10, 0))
+            .move_layer(key(2, 0), key(20, 5))
+            .delete_layer(key(63, 0));
+        assert_eq!(lib.remap_layers(&remap), 4);
+
+        let top = lib.structures["top"].read().unwrap();
+        let layers: Vec<GdsLayerKey> = top.boundarys.iter().map(|e| e.layer_key()).collect();
+        assert_eq!(layers, [key(1, 0), key(10, 0), key(20, 5), key(3, 0)]);
+        let texts: Vec<GdsLayerKey> = top.texts.iter().map(|e| e.layer_key()).collect();
+        assert_eq!(texts, [key(1, 0), key(10, 0)]);
+        use GdsElementKind::*;
+        assert_eq!(top.element_order, [Boundary, Boundary, Sref, Boundary, Text, Text, Boundary]);
+
+        let child = lib.structures["child"].read().unwrap();
+        assert!(child.boundarys.is_empty());
+        assert!(child.element_order.is_empty());
+    }
+
+    #[test]
+    fn test_remap_leaves_clones_intact() {
+        let mut top = GdsStructure::new("top");
+        top.push_element(rect(1, 0, 0, 10, 10));
+        let mut other = GdsStructure::new("other");
+        other.push_element(rect(2, 0, 0, 10, 10));
+        let original = library(vec![top, other]);
+        let mut remapped = original.clone();
+        remapped.remap_layers(GdsLayerRemap::new().move_layer(key(1, 0), key(5, 0)));
+
+        assert_eq!(original.structures["top"].read().unwrap().boundarys[0].layer, 1);
+        assert_eq!(remapped.structures["top"].read().unwrap().boundarys[0].layer, 5);
+        // Structures without matched shapes are still shared
+        assert!(Arc::ptr_eq(&original.structures["other"], &remapped.structures["other"]));
+    }
+
+    #[test]
+    fn test_combined_rules() {
+        let mut remap = GdsLayerRemap::new();
+        remap.copy_layer(key(1, 0), key(2, 0)).move_layer(key(1, 0), key(3, 0));
+        assert_eq!(remap.targets(key(1, 0)), Some([key(2, 0), key(3, 0)].as_slice()));
+        remap.delete_layer(key(1, 0));
+        assert_eq!(remap.targets(key(1, 0)), Some([].as_slice()));
+        assert_eq!(remap.targets(key(2, 0)), None);
+    }
+}
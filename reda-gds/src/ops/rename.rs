@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use crate::{GdsLibrary, GdsStructure};
+use super::{GdsOpError, GdsOpResult};
+
+impl GdsLibrary {
+    /// Rename a structure, updating every SREF and AREF that references it
+    pub fn rename_structure(&mut self, old_name: &str, new_name: &str) -> GdsOpResult<()> {
+        if !self.structures.contains_key(old_name) {
+            return Err(GdsOpError::StructureNotFound(old_name.to_string()));
+        }
+        if old_name == new_name {
+            return Ok(());
+        }
+        if self.structures.contains_key(new_name) {
+            return Err(GdsOpError::NameTaken(new_name.to_string()));
+        }
+        self.rename_structures(HashMap::from([(old_name.to_string(), new_name.to_string())]));
+        Ok(())
+    }
+
+    /// Prepend `prefix` to the name of every structure accepted by `filter`, updating the
+    /// references to them. Returns the (old, new) names, sorted.
+    pub fn prefix_all<F: Fn(&str) -> bool>(&mut self, prefix: &str, filter: F) -> GdsOpResult<Vec<(String, String)>> {
+        let renames: HashMap<String, String> = self.structures.keys()
+            .filter(|name| filter(name))
+            .map(|name| (name.clone(), format!("{}{}", prefix, name)))
+            .collect();
+
+        // Fail before touching anything when a new name is used by a structure that stays
+        for new_name in renames.values() {
+            if self.structures.contains_key(new_name) && !renames.contains_key(new_name) {
+                return Err(GdsOpError::NameTaken(new_name.clone()));
+            }
+        }
+
+        let mut renamed: Vec<(String, String)> = renames.iter()
+            .map(|(old, new)| (old.clone(), new.clone()))
+            .collect();
+        renamed.sort();
+        self.rename_structures(renames);
+        Ok(renamed)
+    }
+
+    fn rename_structures(&mut self, renames: HashMap<String, String>) {
+        if renames.is_empty() {
+            return;
+        }
+        // Structures may be shared with clones of the library, changed ones are copied
+        let structures = std::mem::take(&mut self.structures);
+        for (name, structure) in structures {
+            let new_name = renames.get(&name);
+            let changed = {
+                let structure = structure.read().unwrap();
+                new_name.is_some() || references_any(&structure, &renames)
+            };
+            if !changed {
+                self.structures.insert(name, structure);
+                continue;
+            }
+
+            let mut copy = structure.read().unwrap().clone();
+            let name = new_name.cloned().unwrap_or(name);
+            copy.name = name.clone();
+            rename_references(&mut copy, &renames);
+            self.structures.insert(name, Arc::new(RwLock::new(copy)));
+        }
+    }
+}
+
+fn references_any(structure: &GdsStructure, renames: &HashMap<String, String>) -> bool {
+    structure.srefs.iter().any(|sref| renames.contains_key(&sref.s_name))
+        || structure.arefs.iter().any(|aref| renames.contains_key(&aref.s_name))
+}
+
+/// Point the SREF and AREF of a structure to the new names of renamed structures
+pub(super) fn rename_references(structure: &mut GdsStructure, renames: &HashMap<String, String>) {
+    for sref in structure.srefs.iter_mut() {
+        if let Some(new_name) = renames.get(&sref.s_name) {
+            sref.s_name = new_name.clone();
+        }
+    }
+    for aref in structure.arefs.iter_mut() {
+        if let Some(new_name) = renames.get(&aref.s_name) {
+            aref.s_name = new_name.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::test_utils::hierarchy;
+
+    #[test]
+    fn test_rename_structure() {
+        let mut library = hierarchy();
+        library.rename_structure("leaf", "bitcell").unwrap();
+        assert!(!library.structures.contains_key("leaf"));
+        assert_eq!(library.structures["bitcell"].read().unwrap().name, "bitcell");
+
+        let mid = library.structures["mid"].read().unwrap();
+        assert_eq!(mid.arefs[0].s_name, "bitcell");
+        assert_eq!(mid.srefs[0].s_name, "bitcell");
+        drop(mid);
+        assert!(library.hierarchy().dangling_references().is_empty());
+
+        assert!(matches!(library.rename_structure("leaf", "x"), Err(GdsOpError::StructureNotFound(_))));
+        assert!(matches!(library.rename_structure("mid", "top"), Err(GdsOpError::NameTaken(_))));
+    }
+
+    #[test]
+    fn test_rename_leaves_clones_intact() {
+        let original = hierarchy();
+        let mut renamed = original.clone();
+        renamed.rename_structure("leaf", "bitcell").unwrap();
+
+        assert_eq!(original.structures["leaf"].read().unwrap().name, "leaf");
+        assert_eq!(original.structures["mid"].read().unwrap().srefs[0].s_name, "leaf");
+        assert_eq!(renamed.structures["mid"].read().unwrap().srefs[0].s_name, "bitcell");
+        // Structures the rename does not touch are still shared
+        assert!(Arc::ptr_eq(&original.structures["top"], &renamed.structures["top"]));
+    }
+
+    #[test]
+    fn test_prefix_all() {
+        let mut library = hierarchy();
+        let renamed = library.prefix_all("ip_", |name| name != "top").unwrap();
+        assert_eq!(renamed, vec![
+            ("leaf".to_string(), "ip_leaf".to_string()),
+            ("mid".to_string(), "ip_mid".to_string()),
+        ]);
+        assert_eq!(library.structures["top"].read().unwrap().srefs[0].s_name, "ip_mid");
+        assert_eq!(library.hierarchy().top_cells(), vec!["top"]);
+        assert!(library.hierarchy().dangling_references().is_empty());
+
+        // "ip_" + "mid" exists and is not renamed
+        let mut library = hierarchy();
+        library.rename_structure("top", "ip_mid").unwrap();
+        assert!(library.prefix_all("ip_", |name| name == "mid").is_err());
+        assert!(library.structures.contains_key("mid"));
+    }
+}
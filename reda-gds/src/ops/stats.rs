@@ -0,0 +1,234 @@
+use std::collections::{BTreeMap, HashMap};
+use std::ops::AddAssign;
+use crate::{GdsBoundary, GdsBox, GdsDbCoord, GdsLayerKey, GdsLibrary, GdsPath, GdsStructure};
+use super::{GdsOpError, GdsOpResult};
+
+/// Element counts and area of one (layer, datatype) pair
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GdsLayerStats {
+    pub boundaries: usize,
+    pub paths: usize,
+    pub texts: usize,
+    pub boxes: usize,
+    pub nodes: usize,
+    /// Area of boundaries, paths and boxes in square user units, overlaps are counted twice
+    pub area: f64,
+}
+
+impl GdsLayerStats {
+    pub fn elements(&self) -> usize {
+        self.boundaries + self.paths + self.texts + self.boxes + self.nodes
+    }
+
+    /// Same statistics for `factor` copies
+    pub fn scaled(&self, factor: usize) -> Self {
+        Self {
+            boundaries: self.boundaries * factor,
+            paths: self.paths * factor,
+            texts: self.texts * factor,
+            boxes: self.boxes * factor,
+            nodes: self.nodes * factor,
+            area: self.area * factor as f64,
+        }
+    }
+}
+
+impl AddAssign for GdsLayerStats {
+    fn add_assign(&mut self, rhs: Self) {
+        self.boundaries += rhs.boundaries;
+        self.paths += rhs.paths;
+        self.texts += rhs.texts;
+        self.boxes += rhs.boxes;
+        self.nodes += rhs.nodes;
+        self.area += rhs.area;
+    }
+}
+
+/// Statistics of every (layer, datatype) pair in use, sorted
+pub type GdsLayerReport = BTreeMap<GdsLayerKey, GdsLayerStats>;
+
+fn merge_report(report: &mut GdsLayerReport, other: &GdsLayerReport, factor: usize) {
+    for (key, stats) in other {
+        *report.entry(*key).or_default() += stats.scaled(factor);
+    }
+}
+
+/// Area enclosed by a closed or open point list, in square database units
+pub fn polygon_area(points: &[GdsDbCoord]) -> f64 {
+    let n = points.len();
+    if n < 3 {
+        return 0.0;
+    }
+    let twice: i128 = (0..n)
+        .map(|i| {
+            let (p, q) = (points[i], points[(i + 1) % n]);
+            p.x as i128 * q.y as i128 - q.x as i128 * p.y as i128
+        })
+        .sum();
+    twice.abs() as f64 / 2.0
+}
+
+impl GdsBoundary {
+    /// Area in square database units
+    pub fn area(&self) -> f64 {
+        polygon_area(&self.xy)
+    }
+}
+
+impl GdsBox {
+    /// Area in square database units
+    pub fn area(&self) -> f64 {
+        polygon_area(&self.xy)
+    }
+}
+
+impl GdsPath {
+    /// Area of the outline in square database units
+    pub fn area(&self) -> f64 {
+        self.to_polygon().map(|polygon| polygon.area()).unwrap_or(0.0)
+    }
+}
+
+impl GdsStructure {
+    /// Statistics of the elements of this structure alone, references left out.
+    /// Areas are converted with `usrunits_per_dbunit`.
+    pub fn layer_report(&self, usrunits_per_dbunit: f64) -> GdsLayerReport {
+        let scale = usrunits_per_dbunit * usrunits_per_dbunit;
+        let mut report = GdsLayerReport::new();
+
+        for e in &self.boundarys {
+            let stats = report.entry(e.layer_key()).or_default();
+            stats.boundaries += 1;
+            stats.area += e.area() * scale;
+        }
+        for e in &self.paths {
+            let stats = report.entry(e.layer_key()).or_default();
+            stats.paths += 1;
+            stats.area += e.area() * scale;
+        }
+        for e in &self.boxes {
+            let stats = report.entry(e.layer_key()).or_default();
+            stats.boxes += 1;
+            stats.area += e.area() * scale;
+        }
+        for e in &self.texts {
+            report.entry(e.layer_key()).or_default().texts += 1;
+        }
+        for e in &self.nodes {
+            report.entry(e.layer_key()).or_default().nodes += 1;
+        }
+
+        report
+    }
+}
+
+impl GdsLibrary {
+    /// Statistics of all structures, each counted once whatever its number of instances
+    pub fn layer_report(&self) -> GdsLayerReport {
+        let mut report = GdsLayerReport::new();
+        for structure in self.structures.values() {
+            let local = structure.read().unwrap().layer_report(self.usrunits_per_dbunit);
+            merge_report(&mut report, &local, 1);
+        }
+        report
+    }
+
+    /// Statistics of the structure named `name` with its hierarchy expanded: the elements
+    /// of every structure are counted once per instance, and their area is scaled by the
+    /// square of the magnification of each instance.
+    pub fn hierarchical_layer_report(&self, name: &str) -> GdsOpResult<GdsLayerReport> {
+        if !self.structures.contains_key(name) {
+            return Err(GdsOpError::StructureNotFound(name.to_string()));
+        }
+
+        let order = self.hierarchy().topological_order_from(&[name])?;
+
+        // Top-down, so the instances of a structure are complete before its children use them.
+        // Each structure gets its instance count and the sum of their area scales.
+        let mut instances: HashMap<String, (usize, f64)> = HashMap::from([(name.to_string(), (1, 1.0))]);
+        for parent in order.iter().rev() {
+            let (count, scale) = instances.get(parent).copied().unwrap_or_default();
+            let Some(structure) = self.structures.get(parent) else { continue };
+            let structure = structure.read().unwrap();
+            let references = structure.srefs.iter()
+                .map(|r| (&r.s_name, 1, r.transform.as_ref()))
+                .chain(structure.arefs.iter()
+                    .map(|r| (&r.s_name, r.col.max(0) as usize * r.row.max(0) as usize, r.transform.as_ref())));
+            for (child, placed, transform) in references {
+                let (magnification, absolute) = transform
+                    .map_or((1.0, false), |t| (t.magnification(), t.flag.absolute_magnification));
+                // An absolute magnification ignores the scale of the instances above
+                let parent_scale = if absolute { count as f64 } else { scale };
+                let entry = instances.entry(child.clone()).or_default();
+                entry.0 += count * placed;
+                entry.1 += parent_scale * placed as f64 * magnification * magnification;
+            }
+        }
+
+        let mut report = GdsLayerReport::new();
+        for (structure, (count, scale)) in instances {
+            // Dangling references have no structure to count
+            let Some(structure) = self.structures.get(&structure) else { continue };
+            let local = structure.read().unwrap().layer_report(self.usrunits_per_dbunit);
+            for (key, stats) in &local {
+                let scaled = GdsLayerStats { area: stats.area * scale, ..stats.scaled(count) };
+                *report.entry(*key).or_default() += scaled;
+            }
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, RwLock};
+    use super::super::test_utils::*;
+    use super::*;
+
+    #[test]
+    fn test_flat_report() {
+        let lib = hierarchy();
+        let report = lib.layer_report();
+        let stats = report[&GdsLayerKey::new(1, 0)];
+        assert_eq!(stats.boundaries, 1);
+        assert!((stats.area - 100.0 * 1e-6).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_hierarchical_report() {
+        let lib = hierarchy();
+        let report = lib.hierarchical_layer_report("top").unwrap();
+        let stats = report[&GdsLayerKey::new(1, 0)];
+        assert_eq!(stats.boundaries, 5);
+        assert_eq!(stats.elements(), 5);
+        assert!((stats.area - 500.0 * 1e-6).abs() < 1e-12);
+        assert!(lib.hierarchical_layer_report("nope").is_err());
+    }
+
+    #[test]
+    fn test_hierarchical_report_magnification() {
+        let mut top = GdsStructure::new("top");
+        top.srefs.push(sref("leaf", 0, 0, Some(transform(false, 2.0, 0.0))));
+        top.srefs.push(sref("leaf", 100, 0, None));
+        let mut leaf = GdsStructure::new("leaf");
+        leaf.boundarys.push(rect(1, 0, 0, 10, 10));
+        let lib = library(vec![top, leaf]);
+
+        let stats = lib.hierarchical_layer_report("top").unwrap()[&GdsLayerKey::new(1, 0)];
+        assert_eq!(stats.boundaries, 2);
+        assert!((stats.area - 500.0 * 1e-6).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_hierarchical_report_ignores_unrelated_cycle() {
+        let mut lib = hierarchy();
+        let mut looped = GdsStructure::new("looped");
+        looped.srefs.push(sref("looped", 0, 0, None));
+        looped.srefs.push(sref("missing", 0, 0, None));
+        lib.structures.insert("looped".to_string(), Arc::new(RwLock::new(looped)));
+
+        let report = lib.hierarchical_layer_report("top").unwrap();
+        assert_eq!(report[&GdsLayerKey::new(1, 0)].boundaries, 5);
+        assert!(matches!(lib.hierarchical_layer_report("looped"), Err(GdsOpError::ReferenceCycle(_))));
+    }
+}
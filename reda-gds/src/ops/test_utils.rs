@@ -0,0 +1,77 @@
+use std::sync::{Arc, RwLock};
+use crate::*;
+
+pub fn rect(layer: i16, x0: i32, y0: i32, x1: i32, y1: i32) -> GdsBoundary {
+    GdsBoundaryBuilder::default()
+        .layer(layer)
+        .xy(vec![
+            GdsDbCoord::new(x0, y0), 
+            GdsDbCoord::new(x1, y0), 
+            GdsDbCoord::new(x1, y1), 
+            GdsDbCoord::new(x0, y1), 
+            GdsDbCoord::new(x0, y0),
+        ])
+        .build().unwrap()
+}
+
+pub fn sref(name: &str, x: i32, y: i32, transform: Option<GdsTransform>) -> GdsSref {
+    let mut sref = GdsSrefBuilder::default()
+        .s_name(name.to_string())
+        .xy(vec![GdsDbCoord::new(x, y)])
+        .build().unwrap();
+    sref.transform = transform;
+    sref
+}
+
+pub fn aref(name: &str, col: i16, row: i16, origin: (i32, i32), pitch: (i32, i32)) -> GdsAref {
+    let (x, y) = origin;
+    GdsArefBuilder::default()
+        .s_name(name.to_string())
+        .col(col)
+        .row(row)
+        .xy(vec![
+            GdsDbCoord::new(x, y),
+            GdsDbCoord::new(x + pitch.0 * col as i32, y),
+            GdsDbCoord::new(x, y + pitch.1 * row as i32),
+        ])
+        .build().unwrap()
+}
+
+pub fn transform(reflect: bool, magnification: f64, angle: f64) -> GdsTransform {
+    let mut t = GdsTransform::with_flag(0);
+    t.flag.reflect = reflect;
+    t.magnification = Some(magnification);
+    t.angle = Some(angle);
+    t
+}
+
+pub fn library(structures: Vec<GdsStructure>) -> GdsLibrary {
+    GdsLibraryBuilder::default()
+        .version(600)
+        .create_date(GdsDateTime::now())
+        .modify_date(GdsDateTime::now())
+        .name("lib".to_string())
+        .usrunits_per_dbunit(1e-3)
+        .meters_per_dbunit(1e-9)
+        .structures(structures.into_iter()
+            .map(|s| (s.name.clone(), Arc::new(RwLock::new(s))))
+            .collect())
+        .build().unwrap()
+}
+
+/// top: `mid` at (100, 0)
+/// mid: 2 x 2 array of `leaf` with a 20 pitch, and `leaf` at (0, 100) reflected and rotated by 90
+/// leaf: a 10 x 10 square on layer 1
+pub fn hierarchy() -> GdsLibrary {
+    let mut leaf = GdsStructure::new("leaf");
+    leaf.boundarys.push(rect(1, 0, 0, 10, 10));
+
+    let mut mid = GdsStructure::new("mid");
+    mid.arefs.push(aref("leaf", 2, 2, (0, 0), (20, 20)));
+    mid.srefs.push(sref("leaf", 0, 100, Some(transform(true, 1.0, 90.0))));
+
+    let mut top = GdsStructure::new("top");
+    top.srefs.push(sref("mid", 100, 0, None));
+
+    library(vec![top, mid, leaf])
+}
@@ -0,0 +1,260 @@
+use std::sync::{Arc, RwLock};
+use crate::{GdsAref, GdsDbCoord, GdsLibrary, GdsStructure, round_to_db};
+use super::{GdsOpError, GdsOpResult};
+
+/// Largest distance to the grid, in database units, still considered on the grid
+const GRID_TOLERANCE: f64 = 1e-6;
+
+/// A value moved to the grid by `GdsLibrary::rescale` or `GdsLibrary::snap_to_grid`
+#[derive(Debug, Clone, PartialEq)]
+pub struct GdsOffGrid {
+    pub structure: String,
+    /// Kind of the element, "boundary", "path", "sref", ...
+    pub element: &'static str,
+    /// Index of the element in the list of its kind
+    pub index: usize,
+    pub value: GdsOffGridValue,
+}
+
+/// The exact value, in the new database unit, and the value written instead
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GdsOffGridValue {
+    Coord { exact: (f64, f64), rounded: GdsDbCoord },
+    /// Width of a path or a text
+    Width { exact: f64, rounded: i32 },
+    /// Column or row displacement of an AREF
+    Pitch { exact: (f64, f64), rounded: GdsDbCoord },
+}
+
+impl GdsLibrary {
+    /// Change the database unit to `meters_per_dbunit`, keeping the user unit.
+    ///
+    /// Every coordinate, width and AREF lattice vector is rescaled, values that do not land
+    /// on the new grid are rounded and reported. Fails if either unit is not finite and positive.
+    pub fn rescale(&mut self, meters_per_dbunit: f64) -> GdsOpResult<Vec<GdsOffGrid>> {
+        for unit in [meters_per_dbunit, self.meters_per_dbunit] {
+            if !unit.is_finite() || unit <= 0.0 {
+                return Err(GdsOpError::InvalidUnit(unit));
+            }
+        }
+        let factor = self.meters_per_dbunit / meters_per_dbunit;
+        self.usrunits_per_dbunit *= meters_per_dbunit / self.meters_per_dbunit;
+        self.meters_per_dbunit = meters_per_dbunit;
+        Ok(self.remap(&|value| value as f64 * factor, &round_to_db))
+    }
+
+    /// Move every coordinate, width and AREF lattice vector to the nearest multiple of
+    /// `grid` database units, reporting the values that moved. Fails if `grid` is not positive.
+    pub fn snap_to_grid(&mut self, grid: i32) -> GdsOpResult<Vec<GdsOffGrid>> {
+        if grid <= 0 {
+            return Err(GdsOpError::InvalidGrid(grid));
+        }
+        let grid = grid as f64;
+        Ok(self.remap(&|value| value as f64, &|value| round_to_db((value / grid).round() * grid)))
+    }
+
+    fn remap(&mut self, exact: &dyn Fn(i32) -> f64, round: &dyn Fn(f64) -> i32) -> Vec<GdsOffGrid> {
+        let mut names: Vec<String> = self.structures.keys().cloned().collect();
+        names.sort();
+        let mut off_grid = Vec::new();
+        for name in names {
+            // Structures may be shared with clones of the library, they are replaced by copies
+            let mut structure = self.structures[&name].read().unwrap().clone();
+            let mut remap = Remap { exact, round, structure: name.clone(), off_grid: Vec::new() };
+            remap.structure(&mut structure);
+            off_grid.extend(remap.off_grid);
+            self.structures.insert(name, Arc::new(RwLock::new(structure)));
+        }
+        off_grid
+    }
+}
+
+impl GdsStructure {
+    /// Copy with every coordinate and width multiplied by `factor`, rounded to the grid
+    pub(crate) fn scaled(&self, factor: f64) -> Self {
+        let mut scaled = self.clone();
+        let exact = |value: i32| value as f64 * factor;
+        let mut remap = Remap { exact: &exact, round: &round_to_db, structure: String::new(), off_grid: Vec::new() };
+        remap.structure(&mut scaled);
+        scaled
+    }
+}
+
+/// Maps every value of a structure: `exact` gives the value in the new grid, `round` the
+/// grid value to use
+struct Remap<'a> {
+    exact: &'a dyn Fn(i32) -> f64,
+    round: &'a dyn Fn(f64) -> i32,
+    structure: String,
+    off_grid: Vec<GdsOffGrid>,
+}
+
+impl Remap<'_> {
+    fn structure(&mut self, structure: &mut GdsStructure) {
+        for (i, e) in structure.boundarys.iter_mut().enumerate() {
+            self.coords("boundary", i, &mut e.xy);
+        }
+        for (i, e) in structure.paths.iter_mut().enumerate() {
+            self.coords("path", i, &mut e.xy);
+            self.width("path", i, &mut e.width);
+        }
+        for (i, e) in structure.srefs.iter_mut().enumerate() {
+            self.coords("sref", i, &mut e.xy);
+        }
+        for (i, e) in structure.arefs.iter_mut().enumerate() {
+            self.aref(i, e);
+        }
+        for (i, e) in structure.texts.iter_mut().enumerate() {
+            self.coords("text", i, &mut e.xy);
+            self.width("text", i, &mut e.width);
+        }
+        for (i, e) in structure.nodes.iter_mut().enumerate() {
+            self.coords("node", i, &mut e.xy);
+        }
+        for (i, e) in structure.boxes.iter_mut().enumerate() {
+            self.coords("box", i, &mut e.xy);
+        }
+    }
+
+    fn report(&mut self, element: &'static str, index: usize, value: GdsOffGridValue) {
+        self.off_grid.push(GdsOffGrid { structure: self.structure.clone(), element, index, value });
+    }
+
+    fn point(&mut self, element: &'static str, index: usize, p: GdsDbCoord) -> GdsDbCoord {
+        let exact = ((self.exact)(p.x), (self.exact)(p.y));
+        let rounded = GdsDbCoord::new((self.round)(exact.0), (self.round)(exact.1));
+        if off_grid(exact.0, rounded.x) || off_grid(exact.1, rounded.y) {
+            self.report(element, index, GdsOffGridValue::Coord { exact, rounded });
+        }
+        rounded
+    }
+
+    fn coords(&mut self, element: &'static str, index: usize, xy: &mut [GdsDbCoord]) {
+        for p in xy.iter_mut() {
+            *p = self.point(element, index, *p);
+        }
+    }
+
+    fn width(&mut self, element: &'static str, index: usize, width: &mut Option<i32>) {
+        if let Some(width) = width.as_mut() {
+            let exact = (self.exact)(*width);
+            let rounded = (self.round)(exact);
+            if off_grid(exact, rounded) {
+                self.report(element, index, GdsOffGridValue::Width { exact, rounded });
+            }
+            *width = rounded;
+        }
+    }
+
+    /// The lattice vectors are mapped on their own so that every instance lands on the grid
+    fn aref(&mut self, index: usize, aref: &mut GdsAref) {
+        if aref.xy.len() < 3 {
+            self.coords("aref", index, &mut aref.xy);
+            return;
+        }
+        let origin = self.point("aref", index, aref.xy[0]);
+        let mut xy = vec![origin];
+        for (corner, count) in [(aref.xy[1], aref.col), (aref.xy[2], aref.row)] {
+            let count = count.max(1) as f64;
+            let exact = (
+                ((self.exact)(corner.x) - (self.exact)(aref.xy[0].x)) / count,
+                ((self.exact)(corner.y) - (self.exact)(aref.xy[0].y)) / count,
+            );
+            let rounded = GdsDbCoord::new((self.round)(exact.0), (self.round)(exact.1));
+            if off_grid(exact.0, rounded.x) || off_grid(exact.1, rounded.y) {
+                self.report("aref", index, GdsOffGridValue::Pitch { exact, rounded });
+            }
+            xy.push(GdsDbCoord::new(
+                origin.x + round_to_db(rounded.x as f64 * count),
+                origin.y + round_to_db(rounded.y as f64 * count),
+            ));
+        }
+        aref.xy = xy;
+    }
+}
+
+fn off_grid(exact: f64, rounded: i32) -> bool {
+    (exact - rounded as f64).abs() > GRID_TOLERANCE
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{GdsPathBuilder, GdsRect};
+    use super::*;
+    use super::super::test_utils::{aref, library, rect};
+
+    fn cell() -> GdsStructure {
+        let mut structure = GdsStructure::new("cell");
+        structure.boundarys.push(rect(1, 0, 0, 10, 5));
+        structure.paths.push(GdsPathBuilder::default()
+            .layer(2).data_type(0).width(6)
+            .xy(vec![GdsDbCoord::new(0, 0), GdsDbCoord::new(20, 0)])
+            .build().unwrap());
+        structure.arefs.push(aref("leaf", 2, 1, (0, 0), (15, 20)));
+        structure
+    }
+
+    #[test]
+    fn test_rescale() {
+        let mut finer = library(vec![cell()]);
+        assert!(finer.rescale(0.25e-9).unwrap().is_empty());
+        assert_eq!(finer.meters_per_dbunit, 0.25e-9);
+        assert!((finer.usrunits_per_dbunit - 0.25e-3).abs() < 1e-15);
+
+        let structure = finer.structures["cell"].read().unwrap();
+        assert_eq!(structure.boundarys[0].bbox(), Some(GdsRect::new(0, 0, 40, 20)));
+        assert_eq!(structure.paths[0].width, Some(24));
+        assert_eq!(structure.arefs[0].xy[1], GdsDbCoord::new(120, 0));
+        drop(structure);
+
+        let mut coarser = library(vec![cell()]);
+        let off_grid = coarser.rescale(10e-9).unwrap();
+        assert_eq!(off_grid.len(), 4);
+        assert_eq!(off_grid[0], GdsOffGrid {
+            structure: "cell".to_string(),
+            element: "boundary",
+            index: 0,
+            value: GdsOffGridValue::Coord { exact: (1.0, 0.5), rounded: GdsDbCoord::new(1, 1) },
+        });
+        assert!(matches!(off_grid[2].value, GdsOffGridValue::Width { rounded: 1, .. }));
+        assert!(matches!(off_grid[3].value, GdsOffGridValue::Pitch { exact: (1.5, 0.0), .. }));
+        let structure = coarser.structures["cell"].read().unwrap();
+        assert_eq!(structure.arefs[0].xy[1], GdsDbCoord::new(4, 0));
+    }
+
+    #[test]
+    fn test_snap_to_grid() {
+        let mut library = library(vec![cell()]);
+        let off_grid = library.snap_to_grid(5).unwrap();
+        assert_eq!(off_grid.len(), 1);
+        assert_eq!(off_grid[0].element, "path");
+        assert!(matches!(off_grid[0].value, GdsOffGridValue::Width { exact: 6.0, rounded: 5 }));
+        assert!(library.snap_to_grid(5).unwrap().is_empty());
+
+        let off_grid = library.snap_to_grid(10).unwrap();
+        assert_eq!(off_grid.len(), 4);
+        assert!(matches!(off_grid[3].value, GdsOffGridValue::Pitch { exact: (15.0, 0.0), rounded: GdsDbCoord { x: 20, y: 0 } }));
+
+        assert!(matches!(library.snap_to_grid(0), Err(GdsOpError::InvalidGrid(0))));
+        assert!(matches!(library.snap_to_grid(-5), Err(GdsOpError::InvalidGrid(-5))));
+    }
+
+    #[test]
+    fn test_rescale_invalid_unit() {
+        let mut library = library(vec![cell()]);
+        for unit in [0.0, -1e-9, f64::NAN, f64::INFINITY] {
+            assert!(matches!(library.rescale(unit), Err(GdsOpError::InvalidUnit(_))));
+        }
+        assert_eq!(library.meters_per_dbunit, 1e-9);
+        assert_eq!(library.structures["cell"].read().unwrap().boundarys[0].bbox(), Some(GdsRect::new(0, 0, 10, 5)));
+    }
+
+    #[test]
+    fn test_rescale_leaves_clones_intact() {
+        let original = library(vec![cell()]);
+        let mut finer = original.clone();
+        finer.rescale(0.5e-9).unwrap();
+        assert_eq!(original.structures["cell"].read().unwrap().boundarys[0].bbox(), Some(GdsRect::new(0, 0, 10, 5)));
+        assert_eq!(finer.structures["cell"].read().unwrap().boundarys[0].bbox(), Some(GdsRect::new(0, 0, 20, 10)));
+    }
+}
@@ -0,0 +1,255 @@
+use std::fmt;
+use crate::{GdsDbCoord, GdsLibrary, GdsStructure};
+
+/// Most points of an XY record
+pub const MAX_XY_POINTS: usize = 8191;
+/// Most bytes of a TEXT string
+pub const MAX_TEXT_LENGTH: usize = 512;
+/// Most characters of a structure name
+pub const MAX_NAME_LENGTH: usize = 32;
+
+/// A construct other GDSII tools may reject, found by `GdsLibrary::validate`
+#[derive(Debug, Clone, PartialEq)]
+pub struct GdsDiagnostic {
+    pub structure: String,
+    /// Kind ("boundary", "path", "sref", ...) and index of the element in the list of its
+    /// kind, `None` for the structure itself
+    pub element: Option<(&'static str, usize)>,
+    pub issue: GdsIssue,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GdsIssue {
+    /// The last point of the boundary differs from the first one
+    UnclosedBoundary,
+    /// Fewer points than a closed triangle
+    TooFewPoints(usize),
+    /// More points than an XY record holds
+    TooManyPoints(usize),
+    /// Two edges of the boundary cross
+    SelfIntersecting,
+    ZeroWidthPath,
+    /// Fewer than two distinct points
+    DegeneratePath,
+    /// Column or row count below one
+    EmptyArray { col: i16, row: i16 },
+    /// Length of the string in bytes
+    TextTooLong(usize),
+    /// A character outside `A-Z a-z 0-9 _ ? $`
+    IllegalName,
+    NameTooLong,
+    /// The referenced structure is not in the library
+    MissingStructure(String),
+}
+
+impl fmt::Display for GdsIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnclosedBoundary => write!(f, "boundary is not closed"),
+            Self::TooFewPoints(n) => write!(f, "{} points, at least 4 expected", n),
+            Self::TooManyPoints(n) => write!(f, "{} points, at most {} allowed", n, MAX_XY_POINTS),
+            Self::SelfIntersecting => write!(f, "polygon intersects itself"),
+            Self::ZeroWidthPath => write!(f, "path has no width"),
+            Self::DegeneratePath => write!(f, "path has fewer than 2 distinct points"),
+            Self::EmptyArray { col, row } => write!(f, "array of {} x {} instances", col, row),
+            Self::TextTooLong(n) => write!(f, "text of {} bytes, at most {} allowed", n, MAX_TEXT_LENGTH),
+            Self::IllegalName => write!(f, "name has illegal characters"),
+            Self::NameTooLong => write!(f, "name is longer than {} characters", MAX_NAME_LENGTH),
+            Self::MissingStructure(name) => write!(f, "reference to missing structure '{}'", name),
+        }
+    }
+}
+
+impl fmt::Display for GdsDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.element {
+            Some((kind, index)) => write!(f, "{} {} #{}: {}", self.structure, kind, index, self.issue),
+            None => write!(f, "{}: {}", self.structure, self.issue),
+        }
+    }
+}
+
+impl GdsLibrary {
+    /// Every construct of the library other tools may reject, structures in name order
+    pub fn validate(&self) -> Vec<GdsDiagnostic> {
+        let mut names: Vec<&String> = self.structures.keys().collect();
+        names.sort();
+        let mut diagnostics = Vec::new();
+        for name in names {
+            let structure = self.structures[name].read().unwrap();
+            let mut check = Check { library: self, structure: name, diagnostics: &mut diagnostics };
+            check.structure(&structure);
+        }
+        diagnostics
+    }
+}
+
+struct Check<'a> {
+    library: &'a GdsLibrary,
+    structure: &'a str,
+    diagnostics: &'a mut Vec<GdsDiagnostic>,
+}
+
+impl Check<'_> {
+    fn report(&mut self, element: Option<(&'static str, usize)>, issue: GdsIssue) {
+        self.diagnostics.push(GdsDiagnostic { structure: self.structure.to_string(), element, issue });
+    }
+
+    fn structure(&mut self, structure: &GdsStructure) {
+        if !structure.name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '?' | '$')) {
+            self.report(None, GdsIssue::IllegalName);
+        }
+        if structure.name.chars().count() > MAX_NAME_LENGTH {
+            self.report(None, GdsIssue::NameTooLong);
+        }
+
+        for (i, e) in structure.boundarys.iter().enumerate() {
+            let element = Some(("boundary", i));
+            if e.xy.len() < 4 {
+                self.report(element, GdsIssue::TooFewPoints(e.xy.len()));
+            }
+            if e.xy.len() > MAX_XY_POINTS {
+                self.report(element, GdsIssue::TooManyPoints(e.xy.len()));
+            }
+            if e.xy.first() != e.xy.last() {
+                self.report(element, GdsIssue::UnclosedBoundary);
+            }
+            if self_intersecting(&e.xy) {
+                self.report(element, GdsIssue::SelfIntersecting);
+            }
+        }
+
+        for (i, e) in structure.paths.iter().enumerate() {
+            let element = Some(("path", i));
+            if e.width.unwrap_or(0) == 0 {
+                self.report(element, GdsIssue::ZeroWidthPath);
+            }
+            if e.xy.iter().all(|p| *p == e.xy[0]) {
+                self.report(element, GdsIssue::DegeneratePath);
+            }
+            if e.xy.len() > MAX_XY_POINTS {
+                self.report(element, GdsIssue::TooManyPoints(e.xy.len()));
+            }
+        }
+
+        for (i, e) in structure.srefs.iter().enumerate() {
+            self.reference(("sref", i), &e.s_name);
+        }
+
+        for (i, e) in structure.arefs.iter().enumerate() {
+            if e.col < 1 || e.row < 1 {
+                self.report(Some(("aref", i)), GdsIssue::EmptyArray { col: e.col, row: e.row });
+            }
+            self.reference(("aref", i), &e.s_name);
+        }
+
+        for (i, e) in structure.texts.iter().enumerate() {
+            if e.string.len() > MAX_TEXT_LENGTH {
+                self.report(Some(("text", i)), GdsIssue::TextTooLong(e.string.len()));
+            }
+        }
+    }
+
+    fn reference(&mut self, element: (&'static str, usize), name: &str) {
+        if !self.library.structures.contains_key(name) {
+            self.report(Some(element), GdsIssue::MissingStructure(name.to_string()));
+        }
+    }
+}
+
+/// Whether two edges of the ring cross at a point inside both of them.
+///
+/// Edges that only touch or overlap, like the two sides of a keyhole, are accepted.
+fn self_intersecting(xy: &[GdsDbCoord]) -> bool {
+    let mut ring = xy;
+    if ring.len() > 1 && ring.first() == ring.last() {
+        ring = &ring[..ring.len() - 1];
+    }
+    let n = ring.len();
+    if n < 4 {
+        return false;
+    }
+
+    let cross = |o: GdsDbCoord, a: GdsDbCoord, b: GdsDbCoord| {
+        let value = (a.x as i64 - o.x as i64) * (b.y as i64 - o.y as i64) - (a.y as i64 - o.y as i64) * (b.x as i64 - o.x as i64);
+        value.signum()
+    };
+    for i in 0..n {
+        let (a, b) = (ring[i], ring[(i + 1) % n]);
+        let (min_x, max_x) = (a.x.min(b.x), a.x.max(b.x));
+        let (min_y, max_y) = (a.y.min(b.y), a.y.max(b.y));
+        for j in i + 2..n {
+            if i == 0 && j == n - 1 {
+                continue;
+            }
+            let (c, d) = (ring[j], ring[(j + 1) % n]);
+            if c.x.max(d.x) < min_x || c.x.min(d.x) > max_x || c.y.max(d.y) < min_y || c.y.min(d.y) > max_y {
+                continue;
+            }
+            let (d1, d2) = (cross(a, b, c), cross(a, b, d));
+            let (d3, d4) = (cross(c, d, a), cross(c, d, b));
+            if d1 * d2 < 0 && d3 * d4 < 0 {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{GdsPathBuilder, GdsTextBuilder};
+    use super::*;
+    use super::super::test_utils::{aref, hierarchy, library, rect, sref};
+
+    #[test]
+    fn test_valid_library() {
+        assert!(hierarchy().validate().is_empty());
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let mut cell = GdsStructure::new("bad-name");
+        let mut open = rect(1, 0, 0, 10, 10);
+        open.xy.pop();
+        cell.boundarys.push(open);
+        let mut bowtie = rect(1, 0, 0, 10, 10);
+        bowtie.xy.swap(1, 2);
+        cell.boundarys.push(bowtie);
+        cell.paths.push(GdsPathBuilder::default()
+            .layer(1).data_type(0)
+            .xy(vec![GdsDbCoord::new(5, 5), GdsDbCoord::new(5, 5)])
+            .build().unwrap());
+        cell.srefs.push(sref("missing", 0, 0, None));
+        cell.arefs.push(aref("bad-name", 0, 2, (0, 0), (10, 10)));
+        cell.texts.push(GdsTextBuilder::default()
+            .layer(1).text_type(0).string("x".repeat(600))
+            .xy(vec![GdsDbCoord::new(0, 0)])
+            .build().unwrap());
+
+        let diagnostics = library(vec![cell]).validate();
+        let issues: Vec<(Option<(&str, usize)>, GdsIssue)> = diagnostics.into_iter()
+            .map(|d| (d.element, d.issue))
+            .collect();
+        assert_eq!(issues, vec![
+            (None, GdsIssue::IllegalName),
+            (Some(("boundary", 0)), GdsIssue::UnclosedBoundary),
+            (Some(("boundary", 1)), GdsIssue::SelfIntersecting),
+            (Some(("path", 0)), GdsIssue::ZeroWidthPath),
+            (Some(("path", 0)), GdsIssue::DegeneratePath),
+            (Some(("sref", 0)), GdsIssue::MissingStructure("missing".to_string())),
+            (Some(("aref", 0)), GdsIssue::EmptyArray { col: 0, row: 2 }),
+            (Some(("text", 0)), GdsIssue::TextTooLong(600)),
+        ]);
+    }
+
+    #[test]
+    fn test_keyhole_is_not_self_intersecting() {
+        // Square with a square hole joined by a zero width bridge
+        let xy: Vec<GdsDbCoord> = [(0, 0), (10, 0), (10, 10), (0, 10), (0, 5), (3, 5), (3, 7), (7, 7), (7, 3), (3, 3), (3, 5), (0, 5), (0, 0)]
+            .into_iter()
+            .map(|(x, y)| GdsDbCoord::new(x, y))
+            .collect();
+        assert!(!self_intersecting(&xy));
+    }
+}
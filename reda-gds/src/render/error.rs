@@ -0,0 +1,21 @@
+use crate::GdsOpError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum GdsRenderError {
+    #[error("{0}")]
+    Op(#[from] GdsOpError),
+
+    #[error("Structure '{0}' has nothing to draw")]
+    EmptyStructure(String),
+
+    #[error("fill background error: {0}")]
+    FillBackground(String),
+
+    #[error("draw {0} error: {1}")]
+    Draw(&'static str, String),
+
+    #[error("present error: {0}")]
+    Present(String),
+}
+
+pub type GdsRenderResult<T> = Result<T, GdsRenderError>;
@@ -0,0 +1,401 @@
+mod error;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use derive_builder::Builder;
+pub use error::*;
+
+use plotters::{
+    coord::Shift,
+    element::{PathElement, Polygon, Rectangle, Text},
+    prelude::{BitMapBackend, DrawingArea, DrawingBackend, IntoDrawingArea, SVGBackend},
+    style::{Color, IntoFont, Palette, Palette99, RGBColor, ShapeStyle, BLACK, WHITE},
+};
+
+use crate::{GdsBBoxCache, GdsBBoxOptions, GdsDbCoord, GdsLayerKey, GdsLibrary, GdsRect, GdsStructure};
+
+/// How the shapes of a layer are filled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GdsFill {
+    Solid,
+    /// Filled with a see-through colour
+    #[default]
+    Translucent,
+    /// Diagonal lines
+    Hatched,
+    /// Outline only
+    Outline,
+}
+
+/// Drawing style of one (layer, datatype)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GdsLayerStyle {
+    pub color: RGBColor,
+    pub fill: GdsFill,
+    pub visible: bool,
+}
+
+impl GdsLayerStyle {
+    pub fn new(color: RGBColor, fill: GdsFill) -> Self {
+        Self { color, fill, visible: true }
+    }
+
+    pub fn hidden() -> Self {
+        Self { color: BLACK, fill: GdsFill::Outline, visible: false }
+    }
+}
+
+/// Draws a structure, flattened to a chosen depth, to SVG or PNG.
+///
+/// Layers without a style get a colour of a fixed palette, in the order of their keys.
+#[derive(Debug, Clone, Builder)]
+#[builder(setter(strip_option, into))]
+pub struct GdsRenderer {
+    #[builder(default = "1024")]
+    pub width: u32,
+
+    #[builder(default = "1024")]
+    pub height: u32,
+
+    /// Space left around the drawing, in pixels
+    #[builder(default = "16")]
+    pub margin: u32,
+
+    #[builder(default = "WHITE")]
+    pub background_color: RGBColor,
+
+    /// Number of SREF/AREF levels to expand, `None` expands all of them.
+    /// References past the limit are drawn as outlines
+    #[builder(default)]
+    pub depth: Option<usize>,
+
+    #[builder(default)]
+    pub styles: BTreeMap<GdsLayerKey, GdsLayerStyle>,
+
+    #[builder(default = "true")]
+    pub draw_texts: bool,
+
+    #[builder(default = "BLACK")]
+    pub instance_color: RGBColor,
+
+    /// Distance between hatch lines, in pixels
+    #[builder(default = "8.0")]
+    pub hatch_spacing: f64,
+
+    #[builder(default = "(\"sans-serif\", 12)")]
+    pub font: (&'static str, u32),
+}
+
+impl Default for GdsRenderer {
+    fn default() -> Self {
+        GdsRendererBuilder::default().build().unwrap()
+    }
+}
+
+impl GdsRenderer {
+    pub fn render_svg<P: AsRef<Path>>(&self, library: &GdsLibrary, cell_name: &str, path: P) -> GdsRenderResult<()> {
+        let area = SVGBackend::new(path.as_ref(), (self.width, self.height)).into_drawing_area();
+        self.render(&area, library, cell_name)?;
+        area.present().map_err(|e| GdsRenderError::Present(e.to_string()))
+    }
+
+    pub fn render_svg_string(&self, library: &GdsLibrary, cell_name: &str) -> GdsRenderResult<String> {
+        let mut svg = String::new();
+        {
+            let area = SVGBackend::with_string(&mut svg, (self.width, self.height)).into_drawing_area();
+            self.render(&area, library, cell_name)?;
+            area.present().map_err(|e| GdsRenderError::Present(e.to_string()))?;
+        }
+        Ok(svg)
+    }
+
+    pub fn render_png<P: AsRef<Path>>(&self, library: &GdsLibrary, cell_name: &str, path: P) -> GdsRenderResult<()> {
+        let area = BitMapBackend::new(path.as_ref(), (self.width, self.height)).into_drawing_area();
+        self.render(&area, library, cell_name)?;
+        area.present().map_err(|e| GdsRenderError::Present(e.to_string()))
+    }
+
+    pub fn render<DB: DrawingBackend>(
+        &self, 
+        area: &DrawingArea<DB, Shift>, 
+        library: &GdsLibrary, 
+        cell_name: &str
+    ) -> GdsRenderResult<()> {
+        let bbox = library.bbox(cell_name, &GdsBBoxOptions::default())?
+            .ok_or_else(|| GdsRenderError::EmptyStructure(cell_name.to_string()))?;
+        let flat = library.flatten(cell_name, self.depth)?;
+        let view = View::new(&bbox, self.width, self.height, self.margin);
+
+        area.fill(&self.background_color)
+            .map_err(|e| GdsRenderError::FillBackground(e.to_string()))?;
+
+        let styles = self.layer_styles(&flat);
+        let mut polygons: BTreeMap<GdsLayerKey, Vec<Vec<(i32, i32)>>> = BTreeMap::new();
+        let mut lines: BTreeMap<GdsLayerKey, Vec<Vec<(i32, i32)>>> = BTreeMap::new();
+        for e in &flat.boundarys {
+            polygons.entry(e.layer_key()).or_default().push(view.points(&e.xy));
+        }
+        for e in &flat.boxes {
+            polygons.entry(e.layer_key()).or_default().push(view.points(&e.xy));
+        }
+        for e in &flat.paths {
+            match e.to_polygon() {
+                Some(polygon) => polygons.entry(e.layer_key()).or_default().push(view.points(&polygon.xy)),
+                None => lines.entry(e.layer_key()).or_default().push(view.points(&e.xy)),
+            }
+        }
+
+        for (key, style) in &styles {
+            if !style.visible {
+                continue;
+            }
+            for points in polygons.get(key).into_iter().flatten() {
+                self.draw_polygon(area, points, style)?;
+            }
+            for points in lines.get(key).into_iter().flatten() {
+                draw(area, "path", &PathElement::new(points.clone(), style.color.stroke_width(1)))?;
+            }
+        }
+
+        self.draw_instances(area, library, &flat, &view)?;
+
+        if self.draw_texts {
+            for text in &flat.texts {
+                let style = styles.get(&text.layer_key()).copied().unwrap_or(GdsLayerStyle::hidden());
+                if let (true, Some(p)) = (style.visible, text.xy.first()) {
+                    let font = self.font.into_font().color(&style.color);
+                    draw(area, "text", &Text::new(text.string.clone(), view.point(p), font))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Style of every layer of the structure, configured or picked from the palette
+    fn layer_styles(&self, structure: &GdsStructure) -> BTreeMap<GdsLayerKey, GdsLayerStyle> {
+        let mut keys = BTreeSet::new();
+        keys.extend(structure.boundarys.iter().map(|e| e.layer_key()));
+        keys.extend(structure.boxes.iter().map(|e| e.layer_key()));
+        keys.extend(structure.paths.iter().map(|e| e.layer_key()));
+        keys.extend(structure.texts.iter().map(|e| e.layer_key()));
+
+        keys.into_iter()
+            .enumerate()
+            .map(|(i, key)| {
+                let style = self.styles.get(&key).copied().unwrap_or_else(|| {
+                    let (r, g, b) = Palette99::COLORS[i % Palette99::COLORS.len()];
+                    GdsLayerStyle::new(RGBColor(r, g, b), GdsFill::default())
+                });
+                (key, style)
+            })
+            .collect()
+    }
+
+    fn draw_polygon<DB: DrawingBackend>(
+        &self, 
+        area: &DrawingArea<DB, Shift>, 
+        points: &[(i32, i32)], 
+        style: &GdsLayerStyle
+    ) -> GdsRenderResult<()> {
+        let outline = style.color.stroke_width(1);
+        match style.fill {
+            GdsFill::Solid => draw(area, "polygon", &Polygon::new(points.to_vec(), style.color.filled()))?,
+            GdsFill::Translucent => {
+                let fill = ShapeStyle::from(style.color.mix(0.35)).filled();
+                draw(area, "polygon", &Polygon::new(points.to_vec(), fill))?;
+            }
+            GdsFill::Hatched => {
+                for (p, q) in hatch_lines(points, self.hatch_spacing) {
+                    draw(area, "hatch", &PathElement::new(vec![p, q], outline))?;
+                }
+            }
+            GdsFill::Outline => {}
+        }
+
+        let mut closed = points.to_vec();
+        if let Some(first) = points.first() {
+            closed.push(*first);
+        }
+        draw(area, "outline", &PathElement::new(closed, outline))
+    }
+
+    /// Outlines and names of the references left by the depth limit
+    fn draw_instances<DB: DrawingBackend>(
+        &self, 
+        area: &DrawingArea<DB, Shift>, 
+        library: &GdsLibrary, 
+        flat: &GdsStructure, 
+        view: &View
+    ) -> GdsRenderResult<()> {
+        let mut bboxes = GdsBBoxCache::new(library, GdsBBoxOptions::default());
+        let mut instances = Vec::new();
+        for sref in &flat.srefs {
+            if let Some(child) = bboxes.bbox(&sref.s_name)? {
+                instances.push((sref.s_name.clone(), sref.bbox(&child)));
+            }
+        }
+        for aref in &flat.arefs {
+            if let Some(bbox) = bboxes.bbox(&aref.s_name)?.and_then(|child| aref.bbox(&child)) {
+                instances.push((format!("{} [{}x{}]", aref.s_name, aref.col, aref.row), bbox));
+            }
+        }
+
+        let outline = self.instance_color.stroke_width(1);
+        for (name, bbox) in instances {
+            let (min, max) = (view.point(&bbox.min), view.point(&bbox.max));
+            draw(area, "instance", &Rectangle::new([min, max], outline))?;
+            if self.draw_texts {
+                let font = self.font.into_font().color(&self.instance_color);
+                draw(area, "instance name", &Text::new(name, (min.0 + 2, max.1 + 2), font))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn draw<DB, E>(area: &DrawingArea<DB, Shift>, what: &'static str, element: &E) -> GdsRenderResult<()> 
+where
+    DB: DrawingBackend,
+    for<'a> &'a E: plotters::element::PointCollection<'a, (i32, i32)>,
+    E: plotters::element::Drawable<DB>,
+{
+    area.draw(element).map_err(|e| GdsRenderError::Draw(what, e.to_string()))
+}
+
+/// Maps database coordinates to pixels, the y axis pointing down
+struct View {
+    min: (f64, f64),
+    scale: f64,
+    offset: (f64, f64),
+    height: f64,
+}
+
+impl View {
+    fn new(bbox: &GdsRect, width: u32, height: u32, margin: u32) -> Self {
+        let (w, h) = (width.saturating_sub(2 * margin).max(1) as f64, height.saturating_sub(2 * margin).max(1) as f64);
+        let (bw, bh) = (bbox.width().max(1) as f64, bbox.height().max(1) as f64);
+        let scale = (w / bw).min(h / bh);
+        let offset = (
+            margin as f64 + (w - bw * scale) / 2.0, 
+            margin as f64 + (h - bh * scale) / 2.0,
+        );
+        Self { min: (bbox.min.x as f64, bbox.min.y as f64), scale, offset, height: height as f64 }
+    }
+
+    fn point(&self, p: &GdsDbCoord) -> (i32, i32) {
+        let x = self.offset.0 + (p.x as f64 - self.min.0) * self.scale;
+        let y = self.height - self.offset.1 - (p.y as f64 - self.min.1) * self.scale;
+        (x.round() as i32, y.round() as i32)
+    }
+
+    fn points(&self, xy: &[GdsDbCoord]) -> Vec<(i32, i32)> {
+        xy.iter().map(|p| self.point(p)).collect()
+    }
+}
+
+/// Pieces of the diagonals `x + y = c` inside the polygon, with `c` a multiple of `spacing`
+fn hatch_lines(points: &[(i32, i32)], spacing: f64) -> Vec<((i32, i32), (i32, i32))> {
+    let spacing = spacing.max(1.0);
+    let n = points.len();
+    let diagonal = |p: (i32, i32)| (p.0 + p.1) as f64;
+    let (low, high) = points.iter()
+        .map(|&p| diagonal(p))
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), c| (low.min(c), high.max(c)));
+
+    let mut lines = Vec::new();
+    let mut c = (low / spacing).ceil() * spacing;
+    while c <= high {
+        let mut crossings: Vec<(f64, f64)> = (0..n)
+            .filter_map(|i| {
+                let (p, q) = (points[i], points[(i + 1) % n]);
+                let (cp, cq) = (diagonal(p), diagonal(q));
+                if (cp <= c && cq > c) || (cq <= c && cp > c) {
+                    let t = (c - cp) / (cq - cp);
+                    Some((p.0 as f64 + t * (q.0 - p.0) as f64, p.1 as f64 + t * (q.1 - p.1) as f64))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+        for pair in crossings.chunks_exact(2) {
+            let round = |(x, y): (f64, f64)| (x.round() as i32, y.round() as i32);
+            let (p, q) = (round(pair[0]), round(pair[1]));
+            if p != q {
+                lines.push((p, q));
+            }
+        }
+        c += spacing;
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{GdsLibraryBuilder, GdsStructure, GdsTextBuilder};
+    use super::*;
+
+    fn library() -> GdsLibrary {
+        let mut leaf = GdsStructure::new("leaf");
+        leaf.boundarys.push(crate::GdsBoundaryBuilder::default()
+            .layer(1)
+            .xy(GdsRect::new(0, 0, 10, 10).corners().to_vec())
+            .build().unwrap());
+        leaf.texts.push(GdsTextBuilder::default()
+            .layer(2).text_type(0).string("vdd".to_string())
+            .xy(vec![GdsDbCoord::new(5, 5)])
+            .build().unwrap());
+
+        let mut top = GdsStructure::new("top");
+        top.srefs.push(crate::GdsSrefBuilder::default()
+            .s_name("leaf".to_string())
+            .xy(vec![GdsDbCoord::new(20, 0)])
+            .build().unwrap());
+
+        GdsLibraryBuilder::default()
+            .version(600)
+            .create_date(crate::GdsDateTime::now())
+            .modify_date(crate::GdsDateTime::now())
+            .name("lib".to_string())
+            .usrunits_per_dbunit(1e-3)
+            .meters_per_dbunit(1e-9)
+            .structures([top, leaf].into_iter()
+                .map(|s| (s.name.clone(), std::sync::Arc::new(std::sync::RwLock::new(s))))
+                .collect())
+            .build().unwrap()
+    }
+
+    #[test]
+    fn test_render_svg() {
+        let library = library();
+        let svg = GdsRenderer::default().render_svg_string(&library, "top").unwrap();
+        assert!(svg.contains("<polygon"));
+        assert!(svg.contains("vdd"));
+
+        let renderer = GdsRendererBuilder::default()
+            .depth(0usize)
+            .draw_texts(false)
+            .build().unwrap();
+        let svg = renderer.render_svg_string(&library, "top").unwrap();
+        assert!(!svg.contains("<polygon"));
+        assert!(svg.contains("<rect"));
+
+        let mut styles = BTreeMap::new();
+        styles.insert(GdsLayerKey::new(1, 0), GdsLayerStyle::hidden());
+        let renderer = GdsRendererBuilder::default().styles(styles).build().unwrap();
+        let svg = renderer.render_svg_string(&library, "top").unwrap();
+        assert!(!svg.contains("<polygon"));
+        assert!(svg.contains("vdd"));
+
+        assert!(GdsRenderer::default().render_svg_string(&library, "none").is_err());
+    }
+
+    #[test]
+    fn test_hatch_lines() {
+        let square = [(0, 0), (10, 0), (10, 10), (0, 10)];
+        let lines = hatch_lines(&square, 5.0);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1], ((0, 10), (10, 0)));
+    }
+}